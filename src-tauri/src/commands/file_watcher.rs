@@ -1,7 +1,7 @@
 use notify_debouncer_full::{
     new_debouncer,
     notify::{EventKind, RecursiveMode},
-    DebounceEventResult, Debouncer, RecommendedCache,
+    DebounceEventResult, DebouncedEvent, Debouncer, RecommendedCache,
 };
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
@@ -195,7 +195,7 @@ fn normalize_path_str(path: &Path) -> String {
     }
 }
 
-fn is_ignored_path(path: &Path) -> bool {
+pub(crate) fn is_ignored_path(path: &Path) -> bool {
     for component in path.components() {
         if let std::path::Component::Normal(os_str) = component {
             if let Some(s) = os_str.to_str() {
@@ -221,10 +221,12 @@ fn is_ignored_path(path: &Path) -> bool {
     false
 }
 
+type WorkspaceDebouncer = Debouncer<notify_debouncer_full::notify::RecommendedWatcher, RecommendedCache>;
+
 /// Holds an active debounced workspace watcher.
 /// Wrapped in Mutex<Option<...>> so we can drop (stop) and replace it safely.
 pub struct WorkspaceWatcher {
-    inner: Mutex<Option<Debouncer<notify_debouncer_full::notify::RecommendedWatcher, RecommendedCache>>>,
+    inner: Mutex<Option<WorkspaceDebouncer>>,
 }
 
 impl WorkspaceWatcher {
@@ -235,6 +237,117 @@ impl WorkspaceWatcher {
     }
 }
 
+/// Translate a batch of raw notify events into workspace change events, dropping
+/// paths inside ignored directories.
+fn collect_changes(events: &[DebouncedEvent]) -> Vec<WorkspaceChangeEvent> {
+    let mut batch: Vec<WorkspaceChangeEvent> = Vec::new();
+
+    for event in events {
+        #[cfg(debug_assertions)]
+        println!("[WorkspaceWatcher] 🔍 Raw notify event: {:?}", event);
+
+        match &event.kind {
+            EventKind::Create(_) => {
+                for p in &event.paths {
+                    if !is_ignored_path(p) {
+                        batch.push(WorkspaceChangeEvent {
+                            kind: ChangeKind::Created,
+                            path: normalize_path_str(p),
+                            from_path: None,
+                        });
+                    }
+                }
+            }
+            EventKind::Modify(notify_debouncer_full::notify::event::ModifyKind::Name(
+                notify_debouncer_full::notify::event::RenameMode::Both,
+            )) => {
+                if event.paths.len() >= 2 {
+                    let from = &event.paths[0];
+                    let to = &event.paths[1];
+                    if !is_ignored_path(from) || !is_ignored_path(to) {
+                        batch.push(WorkspaceChangeEvent {
+                            kind: ChangeKind::Renamed,
+                            path: normalize_path_str(to),
+                            from_path: Some(normalize_path_str(from)),
+                        });
+                    }
+                } else {
+                    for p in &event.paths {
+                        if !is_ignored_path(p) {
+                            batch.push(WorkspaceChangeEvent {
+                                kind: ChangeKind::Modified,
+                                path: normalize_path_str(p),
+                                from_path: None,
+                            });
+                        }
+                    }
+                }
+            }
+            EventKind::Modify(_) => {
+                for p in &event.paths {
+                    if !is_ignored_path(p) {
+                        batch.push(WorkspaceChangeEvent {
+                            kind: ChangeKind::Modified,
+                            path: normalize_path_str(p),
+                            from_path: None,
+                        });
+                    }
+                }
+            }
+            EventKind::Remove(_) => {
+                for p in &event.paths {
+                    if !is_ignored_path(p) {
+                        batch.push(WorkspaceChangeEvent {
+                            kind: ChangeKind::Removed,
+                            path: normalize_path_str(p),
+                            from_path: None,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    batch
+}
+
+/// Watch `root` recursively and call `on_changes` with each non-empty debounced batch.
+/// Watching stops when the returned debouncer is dropped. Shared by the desktop app
+/// and the MCP server's `--watch` mode.
+pub(crate) fn watch_workspace_changes(
+    root: &Path,
+    mut on_changes: impl FnMut(Vec<WorkspaceChangeEvent>) + Send + 'static,
+) -> Result<WorkspaceDebouncer, String> {
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(500),
+        None,
+        move |result: DebounceEventResult| {
+            match result {
+                Ok(events) => {
+                    #[cfg(debug_assertions)]
+                    println!("[WorkspaceWatcher] 📬 Received raw notify event batch of size: {}", events.len());
+
+                    let batch = collect_changes(&events);
+                    if !batch.is_empty() {
+                        on_changes(batch);
+                    }
+                }
+                Err(errors) => {
+                    eprintln!("[WorkspaceWatcher] ❌ Watch error: {:?}", errors);
+                }
+            }
+        },
+    )
+    .map_err(|e| format!("Failed to create debouncer: {e}"))?;
+
+    debouncer
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to register watch: {e}"))?;
+
+    Ok(debouncer)
+}
+
 /// Start watching the workspace root recursively, skipping ignored directories.
 /// Any previously active workspace watcher is stopped first.
 #[tauri::command]
@@ -259,103 +372,14 @@ pub async fn start_watching_workspace(workspace_root: String, app: AppHandle) ->
 
         let app_clone = app.clone();
 
-        let debouncer_res = new_debouncer(
-            Duration::from_millis(500),
-            None,
-            move |result: DebounceEventResult| {
-                match result {
-                    Ok(events) => {
-                        #[cfg(debug_assertions)]
-                        println!("[WorkspaceWatcher] 📬 Received raw notify event batch of size: {}", events.len());
-
-                        let mut batch: Vec<WorkspaceChangeEvent> = Vec::new();
-
-                        for event in &events {
-                            #[cfg(debug_assertions)]
-                            println!("[WorkspaceWatcher] 🔍 Raw notify event: {:?}", event);
-
-                            match &event.kind {
-                                EventKind::Create(_) => {
-                                    for p in &event.paths {
-                                        if !is_ignored_path(p) {
-                                            batch.push(WorkspaceChangeEvent {
-                                                kind: ChangeKind::Created,
-                                                path: normalize_path_str(p),
-                                                from_path: None,
-                                            });
-                                        }
-                                    }
-                                }
-                                EventKind::Modify(notify_debouncer_full::notify::event::ModifyKind::Name(
-                                    notify_debouncer_full::notify::event::RenameMode::Both,
-                                )) => {
-                                    if event.paths.len() >= 2 {
-                                        let from = &event.paths[0];
-                                        let to = &event.paths[1];
-                                        if !is_ignored_path(from) || !is_ignored_path(to) {
-                                            batch.push(WorkspaceChangeEvent {
-                                                kind: ChangeKind::Renamed,
-                                                path: normalize_path_str(to),
-                                                from_path: Some(normalize_path_str(from)),
-                                            });
-                                        }
-                                    } else {
-                                        for p in &event.paths {
-                                            if !is_ignored_path(p) {
-                                                batch.push(WorkspaceChangeEvent {
-                                                    kind: ChangeKind::Modified,
-                                                    path: normalize_path_str(p),
-                                                    from_path: None,
-                                                });
-                                            }
-                                        }
-                                    }
-                                }
-                                EventKind::Modify(_) => {
-                                    for p in &event.paths {
-                                        if !is_ignored_path(p) {
-                                            batch.push(WorkspaceChangeEvent {
-                                                kind: ChangeKind::Modified,
-                                                path: normalize_path_str(p),
-                                                from_path: None,
-                                            });
-                                        }
-                                    }
-                                }
-                                EventKind::Remove(_) => {
-                                    for p in &event.paths {
-                                        if !is_ignored_path(p) {
-                                            batch.push(WorkspaceChangeEvent {
-                                                kind: ChangeKind::Removed,
-                                                path: normalize_path_str(p),
-                                                from_path: None,
-                                            });
-                                        }
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
-
-                        if !batch.is_empty() {
-                            #[cfg(debug_assertions)]
-                            println!("[WorkspaceWatcher] 📢 Emitting {} events to frontend: {:?}", batch.len(), batch);
-                            let _ = app_clone.emit("workspace-changed", batch);
-                        }
-                    }
-                    Err(errors) => {
-                        eprintln!("[WorkspaceWatcher] ❌ Watch error: {:?}", errors);
-                    }
-                }
-            },
-        );
+        let debouncer_res = watch_workspace_changes(&root_path, move |batch| {
+            #[cfg(debug_assertions)]
+            println!("[WorkspaceWatcher] 📢 Emitting {} events to frontend: {:?}", batch.len(), batch);
+            let _ = app_clone.emit("workspace-changed", batch);
+        });
 
         match debouncer_res {
-            Ok(mut debouncer) => {
-                if let Err(e) = debouncer.watch(&root_path, RecursiveMode::Recursive) {
-                    eprintln!("[WorkspaceWatcher] Failed to register watch: {}", e);
-                    return;
-                }
+            Ok(debouncer) => {
                 if let Ok(mut guard) = state.inner.lock() {
                     *guard = Some(debouncer);
                 }
@@ -363,7 +387,7 @@ pub async fn start_watching_workspace(workspace_root: String, app: AppHandle) ->
                 println!("[WorkspaceWatcher] Watch registered on: {}", workspace_root);
             }
             Err(e) => {
-                eprintln!("[WorkspaceWatcher] Failed to create debouncer: {}", e);
+                eprintln!("[WorkspaceWatcher] {}", e);
             }
         }
     });
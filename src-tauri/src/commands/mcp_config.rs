@@ -0,0 +1,490 @@
+// Registers the Depdok MCP server in AI clients' JSON config files. Each client keeps
+// its servers under its own key and entry shape; everything else in the file is
+// preserved, the previous file is kept as a timestamped backup, and the written file
+// is parsed back before it replaces the original.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+/// Name of the Depdok entry in every client's server list.
+pub const SERVER_NAME: &str = "depdok";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpConfigScope {
+    /// The client's config in the user's home directory, used in every project.
+    User,
+    /// A config file in the workspace root, such as `.mcp.json` or `.vscode/mcp.json`.
+    Workspace,
+}
+
+/// Where a client keeps its servers and how it expects an entry to look.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    /// `mcpServers.depdok = { command, args }` (Claude Desktop, Gemini CLI).
+    McpServers,
+    /// `mcpServers.depdok = { type: "stdio", command, args }` (Claude Code).
+    ClaudeCode,
+    /// `servers.depdok = { command, args }` (VS Code's `.vscode/mcp.json`).
+    Servers,
+    /// `mcpServers.depdok = { type: "local", command, args, tools }` (Copilot CLI).
+    CopilotCli,
+    /// `mcp.depdok = { type: "local", command: [..], enabled }` (OpenCode).
+    OpenCode,
+}
+
+impl Layout {
+    fn servers_key(self) -> &'static str {
+        match self {
+            Layout::McpServers | Layout::ClaudeCode | Layout::CopilotCli => "mcpServers",
+            Layout::Servers => "servers",
+            Layout::OpenCode => "mcp",
+        }
+    }
+
+    fn entry(self, server_path: &str) -> Value {
+        match self {
+            Layout::McpServers | Layout::Servers => json!({ "command": server_path, "args": [] }),
+            Layout::ClaudeCode => json!({ "type": "stdio", "command": server_path, "args": [] }),
+            Layout::CopilotCli => json!({ "type": "local", "command": server_path, "args": [], "tools": ["*"] }),
+            Layout::OpenCode => json!({ "type": "local", "command": [server_path], "enabled": true }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpConfigChange {
+    pub path: String,
+    /// Copy of the file as it was before the change; `None` when the file was created
+    /// or left untouched.
+    pub backup_path: Option<String>,
+    pub changed: bool,
+}
+
+/// The config file `agent_id` reads for `scope`, and the layout it expects.
+fn client_config(
+    app: &tauri::AppHandle,
+    agent_id: &str,
+    scope: McpConfigScope,
+    workspace_root: Option<&str>,
+) -> Result<(PathBuf, Layout), String> {
+    let home = || app.path().home_dir().map_err(|e| format!("Failed to resolve home directory: {e}"));
+    let workspace = || {
+        workspace_root
+            .map(PathBuf::from)
+            .ok_or_else(|| "Open a workspace to configure it".to_string())
+    };
+
+    match (agent_id, scope) {
+        ("claude", McpConfigScope::User) => {
+            let config_dir = app
+                .path()
+                .config_dir()
+                .map_err(|e| format!("Failed to resolve config directory: {e}"))?;
+            Ok((config_dir.join("Claude").join("claude_desktop_config.json"), Layout::McpServers))
+        }
+        ("claudecode", McpConfigScope::User) => Ok((home()?.join(".claude.json"), Layout::ClaudeCode)),
+        ("claudecode", McpConfigScope::Workspace) => Ok((workspace()?.join(".mcp.json"), Layout::ClaudeCode)),
+        ("copilot", McpConfigScope::User) => {
+            Ok((home()?.join(".copilot").join("mcp-config.json"), Layout::CopilotCli))
+        }
+        ("copilot", McpConfigScope::Workspace) => Ok((workspace()?.join(".vscode").join("mcp.json"), Layout::Servers)),
+        ("gemini", McpConfigScope::User) => Ok((home()?.join(".gemini").join("settings.json"), Layout::McpServers)),
+        ("gemini", McpConfigScope::Workspace) => {
+            Ok((workspace()?.join(".gemini").join("settings.json"), Layout::McpServers))
+        }
+        ("opencode", McpConfigScope::Workspace) => Ok((workspace()?.join("opencode.jsonc"), Layout::OpenCode)),
+        ("claude", McpConfigScope::Workspace) => Err("Claude Desktop has no workspace config".to_string()),
+        ("codex", _) => {
+            Err("Codex reads its MCP servers from ~/.codex/config.toml; add the server there by hand".to_string())
+        }
+        ("opencode", McpConfigScope::User) => Err("OpenCode is configured per workspace".to_string()),
+        _ => Err(format!("Unknown agent: {agent_id}")),
+    }
+}
+
+/// Remove `//` and `/* */` comments outside of strings, for `.jsonc` files.
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                while chars.next_if(|&next| next != '\n').is_some() {}
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = '\0';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Parse a config file's text. An empty file counts as an empty config.
+fn parse_config(text: &str) -> Result<Map<String, Value>, String> {
+    if text.trim().is_empty() {
+        return Ok(Map::new());
+    }
+    match serde_json::from_str(&strip_comments(text)) {
+        Ok(Value::Object(config)) => Ok(config),
+        Ok(_) => Err("expected a JSON object at the top level".to_string()),
+        Err(e) => Err(format!("invalid JSON: {e}")),
+    }
+}
+
+/// A config file as read from disk: its text, kept for the backup, and its parsed contents.
+struct ConfigFile {
+    text: String,
+    config: Map<String, Value>,
+}
+
+/// Read `path`; `None` when it does not exist yet.
+fn read_config(path: &Path) -> Result<Option<ConfigFile>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let config = parse_config(&text).map_err(|e| format!("Cannot update {}: {e}", path.display()))?;
+    Ok(Some(ConfigFile { text, config }))
+}
+
+fn has_entry(config: &Map<String, Value>, layout: Layout) -> bool {
+    config
+        .get(layout.servers_key())
+        .and_then(Value::as_object)
+        .is_some_and(|servers| servers.contains_key(SERVER_NAME))
+}
+
+/// Add or update the Depdok entry. Other servers, and settings the user added to the
+/// entry (such as `args`, `tools` or `env`), are kept.
+fn upsert_entry(config: &mut Map<String, Value>, layout: Layout, server_path: &str) -> Result<(), String> {
+    let servers = config
+        .entry(layout.servers_key())
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| format!("\"{}\" is not a JSON object", layout.servers_key()))?;
+    let Value::Object(generated) = layout.entry(server_path) else {
+        unreachable!("entries are JSON objects");
+    };
+    match servers.get_mut(SERVER_NAME) {
+        Some(Value::Object(existing)) => {
+            for (key, value) in generated {
+                if matches!(key.as_str(), "args" | "tools") && existing.contains_key(&key) {
+                    continue;
+                }
+                existing.insert(key, value);
+            }
+        }
+        _ => {
+            servers.insert(SERVER_NAME.to_string(), Value::Object(generated));
+        }
+    }
+    Ok(())
+}
+
+fn remove_entry(config: &mut Map<String, Value>, layout: Layout) -> bool {
+    config
+        .get_mut(layout.servers_key())
+        .and_then(Value::as_object_mut)
+        .is_some_and(|servers| servers.remove(SERVER_NAME).is_some())
+}
+
+/// Write `contents` to a new file at `path` that only the owner can read, then give it
+/// `permissions` when set. Client configs can hold API keys, so neither the temp file
+/// nor a backup is ever readable by others, not even before its permissions are set.
+fn write_private(path: &Path, contents: &str, permissions: Option<fs::Permissions>) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(contents.as_bytes())?;
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)?;
+    }
+    Ok(())
+}
+
+/// Write `config` to `path`. The file is written next to the original and renamed over
+/// it only once it parses back to the same config, so a failed write never leaves a
+/// broken file behind. Returns the backup of the previous file, if there was one.
+fn save_config(path: &Path, previous: Option<&str>, config: &Map<String, Value>) -> Result<Option<PathBuf>, String> {
+    let mut text =
+        serde_json::to_string_pretty(config).map_err(|e| format!("Failed to serialize {}: {e}", path.display()))?;
+    text.push('\n');
+    if parse_config(&text)? != *config {
+        return Err(format!("Refusing to write {}: the new config does not parse back", path.display()));
+    }
+
+    // Write through symlinks (e.g. dotfiles managed elsewhere) instead of replacing them.
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = path.parent().ok_or_else(|| format!("Invalid config path: {}", path.display()))?;
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let permissions = fs::metadata(&path).ok().map(|metadata| metadata.permissions());
+
+    // One backup per change, so the config from before the first change is never lost.
+    let backup = match previous {
+        Some(previous) => {
+            let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f");
+            let mut backup = dir.join(format!("{file_name}.depdok-backup-{stamp}"));
+            // Two changes within the same millisecond get numbered instead of overwriting.
+            for n in 1.. {
+                match write_private(&backup, previous, permissions.clone()) {
+                    Ok(()) => break,
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && n < 100 => {
+                        backup = dir.join(format!("{file_name}.depdok-backup-{stamp}-{n}"));
+                    }
+                    Err(e) => return Err(format!("Failed to back up {}: {e}", path.display())),
+                }
+            }
+            Some(backup)
+        }
+        None => None,
+    };
+
+    let temp = dir.join(format!(".{file_name}.depdok-tmp"));
+    // A temp file left by an interrupted write would make `create_new` fail.
+    let _ = fs::remove_file(&temp);
+    write_private(&temp, &text, permissions).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        format!("Failed to write {}: {e}", temp.display())
+    })?;
+    fs::rename(&temp, &path).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        format!("Failed to replace {}: {e}", path.display())
+    })?;
+    Ok(backup)
+}
+
+fn change(path: &Path, backup: Option<PathBuf>, changed: bool) -> McpConfigChange {
+    McpConfigChange {
+        path: path.to_string_lossy().to_string(),
+        backup_path: backup.map(|backup| backup.to_string_lossy().to_string()),
+        changed,
+    }
+}
+
+/// Comments cannot survive a rewrite, so files that have them are left for the user to edit.
+fn ensure_no_comments(path: &Path, text: &str) -> Result<(), String> {
+    if strip_comments(text) != text {
+        return Err(format!(
+            "{} contains comments, which rewriting it would drop; add the server by hand",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+fn write_entry(path: &Path, layout: Layout, server_path: &str) -> Result<McpConfigChange, String> {
+    let (previous, mut config) = match read_config(path)? {
+        Some(file) => {
+            ensure_no_comments(path, &file.text)?;
+            (Some(file.text), file.config)
+        }
+        None => (None, Map::new()),
+    };
+    let before = config.clone();
+    upsert_entry(&mut config, layout, server_path).map_err(|e| format!("Cannot update {}: {e}", path.display()))?;
+    if previous.is_some() && config == before {
+        return Ok(change(path, None, false));
+    }
+    let backup = save_config(path, previous.as_deref(), &config)?;
+    Ok(change(path, backup, true))
+}
+
+fn remove_from(path: &Path, layout: Layout) -> Result<McpConfigChange, String> {
+    let Some(ConfigFile { text, mut config }) = read_config(path)? else {
+        return Ok(change(path, None, false));
+    };
+    if !remove_entry(&mut config, layout) {
+        return Ok(change(path, None, false));
+    }
+    ensure_no_comments(path, &text)?;
+    let backup = save_config(path, Some(&text), &config)?;
+    Ok(change(path, backup, true))
+}
+
+/// Add the Depdok server to `agent_id`'s config for `scope`, pointing at `server_path`.
+#[tauri::command]
+pub fn write_mcp_client_config(
+    app: tauri::AppHandle,
+    agent_id: String,
+    scope: McpConfigScope,
+    workspace_root: Option<String>,
+    server_path: String,
+) -> Result<McpConfigChange, String> {
+    if !Path::new(&server_path).is_file() {
+        return Err(format!("MCP server binary not found: {server_path}"));
+    }
+    let (path, layout) = client_config(&app, &agent_id, scope, workspace_root.as_deref())?;
+    write_entry(&path, layout, &server_path)
+}
+
+/// Remove the Depdok server from `agent_id`'s config for `scope`.
+#[tauri::command]
+pub fn remove_mcp_client_config(
+    app: tauri::AppHandle,
+    agent_id: String,
+    scope: McpConfigScope,
+    workspace_root: Option<String>,
+) -> Result<McpConfigChange, String> {
+    let (path, layout) = client_config(&app, &agent_id, scope, workspace_root.as_deref())?;
+    remove_from(&path, layout)
+}
+
+/// Whether `agent_id` has a Depdok entry in its user or workspace config.
+#[tauri::command]
+pub fn check_mcp_config_status(app: tauri::AppHandle, agent_id: String, workspace_root: Option<String>) -> bool {
+    let configured = |scope| {
+        client_config(&app, &agent_id, scope, workspace_root.as_deref())
+            .ok()
+            .and_then(|(path, layout)| read_config(&path).ok().flatten().map(|file| has_entry(&file.config, layout)))
+            .unwrap_or(false)
+    };
+    // Older setup instructions had Codex users create a standalone codex-config.json.
+    let legacy_codex = || {
+        agent_id == "codex"
+            && workspace_root
+                .as_ref()
+                .and_then(|root| read_config(&Path::new(root).join("codex-config.json")).ok().flatten())
+                .is_some_and(|file| file.config.get("name").and_then(Value::as_str) == Some(SERVER_NAME))
+    };
+    configured(McpConfigScope::User) || configured(McpConfigScope::Workspace) || legacy_codex()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knowledge_base::tests::TempDir;
+
+    #[test]
+    fn writing_keeps_other_servers_and_backs_up_the_file() {
+        let dir = TempDir::new("mcp-config").unwrap();
+        let path = dir.path().join(".mcp.json");
+        let original = "{\n  \"mcpServers\": { \"other\": { \"command\": \"other\" } },\n  \"theme\": \"dark\"\n}";
+        fs::write(&path, original).unwrap();
+
+        let result = write_entry(&path, Layout::ClaudeCode, "/bin/depdok-mcp-server").unwrap();
+        assert!(result.changed);
+        assert_eq!(fs::read_to_string(result.backup_path.unwrap()).unwrap(), original);
+
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.find("mcpServers") < written.find("theme"), "key order is kept");
+        let config = parse_config(&written).unwrap();
+        assert_eq!(config["theme"], "dark");
+        assert_eq!(config["mcpServers"]["other"]["command"], "other");
+        assert_eq!(config["mcpServers"]["depdok"]["type"], "stdio");
+        assert_eq!(config["mcpServers"]["depdok"]["command"], "/bin/depdok-mcp-server");
+
+        // Writing the same entry again leaves the file alone.
+        assert!(!write_entry(&path, Layout::ClaudeCode, "/bin/depdok-mcp-server").unwrap().changed);
+
+        assert!(remove_from(&path, Layout::ClaudeCode).unwrap().changed);
+        let config = parse_config(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(!has_entry(&config, Layout::ClaudeCode));
+        assert_eq!(config["mcpServers"]["other"]["command"], "other");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn backups_are_kept_per_change_with_the_original_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new("mcp-config").unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, "{ \"apiKey\": \"secret\" }").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        let first = write_entry(&path, Layout::McpServers, "/bin/depdok-mcp-server").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let second = remove_from(&path, Layout::McpServers).unwrap();
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let (first, second) = (PathBuf::from(first.backup_path.unwrap()), PathBuf::from(second.backup_path.unwrap()));
+        assert_ne!(first, second, "the second change must not overwrite the first backup");
+        assert_eq!(fs::read_to_string(&first).unwrap(), "{ \"apiKey\": \"secret\" }");
+        assert_eq!(mode(&first), 0o640);
+        assert_eq!(mode(&second), 0o640);
+        assert_eq!(mode(&path), 0o640);
+    }
+
+    #[test]
+    fn updating_keeps_user_args() {
+        let mut config = parse_config(r#"{ "servers": { "depdok": { "command": "/old", "args": ["--watch", "."] } } }"#).unwrap();
+        upsert_entry(&mut config, Layout::Servers, "/new").unwrap();
+        assert_eq!(config["servers"]["depdok"], json!({ "command": "/new", "args": ["--watch", "."] }));
+    }
+
+    #[test]
+    fn copilot_cli_entries_live_under_mcp_servers() {
+        let dir = TempDir::new("mcp-config").unwrap();
+        let path = dir.path().join("mcp-config.json");
+        fs::write(&path, r#"{ "mcpServers": { "depdok": { "type": "local", "command": "/old", "tools": ["kb_search"] } } }"#)
+            .unwrap();
+        let config = parse_config(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(has_entry(&config, Layout::CopilotCli), "an existing CLI setup counts as configured");
+
+        assert!(write_entry(&path, Layout::CopilotCli, "/bin/depdok-mcp-server").unwrap().changed);
+        let config = parse_config(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(config.get("servers").is_none());
+        assert_eq!(
+            config["mcpServers"]["depdok"],
+            json!({ "type": "local", "command": "/bin/depdok-mcp-server", "tools": ["kb_search"], "args": [] })
+        );
+    }
+
+    #[test]
+    fn invalid_configs_are_not_overwritten() {
+        let dir = TempDir::new("mcp-config").unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, "{ \"mcpServers\": ").unwrap();
+        assert!(write_entry(&path, Layout::McpServers, "/bin/depdok-mcp-server").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ \"mcpServers\": ");
+
+        fs::write(&path, "{ \"mcpServers\": [] }").unwrap();
+        assert!(write_entry(&path, Layout::McpServers, "/bin/depdok-mcp-server").is_err());
+    }
+
+    #[test]
+    fn jsonc_comments_are_ignored_outside_strings() {
+        let config = parse_config("{\n  // servers\n  \"mcp\": { /* none */ },\n  \"url\": \"http://x//y\"\n}").unwrap();
+        assert_eq!(config["url"], "http://x//y");
+        assert!(!has_entry(&config, Layout::OpenCode));
+
+        let dir = TempDir::new("mcp-config").unwrap();
+        let path = dir.path().join("opencode.jsonc");
+        fs::write(&path, "{\n  // keep me\n  \"mcp\": {}\n}").unwrap();
+        assert!(write_entry(&path, Layout::OpenCode, "/bin/depdok-mcp-server").is_err());
+    }
+}
@@ -5,3 +5,4 @@ pub mod file_search;
 pub mod content_search;
 pub mod terminal;
 pub mod export;
+pub mod mcp_config;
@@ -1,4 +1,3 @@
-use rusqlite::params;
 use serde::Serialize;
 use tauri::{State, Manager, Emitter};
 use futures_util::StreamExt;
@@ -6,8 +5,17 @@ use tokio::io::AsyncWriteExt;
 use std::fs;
 
 use super::{
+    duplicates::{self, DuplicateReport},
     embedding::EmbedderState,
-    manager::{GraphDocumentRecord, HybridSearchResult, ProjectGraphRecord, SearchResult},
+    language::{LanguageMode, LanguagePreference},
+    manager::{
+        ChangeFeedPage, DeleteGroupResult, GraphDocumentRecord, GroupRecord, HybridSearchResult,
+        ProjectGraphRecord, RecentDocumentRecord, SearchResult,
+    },
+    query_expansion::QueryExpansion,
+    recovery::{self, BackupRecord, RecoveryReport},
+    revisions::{RevisionDiff, RevisionRecord},
+    vector_index::VectorQuantization,
     KbState,
 };
 
@@ -59,28 +67,7 @@ pub async fn connect_to(
     target_id: String,
     edge_type: Option<String>,
 ) -> Result<String, String> {
-    let edge_id = uuid::Uuid::new_v4().to_string();
-    let conn = kb_state.0.db_lock();
-    let conn = conn.lock().await;
-    
-    // Check if target exists first
-    let target_exists: bool = conn.query_row(
-        "SELECT EXISTS(SELECT 1 FROM documents WHERE id = ?1)",
-        params![target_id],
-        |row| row.get(0),
-    ).unwrap_or(false);
-    
-    if !target_exists {
-        return Err("Target document does not exist".to_string());
-    }
-
-    conn.execute(
-        "INSERT INTO edges (id, source_id, target_id, type) VALUES (?1, ?2, ?3, ?4)",
-        params![edge_id, source_id, target_id, edge_type],
-    )
-    .map_err(|e| format!("Failed to insert edge: {e}"))?;
-
-    Ok(edge_id)
+    kb_state.0.connect_documents(source_id, target_id, edge_type).await
 }
 
 #[tauri::command]
@@ -89,29 +76,7 @@ pub async fn delete_connection(
     source_id: String,
     target_id: String,
 ) -> Result<(), String> {
-    let conn = kb_state.0.db_lock();
-    let conn = conn.lock().await;
-
-    let source_like = format!("{}#section:%", source_id);
-    let target_like = format!("{}#section:%", target_id);
-
-    conn.execute(
-        "DELETE FROM edges 
-         WHERE (source_id = ?1 OR source_id LIKE ?2) 
-           AND (target_id = ?3 OR target_id LIKE ?4)",
-        params![source_id, source_like, target_id, target_like],
-    )
-    .map_err(|e| format!("Failed to delete edge: {e}"))?;
-
-    // Also in reverse
-    conn.execute(
-        "DELETE FROM edges 
-         WHERE (source_id = ?1 OR source_id LIKE ?2) 
-           AND (target_id = ?3 OR target_id LIKE ?4)",
-        params![target_id, target_like, source_id, source_like],
-    )
-    .map_err(|e| format!("Failed to delete reverse edge: {e}"))?;
-
+    kb_state.0.disconnect_documents(source_id, target_id).await?;
     Ok(())
 }
 
@@ -127,11 +92,41 @@ pub async fn search_similar(
 
 #[tauri::command]
 pub async fn search_hybrid(
+    app: tauri::AppHandle,
     kb_state: State<'_, KbState>,
+    llm_state: State<'_, crate::llm::LlmState>,
     query: String,
     limit: usize,
+    group_ids: Option<Vec<String>>,
+    expansion: Option<QueryExpansion>,
+    language: Option<String>,
+    language_mode: Option<LanguageMode>,
 ) -> Result<Vec<HybridSearchResult>, String> {
-    kb_state.0.search_hybrid(query, limit).await
+    let language = language
+        .and_then(|code| LanguagePreference::new(&code, language_mode.unwrap_or_default()));
+    let Some(mode) = expansion else {
+        return kb_state.0.search_hybrid_with_language(query, limit, group_ids, language).await;
+    };
+    let config = crate::llm::settings::load_config_internal(&app, &llm_state);
+    let generator = crate::llm::providers::ProviderTextGenerator::new(config, &llm_state).await?;
+    kb_state.0.search_expanded(query, limit, group_ids, language, mode, &generator).await
+}
+
+#[tauri::command]
+pub async fn find_duplicates(
+    kb_state: State<'_, KbState>,
+    group_id: String,
+    similarity_threshold: Option<f32>,
+    min_overlap: Option<f32>,
+) -> Result<DuplicateReport, String> {
+    kb_state
+        .0
+        .find_duplicates(
+            group_id,
+            similarity_threshold.unwrap_or(duplicates::DEFAULT_SIMILARITY_THRESHOLD),
+            min_overlap.unwrap_or(duplicates::DEFAULT_MIN_OVERLAP),
+        )
+        .await
 }
 
 #[tauri::command]
@@ -159,6 +154,164 @@ pub async fn get_project_graph(
     kb_state.0.get_project_graph(group_id).await
 }
 
+#[tauri::command]
+pub async fn list_recent_documents(
+    kb_state: State<'_, KbState>,
+    group_id: String,
+    since: Option<String>,
+    limit: usize,
+) -> Result<Vec<RecentDocumentRecord>, String> {
+    kb_state.0.list_recent_documents(Some(group_id), since, limit).await
+}
+
+#[tauri::command]
+pub async fn get_document_changes(
+    kb_state: State<'_, KbState>,
+    cursor: i64,
+    limit: usize,
+    group_id: Option<String>,
+) -> Result<ChangeFeedPage, String> {
+    kb_state.0.get_changes_since(cursor, limit, group_id).await
+}
+
+#[tauri::command]
+pub async fn list_document_revisions(
+    kb_state: State<'_, KbState>,
+    document_id: String,
+) -> Result<Vec<RevisionRecord>, String> {
+    kb_state.0.list_revisions(document_id).await
+}
+
+#[tauri::command]
+pub async fn diff_document_revisions(
+    kb_state: State<'_, KbState>,
+    from_revision_id: i64,
+    to_revision_id: i64,
+) -> Result<RevisionDiff, String> {
+    kb_state.0.diff_revisions(from_revision_id, to_revision_id).await
+}
+
+#[tauri::command]
+pub async fn restore_document_revision(
+    kb_state: State<'_, KbState>,
+    revision_id: i64,
+) -> Result<String, String> {
+    kb_state.0.restore_revision(revision_id).await
+}
+
+#[tauri::command]
+pub async fn list_groups(
+    kb_state: State<'_, KbState>,
+) -> Result<Vec<GroupRecord>, String> {
+    kb_state.0.list_groups().await
+}
+
+#[tauri::command]
+pub async fn set_document_groups(
+    kb_state: State<'_, KbState>,
+    document_id: String,
+    group_ids: Vec<String>,
+) -> Result<bool, String> {
+    kb_state.0.set_document_groups(document_id, group_ids).await
+}
+
+#[tauri::command]
+pub async fn rename_group(
+    kb_state: State<'_, KbState>,
+    group_id: String,
+    title: String,
+) -> Result<(), String> {
+    kb_state.0.rename_group(group_id, title).await
+}
+
+#[tauri::command]
+pub async fn merge_groups(
+    kb_state: State<'_, KbState>,
+    source_id: String,
+    target_id: String,
+) -> Result<(), String> {
+    kb_state.0.merge_groups(source_id, target_id).await
+}
+
+#[tauri::command]
+pub async fn delete_group(
+    kb_state: State<'_, KbState>,
+    group_id: String,
+    delete_exclusive_documents: bool,
+) -> Result<DeleteGroupResult, String> {
+    kb_state.0.delete_group(group_id, delete_exclusive_documents).await
+}
+
+#[tauri::command]
+pub async fn get_vector_quantization(
+    kb_state: State<'_, KbState>,
+) -> Result<VectorQuantization, String> {
+    kb_state.0.get_vector_quantization().await
+}
+
+#[tauri::command]
+pub async fn set_vector_quantization(
+    kb_state: State<'_, KbState>,
+    quantization: VectorQuantization,
+) -> Result<usize, String> {
+    kb_state.0.set_vector_quantization(quantization).await
+}
+
+#[tauri::command]
+pub fn get_image_extraction(kb_state: State<'_, KbState>) -> bool {
+    kb_state.0.image_extraction_enabled()
+}
+
+/// Turn OCR/SVG text indexing of referenced images on or off. Enabling downloads the
+/// OCR models on first use; images are picked up the next time a file is indexed.
+#[tauri::command]
+pub async fn set_image_extraction(
+    app: tauri::AppHandle,
+    kb_state: State<'_, KbState>,
+    enabled: bool,
+) -> Result<(), String> {
+    use tauri_plugin_store::StoreExt;
+
+    if enabled {
+        let cache_dir = app
+            .path()
+            .app_cache_dir()
+            .map_err(|e| format!("Failed to resolve cache dir: {e}"))?;
+        let extractor = tokio::task::spawn_blocking(move || {
+            super::image_extractor::OcrsExtractor::load(&cache_dir)
+        })
+        .await
+        .map_err(|e| format!("Task error: {e}"))??;
+        kb_state.0.set_image_extractor(Some(std::sync::Arc::new(extractor)));
+    } else {
+        kb_state.0.set_image_extractor(None);
+    }
+
+    if let Ok(store) = app.store("store.json") {
+        store.set("kb_image_extraction", serde_json::json!(enabled));
+        let _ = store.save();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_auto_summaries(app: tauri::AppHandle) -> bool {
+    super::auto_summaries_enabled(&app)
+}
+
+/// Turn the background summary job on or off. It uses the chat LLM provider and picks
+/// up new and changed documents within a couple of minutes.
+#[tauri::command]
+pub fn set_auto_summaries(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    use tauri_plugin_store::StoreExt;
+
+    let store = app
+        .store("store.json")
+        .map_err(|e| format!("Failed to open settings store: {e}"))?;
+    store.set("kb_auto_summaries", serde_json::json!(enabled));
+    store.save().map_err(|e| format!("Failed to save settings: {e}"))
+}
+
 #[tauri::command]
 pub async fn set_current_project_group(
     group_state: State<'_, super::CurrentProjectGroup>,
@@ -172,12 +325,40 @@ pub async fn set_current_project_group(
     Ok(())
 }
 
+#[tauri::command]
+pub fn get_recovery_report(app: tauri::AppHandle) -> Result<Option<RecoveryReport>, String> {
+    recovery::read_report(&super::db::database_path(&app)?)
+}
+
+#[tauri::command]
+pub fn dismiss_recovery_report(app: tauri::AppHandle) -> Result<(), String> {
+    recovery::dismiss_report(&super::db::database_path(&app)?)
+}
+
+#[tauri::command]
+pub fn list_kb_backups(app: tauri::AppHandle) -> Result<Vec<BackupRecord>, String> {
+    recovery::list_backups(&super::db::database_path(&app)?)
+}
+
+#[tauri::command]
+pub async fn create_kb_backup(
+    app: tauri::AppHandle,
+    kb_state: State<'_, KbState>,
+) -> Result<BackupRecord, String> {
+    recovery::create_backup(&kb_state.0, &super::db::database_path(&app)?).await
+}
+
+/// Schedules the backup to replace the database on the next app start.
+#[tauri::command]
+pub fn restore_kb_backup(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    recovery::schedule_restore(&super::db::database_path(&app)?, &name)
+}
+
 #[tauri::command]
 pub async fn test_database_query(
     kb_state: State<'_, KbState>,
 ) -> Result<DatabaseTestResult, String> {
-    let conn = kb_state.0.db_lock();
-    let conn = conn.lock().await;
+    let conn = kb_state.0.read_connection().await;
 
     let sqlite_version: String = conn
         .query_row("SELECT sqlite_version()", [], |row| row.get(0))
@@ -233,6 +414,8 @@ pub struct CurrentModelStatus {
     pub model_name: String,
     #[serde(rename = "openaiKey")]
     pub openai_key: Option<String>,
+    /// Base URL of the OpenAI-compatible server when `model_type` is `"server"`.
+    pub endpoint: Option<String>,
     #[serde(rename = "isDownloaded")]
     pub is_downloaded: bool,
 }
@@ -242,7 +425,7 @@ pub fn get_current_embedding_model(app: tauri::AppHandle) -> Result<CurrentModel
     use tauri_plugin_store::StoreExt;
     let cache_dir = app.path().app_cache_dir().ok();
 
-    let (model_type, model_name, openai_key) = if let Ok(store) = app.store("store.json") {
+    let (model_type, model_name, openai_key, endpoint) = if let Ok(store) = app.store("store.json") {
         let model_type = store.get("embedding_model_type")
             .and_then(|v| v.as_str().map(|s| s.to_string()))
             .unwrap_or_else(|| "local".to_string());
@@ -251,13 +434,17 @@ pub fn get_current_embedding_model(app: tauri::AppHandle) -> Result<CurrentModel
             .unwrap_or_else(|| "all-MiniLM-L6-v2".to_string());
         let openai_key = store.get("openai_api_key")
             .and_then(|v| v.as_str().map(|s| s.to_string()));
-        (model_type, model_name, openai_key)
+        let endpoint = store.get("embedding_endpoint")
+            .and_then(|v| v.as_str().map(|s| s.to_string()));
+        (model_type, model_name, openai_key, endpoint)
     } else {
-        ("local".to_string(), "all-MiniLM-L6-v2".to_string(), None)
+        ("local".to_string(), "all-MiniLM-L6-v2".to_string(), None, None)
     };
 
-    let is_downloaded = if model_type == "remote" {
+    let is_downloaded = if model_type == "remote" || model_type == "server" {
         true
+    } else if model_type == "gguf" {
+        crate::llm::models::resolve_model_path(&app, &model_name).is_ok()
     } else if let Some(ref cache) = cache_dir {
         super::embedding::is_model_downloaded(cache, &model_name)
     } else {
@@ -268,6 +455,7 @@ pub fn get_current_embedding_model(app: tauri::AppHandle) -> Result<CurrentModel
         model_type,
         model_name,
         openai_key,
+        endpoint,
         is_downloaded,
     })
 }
@@ -280,17 +468,20 @@ pub async fn update_embedding_model_and_reindex(
     model_type: String,
     model_name: String,
     openai_key: Option<String>,
+    endpoint: Option<String>,
     workspace_root: String,
 ) -> Result<usize, String> {
     use tauri_plugin_store::StoreExt;
     
     // 1. Re-initialize embedder (forcing download since user requested this change)
     let cache_dir = app.path().app_cache_dir().ok();
+    let model_ref = super::resolve_embedding_model(&app, &model_type, &model_name)?;
     let new_embedder = super::embedding::init_embedder_with_config(
         cache_dir,
         &model_type,
-        &model_name,
+        &model_ref,
         openai_key.clone(),
+        endpoint.clone(),
         true,
     )?;
     let new_dims = new_embedder.dimensions();
@@ -305,6 +496,11 @@ pub async fn update_embedding_model_and_reindex(
             // Remove the key if switching to local
             let _ = store.delete("openai_api_key");
         }
+        if let Some(ref url) = endpoint {
+            store.set("embedding_endpoint", serde_json::json!(url));
+        } else {
+            let _ = store.delete("embedding_endpoint");
+        }
         let _ = store.save();
     }
 
@@ -317,14 +513,8 @@ pub async fn update_embedding_model_and_reindex(
         tx.execute("DROP TABLE IF EXISTS documents_embeddings;", []).map_err(|e| e.to_string())?;
         tx.execute("DELETE FROM document_chunks;", []).map_err(|e| e.to_string())?;
         
-        let create_vec = format!(
-            "CREATE VIRTUAL TABLE IF NOT EXISTS documents_embeddings USING vec0(
-                chunk_id    TEXT PRIMARY KEY,
-                document_id TEXT,
-                embedding   FLOAT[{new_dims}]
-            );"
-        );
-        tx.execute_batch(&create_vec).map_err(|e| e.to_string())?;
+        let quantization = super::vector_index::read_quantization(&tx)?;
+        super::vector_index::create_embeddings_table(&tx, new_dims, quantization)?;
         tx.commit().map_err(|e| e.to_string())?;
     }
 
@@ -400,6 +590,7 @@ pub fn get_downloaded_models(app: tauri::AppHandle) -> Result<Vec<String>, Strin
             "multilingual-e5-base",
             "multilingual-e5-large",
             "paraphrase-multilingual-MiniLM-L12-v2",
+            "paraphrase-multilingual-mpnet-base-v2",
             "bge-small-zh-v1.5",
             "bge-large-zh-v1.5",
         ];
@@ -541,6 +732,11 @@ fn get_model_download_info(model_name: &str) -> Option<ModelDownloadInfo> {
             onnx_file: "onnx/model.onnx",
             additional_files: &[],
         }),
+        "paraphrase-multilingual-mpnet-base-v2" => Some(ModelDownloadInfo {
+            repo_id: "Xenova/paraphrase-multilingual-mpnet-base-v2",
+            onnx_file: "onnx/model.onnx",
+            additional_files: &[],
+        }),
         "bge-small-zh-v1.5" => Some(ModelDownloadInfo {
             repo_id: "Xenova/bge-small-zh-v1.5",
             onnx_file: "onnx/model.onnx",
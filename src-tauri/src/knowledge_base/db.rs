@@ -1,33 +1,70 @@
-use rusqlite::Connection;
+use rusqlite::{Connection, OpenFlags};
 use tauri::Manager;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-/// Initialise the SQLite database for the knowledge base.
-///
-/// Steps:
-/// 1. Resolve the OS app-data directory.
-/// 2. Register the `sqlite-vec` extension globally via `sqlite3_auto_extension`
-///    so every subsequent `Connection::open` has the `vec0` virtual table available.
-/// 3. Open the connection and apply pragmas.
-/// 4. Create the schema (idempotent).
-///
-/// `dims` is read from the active `Embedder::dimensions()` so the `vec0` table
-/// is always sized correctly for the current provider.
-pub fn init_database(app_handle: &tauri::AppHandle, dims: usize) -> Result<Connection, String> {
+/// Number of read-only connections opened next to the writer.
+pub const READ_POOL_SIZE: usize = 4;
+
+/// Writers wait this long for a competing lock (e.g. the MCP binary writing) before failing.
+const BUSY_TIMEOUT_MS: u32 = 5_000;
+
+/// Location of the knowledge base file inside the OS app-data directory.
+pub fn database_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
     let app_data_dir = app_handle
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
 
-    let db_path = app_data_dir.join("knowledge_base.db");
+    Ok(app_data_dir.join("knowledge_base.db"))
+}
 
-    init_database_at_path(&db_path, dims)
+/// Open `count` read-only connections to a database already set up by
+/// `init_database_at_path`. The writer switched the file to WAL, so these read
+/// a consistent snapshot without waiting for in-flight write transactions.
+pub fn open_read_connections(db_path: &Path, count: usize) -> Result<Vec<Connection>, String> {
+    (0..count)
+        .map(|_| {
+            let conn = Connection::open_with_flags(
+                db_path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )
+            .map_err(|e| format!("Failed to open read connection: {e}"))?;
+            conn.busy_timeout(std::time::Duration::from_millis(BUSY_TIMEOUT_MS.into()))
+                .map_err(|e| e.to_string())?;
+            Ok(conn)
+        })
+        .collect()
 }
 
-/// Initialise the SQLite database at an explicit filesystem path.
+/// Register the `sqlite-vec` extension globally so every connection opened afterwards
+/// has the `vec0` virtual table available. Only the first call has an effect.
+pub fn register_sqlite_vec() {
+    static REGISTER: std::sync::Once = std::sync::Once::new();
+    REGISTER.call_once(|| {
+        // SAFETY: sqlite3_auto_extension stores a function pointer in a global SQLite
+        // registry; this is the documented way to load SQLite extensions at startup.
+        unsafe {
+            let _ = rusqlite::ffi::sqlite3_auto_extension(Some(std::mem::transmute(
+                sqlite_vec::sqlite3_vec_init as *const (),
+            )));
+        }
+    });
+}
+
+/// Initialise the SQLite database for the knowledge base at `db_path`.
+///
+/// Steps:
+/// 1. Register the `sqlite-vec` extension globally via `sqlite3_auto_extension`
+///    so every subsequent `Connection::open` has the `vec0` virtual table available.
+/// 2. Open the connection and apply pragmas.
+/// 3. Migrate and create the schema (idempotent).
+///
+/// `dims` is read from the active `Embedder::dimensions()` so the `vec0` table
+/// is always sized correctly for the current provider.
 ///
 /// This is shared by the Tauri app and the standalone MCP binary so both
-/// entrypoints use the same schema and sqlite-vec setup.
+/// entrypoints use the same schema and sqlite-vec setup. It never moves the file;
+/// restores and corruption recovery are left to `open_app_database`.
 pub fn init_database_at_path(db_path: &Path, dims: usize) -> Result<Connection, String> {
     if let Some(parent) = db_path.parent() {
         std::fs::create_dir_all(parent)
@@ -35,34 +72,48 @@ pub fn init_database_at_path(db_path: &Path, dims: usize) -> Result<Connection,
     }
 
     // Register sqlite-vec before the first Connection::open.
-    // SAFETY: sqlite3_auto_extension stores a function pointer in a global SQLite
-    // registry; this is the documented way to load SQLite extensions at startup.
-    unsafe {
-        let _ = rusqlite::ffi::sqlite3_auto_extension(Some(std::mem::transmute(
-            sqlite_vec::sqlite3_vec_init as *const (),
-        )));
+    register_sqlite_vec();
+
+    init_database_inner(db_path, dims)
+}
+
+/// Whether an error from opening the database means the file itself is damaged.
+pub fn is_corruption_error(message: &str) -> bool {
+    message.contains("malformed") || message.contains("corrupt")
+}
+
+/// `init_database_at_path` for the desktop app, which additionally applies a backup
+/// restore scheduled from the UI and recovers a corrupt file.
+///
+/// Both rename the database file, which is only safe in the process that owns it:
+/// an MCP server renaming it would leave the app writing to the quarantined copy.
+/// MCP servers therefore open the database with `init_database_at_path` and fail
+/// on a corrupt file instead.
+pub fn open_app_database(db_path: &Path, dims: usize) -> Result<Connection, String> {
+    // Swap in a backup the user chose to restore while no connection is open.
+    if let Some(replaced) = super::recovery::apply_pending_restore(db_path)? {
+        eprintln!("Restored backup; previous database kept at {}", replaced.display());
     }
 
-    // Try opening and initializing the database. If it fails due to corruption,
-    // delete the database file and try again from scratch.
-    match init_database_inner(db_path, dims) {
+    // If opening fails due to corruption, quarantine the file, start from scratch
+    // and salvage what is still readable.
+    match init_database_at_path(db_path, dims) {
         Ok(conn) => Ok(conn),
-        Err(err_msg) => {
-            if err_msg.contains("malformed") || err_msg.contains("corrupt") {
-                eprintln!("Database corrupted ({err_msg}). Re-creating database file...");
-                let _ = std::fs::remove_file(db_path);
-                let _ = std::fs::remove_file(db_path.with_extension("db-wal"));
-                let _ = std::fs::remove_file(db_path.with_extension("db-shm"));
-                let _ = std::fs::remove_file(db_path.with_extension("db-journal"));
-                
-                init_database_inner(db_path, dims)
-            } else {
-                Err(err_msg)
-            }
+        Err(err_msg) if is_corruption_error(&err_msg) => {
+            super::recovery::recover_corrupt_database(db_path, &err_msg, || {
+                init_database_at_path(db_path, dims)
+            })
         }
+        Err(err_msg) => Err(err_msg),
     }
 }
 
+/// Schema version written to `PRAGMA user_version` once all migrations have run.
+const SCHEMA_VERSION: i32 = 8;
+
+/// Databases older than this are wiped and rebuilt instead of migrated.
+const MIN_MIGRATABLE_VERSION: i32 = 2;
+
 /// Helper that performs the actual database opening, pragmas, version check,
 /// clean resets, and schema table creations.
 fn init_database_inner(db_path: &Path, dims: usize) -> Result<Connection, String> {
@@ -72,46 +123,182 @@ fn init_database_inner(db_path: &Path, dims: usize) -> Result<Connection, String
     conn.execute_batch("PRAGMA foreign_keys = ON;")
         .map_err(|e| e.to_string())?;
 
+    // WAL lets the read connections keep searching while this connection writes.
+    // The mode is persisted in the file, so the MCP binary picks it up as well.
+    conn.execute_batch(&format!(
+        "PRAGMA journal_mode = WAL;
+         PRAGMA synchronous = NORMAL;
+         PRAGMA busy_timeout = {BUSY_TIMEOUT_MS};"
+    ))
+    .map_err(|e| format!("Failed to enable WAL: {e}"))?;
+
     // Check version
     let user_version: i32 = conn
         .query_row("PRAGMA user_version;", [], |row| row.get(0))
         .map_err(|e| format!("Failed to read user_version: {e}"))?;
 
-    let target_version = 2;
+    // The reset drops tables other tables point at; foreign keys can only be switched
+    // off outside a transaction.
+    let resetting = user_version < MIN_MIGRATABLE_VERSION;
+    if resetting {
+        conn.execute_batch("PRAGMA foreign_keys = OFF;")
+            .map_err(|e| e.to_string())?;
+    }
+
+    // Migrations, schema creation and the version bump commit together, so a failed
+    // step leaves the database at its old version rather than half-migrated.
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("Failed to start schema transaction: {e}"))?;
 
-    if user_version < target_version {
+    if resetting {
         // Drop all existing tables to perform a clean reset, avoiding trigger/virtual table mismatches.
-        conn.execute_batch(
+        tx.execute_batch(
             "
-            PRAGMA foreign_keys = OFF;
             DROP TRIGGER IF EXISTS documents_fts_insert;
             DROP TRIGGER IF EXISTS documents_fts_update;
             DROP TRIGGER IF EXISTS documents_fts_delete;
             DROP TABLE IF EXISTS documents_fts;
             DROP TABLE IF EXISTS documents_embeddings;
+            DROP TABLE IF EXISTS document_chunk_vectors;
             DROP TABLE IF EXISTS document_chunks;
             DROP TABLE IF EXISTS document_tags;
             DROP TABLE IF EXISTS document_groups;
             DROP TABLE IF EXISTS edges;
             DROP TABLE IF EXISTS groups;
             DROP TABLE IF EXISTS documents;
-            PRAGMA foreign_keys = ON;
             "
         ).map_err(|e| format!("Failed to clear old database tables: {e}"))?;
+    } else if user_version < SCHEMA_VERSION {
+        migrate_schema(&tx, user_version)?;
+    }
+
+    create_schema(&tx, dims)?;
+
+    if user_version < SCHEMA_VERSION {
+        // Set new user_version
+        tx.execute_batch(&format!("PRAGMA user_version = {SCHEMA_VERSION};"))
+            .map_err(|e| format!("Failed to set user_version: {e}"))?;
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit schema changes: {e}"))?;
+
+    if resetting {
+        conn.execute_batch("PRAGMA foreign_keys = ON;")
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(conn)
+}
+
+/// Bring an existing database at `from_version` up to `SCHEMA_VERSION` in place,
+/// preserving its rows. Each step only adds to the schema so it can be applied
+/// before `create_schema`.
+fn migrate_schema(conn: &Connection, from_version: i32) -> Result<(), String> {
+    if from_version < 3 {
+        conn.execute_batch(
+            "ALTER TABLE groups ADD COLUMN parent_id TEXT REFERENCES groups(id) ON DELETE CASCADE;",
+        )
+        .map_err(|e| format!("Migration to v3 failed: {e}"))?;
+    }
+
+    if from_version < 5 {
+        conn.execute_batch(
+            "
+            ALTER TABLE documents ADD COLUMN created_at TEXT;
+            ALTER TABLE documents ADD COLUMN updated_at TEXT;
+            UPDATE documents
+               SET created_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+                   updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now');
+
+            CREATE TABLE IF NOT EXISTS document_changes (
+                seq         INTEGER PRIMARY KEY AUTOINCREMENT,
+                document_id TEXT NOT NULL,
+                change_type TEXT NOT NULL,
+                changed_at  TEXT NOT NULL
+            );
+            -- Seed the feed so clients starting from cursor 0 see existing documents.
+            INSERT INTO document_changes (document_id, change_type, changed_at)
+            SELECT id, 'upsert', updated_at FROM documents;
+            ",
+        )
+        .map_err(|e| format!("Migration to v5 failed: {e}"))?;
+    }
+
+    // Very old databases have no chunk table yet; `create_schema` adds it with the column.
+    let has_chunks: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'document_chunks')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Migration to v6 failed: {e}"))?;
+    if from_version < 6 && has_chunks {
+        conn.execute_batch("ALTER TABLE document_chunks ADD COLUMN language TEXT;")
+            .map_err(|e| format!("Migration to v6 failed: {e}"))?;
+        backfill_chunk_languages(conn)?;
+    }
+
+    if from_version < 7 {
+        conn.execute_batch("ALTER TABLE document_changes ADD COLUMN group_ids TEXT;")
+            .map_err(|e| format!("Migration to v7 failed: {e}"))?;
     }
 
+    // Sections indexed before this keep their insertion order until the file is re-indexed.
+    if from_version < 8 {
+        conn.execute_batch("ALTER TABLE documents ADD COLUMN section_position INTEGER;")
+            .map_err(|e| format!("Migration to v8 failed: {e}"))?;
+    }
+
+    Ok(())
+}
+
+/// Detect the language of chunks written before the column existed.
+fn backfill_chunk_languages(conn: &Connection) -> Result<(), String> {
+    let chunks: Vec<(String, String)> = {
+        let mut stmt = conn
+            .prepare("SELECT chunk_id, content FROM document_chunks")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    let mut update = conn
+        .prepare("UPDATE document_chunks SET language = ?2 WHERE chunk_id = ?1")
+        .map_err(|e| e.to_string())?;
+    for (chunk_id, content) in chunks {
+        if let Some(language) = super::language::detect(&content) {
+            update
+                .execute(rusqlite::params![chunk_id, language])
+                .map_err(|e| format!("Failed to store chunk language: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Create every table, index, trigger and virtual table used by the knowledge base.
+///
+/// All statements are idempotent, so this runs on every start-up after migrations.
+/// Tests call it directly against an in-memory connection.
+pub(crate) fn create_schema(conn: &Connection, dims: usize) -> Result<(), String> {
     // --- Plain tables ----------------------------------------------------------
     conn.execute_batch(
         "
         CREATE TABLE IF NOT EXISTS documents (
-            id      TEXT PRIMARY KEY,   -- UUID v4 or file:{path}
-            title   TEXT NOT NULL,
-            content TEXT NOT NULL
+            id         TEXT PRIMARY KEY,   -- UUID v4 or file:{path}
+            title      TEXT NOT NULL,
+            content    TEXT NOT NULL,
+            created_at TEXT,               -- RFC 3339, UTC
+            updated_at TEXT,               -- RFC 3339, UTC; moves only when title/content change
+            section_position INTEGER       -- order within its markdown file, for #section: documents
         );
 
         CREATE TABLE IF NOT EXISTS groups (
-            id    TEXT PRIMARY KEY,
-            title TEXT NOT NULL
+            id        TEXT PRIMARY KEY,
+            title     TEXT NOT NULL,
+            parent_id TEXT REFERENCES groups(id) ON DELETE CASCADE  -- NULL for workspace roots
         );
 
         CREATE TABLE IF NOT EXISTS document_groups (
@@ -131,7 +318,8 @@ fn init_database_inner(db_path: &Path, dims: usize) -> Result<Connection, String
             chunk_id    TEXT PRIMARY KEY,
             document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
             chunk_index INTEGER NOT NULL,
-            content     TEXT NOT NULL
+            content     TEXT NOT NULL,
+            language    TEXT                -- ISO 639-1 code, NULL when undetected
         );
 
         CREATE TABLE IF NOT EXISTS document_tags (
@@ -140,10 +328,80 @@ fn init_database_inner(db_path: &Path, dims: usize) -> Result<Connection, String
             PRIMARY KEY (document_id, tag)
         );
 
+        -- Append-only content history. Deliberately not a foreign key: file documents are
+        -- deleted and re-created on every re-index, and their history must survive that.
+        CREATE TABLE IF NOT EXISTS document_revisions (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            document_id  TEXT NOT NULL,
+            title        TEXT NOT NULL,
+            content      TEXT NOT NULL,
+            content_hash TEXT NOT NULL,  -- SHA-256 hex of content
+            created_at   TEXT NOT NULL   -- RFC 3339, UTC
+        );
+
+        -- Change feed: one row per document holding its latest upsert/delete. `seq` is the
+        -- cursor clients pass back to receive only newer changes.
+        CREATE TABLE IF NOT EXISTS document_changes (
+            seq         INTEGER PRIMARY KEY AUTOINCREMENT,
+            document_id TEXT NOT NULL,
+            change_type TEXT NOT NULL,  -- 'upsert' | 'delete'
+            changed_at  TEXT NOT NULL,  -- RFC 3339, UTC
+            group_ids   TEXT            -- JSON array of the groups a deleted document was in
+        );
+
+        -- Key/value settings that must be shared by every process opening the database.
+        CREATE TABLE IF NOT EXISTS kb_meta (
+            key   TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        -- float32 copies of chunk embeddings, used to re-score quantized KNN candidates.
+        CREATE TABLE IF NOT EXISTS document_chunk_vectors (
+            chunk_id  TEXT PRIMARY KEY REFERENCES document_chunks(chunk_id) ON DELETE CASCADE,
+            embedding BLOB NOT NULL
+        );
+
+        -- Text read from images referenced by markdown (OCR or SVG labels), keyed by the
+        -- absolute image path. A cache: re-indexing only re-processes changed images.
+        CREATE TABLE IF NOT EXISTS image_extractions (
+            image_path   TEXT PRIMARY KEY,
+            content_hash TEXT NOT NULL,  -- SHA-256 hex of the image bytes
+            extractor    TEXT NOT NULL,
+            text         TEXT NOT NULL,
+            extracted_at TEXT NOT NULL   -- RFC 3339, UTC
+        );
+
+        -- LLM query rewrites for expanded/HyDE search, keyed by mode, model and query.
+        CREATE TABLE IF NOT EXISTS query_expansions (
+            cache_key  TEXT PRIMARY KEY,  -- SHA-256 hex
+            mode       TEXT NOT NULL,     -- 'expand' | 'hyde'
+            query      TEXT NOT NULL,
+            expansions TEXT NOT NULL,     -- JSON array of generated queries/passages
+            created_at TEXT NOT NULL      -- RFC 3339, UTC
+        );
+
+        -- LLM-written summary and keywords per document. Stale once content_hash no
+        -- longer matches the document's content; regenerated by the summary job.
+        -- An empty summary marks a failed attempt for that content_hash.
+        CREATE TABLE IF NOT EXISTS document_summaries (
+            document_id  TEXT PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+            content_hash TEXT NOT NULL,  -- SHA-256 hex of the summarized content
+            summary      TEXT NOT NULL,
+            keywords     TEXT NOT NULL,  -- JSON array of strings
+            model        TEXT NOT NULL,
+            generated_at TEXT NOT NULL   -- RFC 3339, UTC
+        );
+
         -- Index foreign keys
         CREATE INDEX IF NOT EXISTS idx_chunks_doc ON document_chunks(document_id);
+        CREATE INDEX IF NOT EXISTS idx_chunks_language ON document_chunks(language);
         CREATE INDEX IF NOT EXISTS idx_tags_doc ON document_tags(document_id);
         CREATE INDEX IF NOT EXISTS idx_tags_tag ON document_tags(tag);
+        CREATE INDEX IF NOT EXISTS idx_groups_parent ON groups(parent_id);
+        CREATE INDEX IF NOT EXISTS idx_document_groups_group ON document_groups(group_id);
+        CREATE INDEX IF NOT EXISTS idx_revisions_doc ON document_revisions(document_id, id);
+        CREATE INDEX IF NOT EXISTS idx_documents_updated ON documents(updated_at);
+        CREATE INDEX IF NOT EXISTS idx_changes_doc ON document_changes(document_id);
         ",
     )
     .map_err(|e| format!("Schema creation failed: {e}"))?;
@@ -179,25 +437,9 @@ fn init_database_inner(db_path: &Path, dims: usize) -> Result<Connection, String
     .map_err(|e| format!("FTS5 table or trigger creation failed: {e}"))?;
 
     // --- vec0 virtual table ----------------------------------------------------
-    // Dimension count is interpolated at runtime so it matches the active provider.
-    // NOTE: Changing providers between runs requires re-creating this table because
-    //       vec0 does not support ALTER TABLE.
-    let create_vec = format!(
-        "CREATE VIRTUAL TABLE IF NOT EXISTS documents_embeddings USING vec0(
-            chunk_id    TEXT PRIMARY KEY,
-            document_id TEXT,
-            embedding   FLOAT[{dims}]
-        );"
-    );
-
-    conn.execute_batch(&create_vec)
-        .map_err(|e| format!("vec0 table creation failed: {e}"))?;
+    // Column type follows the quantization mode stored in `kb_meta`.
+    let quantization = super::vector_index::read_quantization(conn)?;
+    super::vector_index::create_embeddings_table(conn, dims, quantization)?;
 
-    if user_version < target_version {
-        // Set new user_version
-        conn.execute_batch(&format!("PRAGMA user_version = {target_version};"))
-            .map_err(|e| format!("Failed to set user_version: {e}"))?;
-    }
-
-    Ok(conn)
+    Ok(())
 }
@@ -0,0 +1,274 @@
+//! Duplicate detection for documentation clean-up: chunks whose normalised text is
+//! identical, and document pairs whose chunks mostly have a near-identical counterpart
+//! in the other document (stale forks, copy-pasted pages).
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Chunks shorter than this (separators, bare headings, "TODO") are not worth reporting.
+const MIN_DUPLICATE_CHUNK_CHARS: usize = 50;
+
+const PREVIEW_CHARS: usize = 160;
+
+/// Default cosine similarity for two chunks to count as the same passage.
+pub const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.92;
+
+/// Default share of a document's chunks that must match for the pair to be reported.
+pub const DEFAULT_MIN_OVERLAP: f32 = 0.5;
+
+/// A stored chunk with its embedding, as loaded for a duplicate scan.
+pub struct ChunkEntry {
+    pub chunk_id: String,
+    pub document_id: String,
+    pub title: String,
+    pub content: String,
+    pub vector: Option<Vec<f32>>,
+    /// Nearest stored chunks by embedding; only these are compared with this chunk.
+    pub neighbors: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkLocation {
+    pub chunk_id: String,
+    pub document_id: String,
+    pub title: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateChunkGroup {
+    /// SHA-256 hex of the whitespace- and case-normalised chunk text.
+    pub content_hash: String,
+    pub preview: String,
+    pub occurrences: Vec<ChunkLocation>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NearDuplicatePair {
+    pub document_a: String,
+    pub title_a: String,
+    pub document_b: String,
+    pub title_b: String,
+    /// Percentage of A's chunks with a match in B.
+    pub overlap_a: f32,
+    /// Percentage of B's chunks with a match in A.
+    pub overlap_b: f32,
+    /// Mean cosine similarity of the matched chunks.
+    pub similarity: f32,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateReport {
+    pub group_id: String,
+    pub similarity_threshold: f32,
+    pub min_overlap: f32,
+    pub exact_duplicates: Vec<DuplicateChunkGroup>,
+    pub near_duplicates: Vec<NearDuplicatePair>,
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Chunks with identical normalised text, in two or more places.
+pub fn find_exact_duplicates(chunks: &[ChunkEntry]) -> Vec<DuplicateChunkGroup> {
+    let mut by_hash: HashMap<String, Vec<&ChunkEntry>> = HashMap::new();
+    for chunk in chunks {
+        let normalized = normalize(&chunk.content);
+        if normalized.len() < MIN_DUPLICATE_CHUNK_CHARS {
+            continue;
+        }
+        let hash = format!("{:x}", Sha256::digest(normalized.as_bytes()));
+        by_hash.entry(hash).or_default().push(chunk);
+    }
+
+    let mut groups: Vec<DuplicateChunkGroup> = by_hash
+        .into_iter()
+        .filter(|(_, entries)| entries.len() > 1)
+        .map(|(content_hash, entries)| DuplicateChunkGroup {
+            content_hash,
+            preview: entries[0].content.trim().chars().take(PREVIEW_CHARS).collect(),
+            occurrences: entries
+                .iter()
+                .map(|c| ChunkLocation {
+                    chunk_id: c.chunk_id.clone(),
+                    document_id: c.document_id.clone(),
+                    title: c.title.clone(),
+                })
+                .collect(),
+        })
+        .collect();
+    groups.sort_by(|a, b| b.occurrences.len().cmp(&a.occurrences.len()).then(a.preview.cmp(&b.preview)));
+    groups
+}
+
+fn unit(v: &[f32]) -> Option<Vec<f32>> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    (norm > 0.0).then(|| v.iter().map(|x| x / norm).collect())
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Matched chunks and their best similarities for one document pair, `a` listed first.
+#[derive(Default)]
+struct PairMatches {
+    matched_a: usize,
+    matched_b: usize,
+    similarities: Vec<f32>,
+}
+
+/// Document pairs where at least `min_overlap` of either document's chunks have a
+/// counterpart in the other with cosine similarity of at least `threshold`.
+///
+/// Only each chunk's `neighbors` are compared, so the cost grows with the chunk count
+/// rather than with its square.
+pub fn find_near_duplicates(chunks: &[ChunkEntry], threshold: f32, min_overlap: f32) -> Vec<NearDuplicatePair> {
+    let by_id: HashMap<&str, &ChunkEntry> = chunks.iter().map(|c| (c.chunk_id.as_str(), c)).collect();
+    let vectors: HashMap<&str, Vec<f32>> = chunks
+        .iter()
+        .filter_map(|c| Some((c.chunk_id.as_str(), c.vector.as_deref().and_then(unit)?)))
+        .collect();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    let mut titles: HashMap<&str, &str> = HashMap::new();
+    let mut chunk_counts: HashMap<&str, usize> = HashMap::new();
+    for chunk in chunks.iter().filter(|c| vectors.contains_key(c.chunk_id.as_str())) {
+        let next = positions.len();
+        positions.entry(chunk.document_id.as_str()).or_insert(next);
+        titles.entry(chunk.document_id.as_str()).or_insert(chunk.title.as_str());
+        *chunk_counts.entry(chunk.document_id.as_str()).or_default() += 1;
+    }
+
+    // Best similarity of each chunk to any chunk of another document.
+    let mut best: HashMap<(&str, &str), f32> = HashMap::new();
+    for chunk in chunks {
+        let Some(vector) = vectors.get(chunk.chunk_id.as_str()) else {
+            continue;
+        };
+        for neighbor_id in &chunk.neighbors {
+            let (Some(neighbor), Some(other)) = (by_id.get(neighbor_id.as_str()), vectors.get(neighbor_id.as_str()))
+            else {
+                continue;
+            };
+            let similarity = dot(vector, other);
+            if neighbor.document_id == chunk.document_id || similarity < threshold {
+                continue;
+            }
+            // A match counts for both chunks; the index need not list it from both ends.
+            for (chunk_id, other_document) in [
+                (chunk.chunk_id.as_str(), neighbor.document_id.as_str()),
+                (neighbor.chunk_id.as_str(), chunk.document_id.as_str()),
+            ] {
+                let entry = best.entry((chunk_id, other_document)).or_insert(similarity);
+                *entry = entry.max(similarity);
+            }
+        }
+    }
+
+    let mut matches: HashMap<(&str, &str), PairMatches> = HashMap::new();
+    for ((chunk_id, other_document), similarity) in best {
+        let document = by_id[chunk_id].document_id.as_str();
+        let a_side = positions[document] < positions[other_document];
+        let key = if a_side { (document, other_document) } else { (other_document, document) };
+        let pair = matches.entry(key).or_default();
+        if a_side {
+            pair.matched_a += 1;
+        } else {
+            pair.matched_b += 1;
+        }
+        pair.similarities.push(similarity);
+    }
+
+    let mut keys: Vec<(&str, &str)> = matches.keys().copied().collect();
+    keys.sort_by_key(|(a, b)| (positions[a], positions[b]));
+
+    let mut pairs = Vec::new();
+    for (a, b) in keys {
+        let pair = &matches[&(a, b)];
+        let overlap_a = pair.matched_a as f32 / chunk_counts[a] as f32;
+        let overlap_b = pair.matched_b as f32 / chunk_counts[b] as f32;
+        if overlap_a.max(overlap_b) < min_overlap {
+            continue;
+        }
+        pairs.push(NearDuplicatePair {
+            document_a: a.to_string(),
+            title_a: titles[a].to_string(),
+            document_b: b.to_string(),
+            title_b: titles[b].to_string(),
+            overlap_a: (overlap_a * 1000.0).round() / 10.0,
+            overlap_b: (overlap_b * 1000.0).round() / 10.0,
+            similarity: pair.similarities.iter().sum::<f32>() / pair.similarities.len() as f32,
+        });
+    }
+    pairs.sort_by(|x, y| {
+        y.overlap_a
+            .max(y.overlap_b)
+            .partial_cmp(&x.overlap_a.max(x.overlap_b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(document_id: &str, index: usize, content: &str, vector: Vec<f32>) -> ChunkEntry {
+        ChunkEntry {
+            chunk_id: format!("{document_id}#{index}"),
+            document_id: document_id.to_string(),
+            title: document_id.to_uppercase(),
+            content: content.to_string(),
+            vector: Some(vector),
+            neighbors: Vec::new(),
+        }
+    }
+
+    /// Let every chunk see every other one, as a large enough index lookup would.
+    fn all_neighbors(mut chunks: Vec<ChunkEntry>) -> Vec<ChunkEntry> {
+        let ids: Vec<String> = chunks.iter().map(|c| c.chunk_id.clone()).collect();
+        chunks.iter_mut().for_each(|c| c.neighbors = ids.clone());
+        chunks
+    }
+
+    #[test]
+    fn groups_identical_chunks_ignoring_whitespace_and_case() {
+        let text = "Deployments run through the release pipeline on every merge to main.";
+        let chunks = vec![
+            chunk("a", 0, text, vec![1.0, 0.0]),
+            chunk("b", 3, &format!("  {}\n", text.to_uppercase()), vec![1.0, 0.0]),
+            chunk("c", 0, "---", vec![1.0, 0.0]),
+            chunk("d", 0, "---", vec![1.0, 0.0]),
+        ];
+        let groups = find_exact_duplicates(&chunks);
+        assert_eq!(groups.len(), 1, "short chunks are ignored");
+        let ids: Vec<_> = groups[0].occurrences.iter().map(|o| o.chunk_id.as_str()).collect();
+        assert_eq!(ids, vec!["a#0", "b#3"]);
+    }
+
+    #[test]
+    fn reports_pairs_with_overlap_percentages() {
+        let chunks = vec![
+            // "fork" copies two of the original's three chunks, nearly verbatim.
+            chunk("original", 0, "", vec![1.0, 0.0, 0.0]),
+            chunk("original", 1, "", vec![0.0, 1.0, 0.0]),
+            chunk("original", 2, "", vec![0.0, 0.0, 1.0]),
+            chunk("fork", 0, "", vec![0.99, 0.05, 0.0]),
+            chunk("fork", 1, "", vec![0.0, 1.0, 0.02]),
+            chunk("unrelated", 0, "", vec![0.6, 0.6, 0.5]),
+        ];
+        let pairs = find_near_duplicates(&all_neighbors(chunks), 0.95, 0.5);
+        assert_eq!(pairs.len(), 1);
+        let pair = &pairs[0];
+        assert_eq!((pair.document_a.as_str(), pair.document_b.as_str()), ("original", "fork"));
+        assert_eq!(pair.overlap_a, 66.7);
+        assert_eq!(pair.overlap_b, 100.0);
+        assert!(pair.similarity > 0.99);
+    }
+}
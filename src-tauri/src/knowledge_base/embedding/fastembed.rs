@@ -32,6 +32,7 @@ impl FastEmbedProvider {
             "multilingual-e5-base" => EmbeddingModel::MultilingualE5Base,
             "multilingual-e5-large" => EmbeddingModel::MultilingualE5Large,
             "paraphrase-multilingual-MiniLM-L12-v2" => EmbeddingModel::ParaphraseMLMiniLML12V2,
+            "paraphrase-multilingual-mpnet-base-v2" => EmbeddingModel::ParaphraseMLMpnetBaseV2,
             "bge-small-zh-v1.5" => EmbeddingModel::BGESmallZHV15,
             "bge-large-zh-v1.5" => EmbeddingModel::BGELargeZHV15,
             _ => EmbeddingModel::AllMiniLML6V2,
@@ -48,6 +49,7 @@ impl FastEmbedProvider {
             "multilingual-e5-base" => 768,
             "multilingual-e5-large" => 1024,
             "paraphrase-multilingual-MiniLM-L12-v2" => 384,
+            "paraphrase-multilingual-mpnet-base-v2" => 768,
             "bge-small-zh-v1.5" => 512,
             "bge-large-zh-v1.5" => 1024,
             _ => 384,
@@ -106,6 +108,7 @@ impl Embedder for FastEmbedProvider {
             "multilingual-e5-base" => "fastembed/multilingual-e5-base",
             "multilingual-e5-large" => "fastembed/multilingual-e5-large",
             "paraphrase-multilingual-MiniLM-L12-v2" => "fastembed/paraphrase-multilingual-MiniLM-L12-v2",
+            "paraphrase-multilingual-mpnet-base-v2" => "fastembed/paraphrase-multilingual-mpnet-base-v2",
             "bge-small-zh-v1.5" => "fastembed/bge-small-zh-v1.5",
             "bge-large-zh-v1.5" => "fastembed/bge-large-zh-v1.5",
             _ => "fastembed/all-MiniLM-L6-v2",
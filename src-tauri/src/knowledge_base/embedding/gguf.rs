@@ -0,0 +1,60 @@
+use std::path::Path;
+use std::sync::Arc;
+use async_trait::async_trait;
+
+use super::Embedder;
+use crate::llm::engine::{LlamaConfig, LlamaEngine};
+
+/// Local embedding provider backed by a GGUF embedding model (nomic-embed, bge, ...)
+/// loaded through llama.cpp. Models are managed in the same directory as chat models.
+pub struct GgufEmbedder {
+    engine: Arc<LlamaEngine>,
+    dims: usize,
+}
+
+impl GgufEmbedder {
+    /// Load the model and probe it once so a chat model is rejected up front
+    /// instead of failing on every chunk during indexing.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        // Embedding models are small; 2048 tokens covers the chunker's output with room to spare.
+        let config = LlamaConfig::default().ctx_size(2048);
+        let engine = LlamaEngine::load(path, config)
+            .map_err(|e| format!("Failed to load GGUF model {:?}: {}", path, e))?;
+        engine
+            .embed("dimension probe")
+            .map_err(|e| format!("{:?} is not an embedding model: {}", path, e))?;
+
+        let dims = engine.n_embd();
+        Ok(Self {
+            engine: Arc::new(engine),
+            dims,
+        })
+    }
+}
+
+#[async_trait]
+impl Embedder for GgufEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        let engine = self.engine.clone();
+        let text = text.to_string();
+        tokio::task::spawn_blocking(move || engine.embed(&text).map_err(|e| e.to_string()))
+            .await
+            .map_err(|e| format!("Embedding task panicked: {}", e))?
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let engine = self.engine.clone();
+        let texts = texts.to_vec();
+        tokio::task::spawn_blocking(move || engine.embed_batch(&texts).map_err(|e| e.to_string()))
+            .await
+            .map_err(|e| format!("Embedding task panicked: {}", e))?
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dims
+    }
+
+    fn name(&self) -> &'static str {
+        "gguf"
+    }
+}
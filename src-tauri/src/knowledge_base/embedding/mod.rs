@@ -3,7 +3,9 @@ use async_trait::async_trait;
 
 pub mod chunker;
 pub mod fastembed;
+pub mod gguf;
 pub mod openai;
+pub mod openai_compatible;
 
 /// Provider-agnostic embedding interface.
 /// Any type implementing this trait can be swapped in as the active provider.
@@ -11,6 +13,14 @@ pub mod openai;
 pub trait Embedder: Send + Sync {
     /// Embed a single piece of text and return a float vector.
     async fn embed(&self, text: &str) -> Result<Vec<f32>, String>;
+    /// Embed several texts in order. Providers with per-call setup cost override this.
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let mut results = Vec::with_capacity(texts.len());
+        for text in texts {
+            results.push(self.embed(text).await?);
+        }
+        Ok(results)
+    }
     /// Number of dimensions produced by this provider (used when creating the vec0 table).
     fn dimensions(&self) -> usize;
     /// Human-readable provider name for logging / UI.
@@ -95,7 +105,7 @@ pub fn is_model_downloaded(cache_dir: &std::path::Path, model_name: &str) -> boo
 
 /// Select and initialise the active embedder provider with defaults.
 pub fn init_embedder(cache_dir: Option<PathBuf>) -> Result<Box<dyn Embedder>, String> {
-    init_embedder_with_config(cache_dir, "local", "all-MiniLM-L6-v2", None, true)
+    init_embedder_with_config(cache_dir, "local", "all-MiniLM-L6-v2", None, None, true)
 }
 
 /// Select and initialise the active embedder provider with explicit configuration.
@@ -104,9 +114,20 @@ pub fn init_embedder_with_config(
     model_type: &str,
     model_name: &str,
     openai_key: Option<String>,
+    endpoint: Option<String>,
     force_download: bool,
 ) -> Result<Box<dyn Embedder>, String> {
-    if model_type == "remote" {
+    if model_type == "server" {
+        // Any OpenAI-compatible `/v1/embeddings` server (Ollama, LM Studio, ...).
+        // The key is optional here and only sent when set.
+        let endpoint = endpoint.unwrap_or_default();
+        let provider = openai_compatible::OpenAiCompatibleProvider::connect(&endpoint, model_name, openai_key)?;
+        Ok(Box::new(provider))
+    } else if model_type == "gguf" {
+        // `model_name` is the resolved path of a .gguf file in the LLM models directory.
+        let provider = gguf::GgufEmbedder::load(std::path::Path::new(model_name))?;
+        Ok(Box::new(provider))
+    } else if model_type == "remote" {
         let key = openai_key.unwrap_or_default();
         if key.is_empty() {
             return Err("OpenAI API Key is required for remote models".to_string());
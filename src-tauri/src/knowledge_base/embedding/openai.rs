@@ -40,6 +40,20 @@ impl OpenAiProvider {
     }
 }
 
+/// Extract `data[0].embedding` from an OpenAI-style `/v1/embeddings` response.
+pub(super) fn parse_embedding_response(json: &serde_json::Value) -> Result<Vec<f32>, String> {
+    json["data"][0]["embedding"]
+        .as_array()
+        .ok_or_else(|| "Missing `data[0].embedding` in embedding response".to_string())?
+        .iter()
+        .map(|v| {
+            v.as_f64()
+                .ok_or_else(|| "Non-numeric value in embedding array".to_string())
+                .map(|f| f as f32)
+        })
+        .collect()
+}
+
 #[async_trait]
 impl Embedder for OpenAiProvider {
     async fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
@@ -67,18 +81,7 @@ impl Embedder for OpenAiProvider {
             .await
             .map_err(|e| format!("Failed to parse OpenAI response: {e}"))?;
 
-        let embedding = json["data"][0]["embedding"]
-            .as_array()
-            .ok_or_else(|| "Missing `data[0].embedding` in OpenAI response".to_string())?
-            .iter()
-            .map(|v| {
-                v.as_f64()
-                    .ok_or_else(|| "Non-numeric value in embedding array".to_string())
-                    .map(|f| f as f32)
-            })
-            .collect::<Result<Vec<f32>, _>>()?;
-
-        Ok(embedding)
+        parse_embedding_response(&json)
     }
 
     fn dimensions(&self) -> usize {
@@ -0,0 +1,207 @@
+// Embedding provider for self-hosted servers that expose the OpenAI `/v1/embeddings`
+// API, such as Ollama, LM Studio, llama.cpp server, vLLM or LocalAI.
+//
+// Integration notes:
+// - The base URL may be given with or without the trailing `/v1`.
+// - The API key is optional; most local servers ignore it.
+// - Dimensions are not known up front, so `connect()` embeds a probe string once and
+//   uses the length of the returned vector to size the vec0 table.
+
+use super::Embedder;
+use async_trait::async_trait;
+
+const PROBE_TEXT: &str = "dimension probe";
+
+pub struct OpenAiCompatibleProvider {
+    client: reqwest::Client,
+    /// Server root without the `/v1` suffix, e.g. `http://localhost:11434`.
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    dimensions: usize,
+}
+
+/// Strip trailing slashes and a trailing `/v1` so both spellings of the URL work.
+pub fn normalize_base_url(base_url: &str) -> String {
+    let trimmed = base_url.trim().trim_end_matches('/');
+    trimmed
+        .strip_suffix("/v1")
+        .unwrap_or(trimmed)
+        .trim_end_matches('/')
+        .to_string()
+}
+
+fn embeddings_url(base_url: &str) -> String {
+    format!("{base_url}/v1/embeddings")
+}
+
+fn request_body(model: &str, text: &str) -> serde_json::Value {
+    serde_json::json!({
+        "input": text,
+        "model": model,
+    })
+}
+
+impl OpenAiCompatibleProvider {
+    /// Connect to the server and probe the model's dimension count.
+    ///
+    /// This blocks the calling thread on one HTTP round trip. The request runs on a
+    /// separate thread so it is safe to call from both sync setup code and async commands.
+    pub fn connect(base_url: &str, model: &str, api_key: Option<String>) -> Result<Self, String> {
+        if model.trim().is_empty() {
+            return Err("An embedding model name is required".to_string());
+        }
+        let base_url = normalize_base_url(base_url);
+        if base_url.is_empty() {
+            return Err("An embedding server URL is required".to_string());
+        }
+
+        let dimensions = {
+            let url = embeddings_url(&base_url);
+            let body = request_body(model, PROBE_TEXT);
+            let api_key = api_key.clone();
+            std::thread::spawn(move || -> Result<usize, String> {
+                let client = reqwest::blocking::Client::builder()
+                    .timeout(std::time::Duration::from_secs(30))
+                    .build()
+                    .map_err(|e| e.to_string())?;
+                let mut request = client.post(&url).json(&body);
+                if let Some(key) = api_key.as_deref().filter(|k| !k.is_empty()) {
+                    request = request.bearer_auth(key);
+                }
+                let response = request
+                    .send()
+                    .map_err(|e| format!("Could not reach embedding server at {url}: {e}"))?;
+                if !response.status().is_success() {
+                    let status = response.status();
+                    let body = response.text().unwrap_or_default();
+                    return Err(format!("Embedding server error {status}: {body}"));
+                }
+                let json: serde_json::Value = response
+                    .json()
+                    .map_err(|e| format!("Failed to parse embedding response: {e}"))?;
+                Ok(super::openai::parse_embedding_response(&json)?.len())
+            })
+            .join()
+            .map_err(|_| "Embedding server probe panicked".to_string())??
+        };
+
+        if dimensions == 0 {
+            return Err(format!("Model `{model}` returned an empty embedding"));
+        }
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            base_url,
+            model: model.to_string(),
+            api_key,
+            dimensions,
+        })
+    }
+}
+
+#[async_trait]
+impl Embedder for OpenAiCompatibleProvider {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        let url = embeddings_url(&self.base_url);
+        let mut request = self.client.post(&url).json(&request_body(&self.model, text));
+        if let Some(key) = self.api_key.as_deref().filter(|k| !k.is_empty()) {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Embedding request to {url} failed: {e}"))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Embedding server error {status}: {body}"));
+        }
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse embedding response: {e}"))?;
+
+        let embedding = super::openai::parse_embedding_response(&json)?;
+        if embedding.len() != self.dimensions {
+            return Err(format!(
+                "Embedding server returned {} dimensions, expected {}. Was the model changed on the server?",
+                embedding.len(),
+                self.dimensions
+            ));
+        }
+        Ok(embedding)
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn name(&self) -> &'static str {
+        "openai-compatible"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_base_url_variants() {
+        assert_eq!(normalize_base_url("http://localhost:11434"), "http://localhost:11434");
+        assert_eq!(normalize_base_url("http://localhost:11434/"), "http://localhost:11434");
+        assert_eq!(normalize_base_url("http://localhost:1234/v1"), "http://localhost:1234");
+        assert_eq!(normalize_base_url(" http://host/v1/ "), "http://host");
+    }
+
+    #[test]
+    fn probes_dimensions_from_a_local_server() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // Read headers, then exactly Content-Length bytes of body.
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            let header_end = loop {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let headers = String::from_utf8_lossy(&request[..header_end]).to_lowercase();
+            let content_length: usize = headers
+                .lines()
+                .find_map(|l| l.strip_prefix("content-length:"))
+                .map(|v| v.trim().parse().unwrap())
+                .unwrap_or(0);
+            while request.len() < header_end + content_length {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+            }
+            let request = String::from_utf8_lossy(&request).to_string();
+            let body = r#"{"data":[{"embedding":[0.1,0.2,0.3,0.4,0.5]}]}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            request
+        });
+
+        let provider =
+            OpenAiCompatibleProvider::connect(&format!("http://{address}/v1/"), "nomic-embed-text", None).unwrap();
+        assert_eq!(provider.dimensions(), 5);
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /v1/embeddings "));
+        assert!(request.contains("nomic-embed-text"));
+    }
+}
@@ -0,0 +1,247 @@
+//! Optional indexing stage that turns images referenced from markdown into searchable
+//! text. Raster images (screenshots, PNG diagram exports) are OCR'd on the CPU with
+//! `ocrs`; SVG exports already carry their labels as `<text>` elements, which are read
+//! directly. Results are cached per image by content hash in `image_extractions`.
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+const DETECTION_MODEL: &str = "text-detection.rten";
+const RECOGNITION_MODEL: &str = "text-recognition.rten";
+const MODELS_BASE_URL: &str = "https://ocrs-models.s3-accelerate.amazonaws.com";
+
+/// Raster formats handed to the OCR model.
+const RASTER_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp"];
+
+/// Extracts text from a single image file. Implementations are blocking and are
+/// called from `spawn_blocking`.
+pub trait ImageTextExtractor: Send + Sync {
+    fn extract(&self, path: &Path) -> Result<String, String>;
+    /// Stored with cached results so switching extractors re-processes images.
+    fn name(&self) -> &'static str;
+}
+
+/// Absolute path of an image referenced as `reference` from a file in `base_dir`, with
+/// `.` and `..` resolved lexically so every spelling of one image maps to one path.
+pub fn resolve_image_path(base_dir: &Path, reference: &str) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in base_dir.join(reference).components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Whether `path` has an extension this stage knows how to read.
+pub fn is_supported_image(path: &Path) -> bool {
+    let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+        return false;
+    };
+    let ext = ext.to_ascii_lowercase();
+    ext == "svg" || RASTER_EXTENSIONS.contains(&ext.as_str())
+}
+
+/// Local OCR backed by the `ocrs` detection and recognition models (~12 MB total),
+/// downloaded into the app cache directory on first use.
+pub struct OcrsExtractor {
+    engine: ocrs::OcrEngine,
+}
+
+impl OcrsExtractor {
+    /// Load the models from `cache_dir/ocrs`, downloading any that are missing.
+    pub fn load(cache_dir: &Path) -> Result<Self, String> {
+        let models_dir = cache_dir.join("ocrs");
+        std::fs::create_dir_all(&models_dir)
+            .map_err(|e| format!("Failed to create OCR model directory: {e}"))?;
+
+        let detection_path = ensure_model(&models_dir, DETECTION_MODEL)?;
+        let recognition_path = ensure_model(&models_dir, RECOGNITION_MODEL)?;
+
+        let detection_model = rten::Model::load_file(&detection_path)
+            .map_err(|e| format!("Failed to load OCR detection model: {e}"))?;
+        let recognition_model = rten::Model::load_file(&recognition_path)
+            .map_err(|e| format!("Failed to load OCR recognition model: {e}"))?;
+
+        let engine = ocrs::OcrEngine::new(ocrs::OcrEngineParams {
+            detection_model: Some(detection_model),
+            recognition_model: Some(recognition_model),
+            ..Default::default()
+        })
+        .map_err(|e| format!("Failed to initialise OCR engine: {e}"))?;
+
+        Ok(Self { engine })
+    }
+
+    fn ocr(&self, path: &Path) -> Result<String, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("Failed to decode image {path:?}: {e}"))?
+            .into_rgb8();
+        let source = ocrs::ImageSource::from_bytes(image.as_raw(), image.dimensions())
+            .map_err(|e| format!("Unsupported image layout {path:?}: {e}"))?;
+        let input = self
+            .engine
+            .prepare_input(source)
+            .map_err(|e| format!("OCR preprocessing failed: {e}"))?;
+        self.engine
+            .get_text(&input)
+            .map_err(|e| format!("OCR failed for {path:?}: {e}"))
+    }
+}
+
+impl ImageTextExtractor for OcrsExtractor {
+    fn extract(&self, path: &Path) -> Result<String, String> {
+        if has_extension(path, "svg") {
+            let svg = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {path:?}: {e}"))?;
+            return Ok(extract_svg_text(&svg));
+        }
+        self.ocr(path)
+    }
+
+    fn name(&self) -> &'static str {
+        "ocrs"
+    }
+}
+
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
+}
+
+fn ensure_model(models_dir: &Path, filename: &str) -> Result<PathBuf, String> {
+    let path = models_dir.join(filename);
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let url = format!("{MODELS_BASE_URL}/{filename}");
+    println!("[knowledge_base][ocr] Downloading {url}");
+    let response = reqwest::blocking::get(&url)
+        .map_err(|e| format!("Failed to download OCR model {filename}: {e}"))?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {} while downloading OCR model {filename}", response.status()));
+    }
+    let bytes = response
+        .bytes()
+        .map_err(|e| format!("Failed to read OCR model {filename}: {e}"))?;
+
+    // Write to a temporary name first so an interrupted download is never loaded.
+    let partial = models_dir.join(format!("{filename}.part"));
+    std::fs::write(&partial, &bytes).map_err(|e| format!("Failed to save OCR model: {e}"))?;
+    std::fs::rename(&partial, &path).map_err(|e| format!("Failed to save OCR model: {e}"))?;
+    Ok(path)
+}
+
+/// Collect the contents of `<text>`/`<tspan>` elements, one label per line.
+/// Diagram tools (draw.io, mermaid, PlantUML) export every box label this way.
+pub fn extract_svg_text(svg: &str) -> String {
+    let mut labels = Vec::new();
+    let mut rest = svg;
+    while let Some(start) = rest.find("<text") {
+        rest = &rest[start..];
+        let Some(end) = rest.find("</text>") else {
+            break;
+        };
+        let element = &rest[..end];
+        rest = &rest[end + "</text>".len()..];
+
+        // Drop every tag inside the element and keep the character data.
+        let mut label = String::new();
+        let mut in_tag = false;
+        for ch in element.chars() {
+            match ch {
+                '<' => in_tag = true,
+                '>' => {
+                    in_tag = false;
+                    label.push(' ');
+                }
+                _ if !in_tag => label.push(ch),
+                _ => {}
+            }
+        }
+        let label = decode_xml_entities(&label.split_whitespace().collect::<Vec<_>>().join(" "));
+        if !label.is_empty() {
+            labels.push(label);
+        }
+    }
+    labels.join("\n")
+}
+
+fn decode_xml_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// SHA-256 hex of the image bytes; the cache key together with the extractor name.
+pub fn image_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Text previously extracted from an image with the same content and extractor.
+pub fn cached_text(
+    conn: &Connection,
+    image_path: &str,
+    content_hash: &str,
+    extractor: &str,
+) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT text FROM image_extractions
+         WHERE image_path = ?1 AND content_hash = ?2 AND extractor = ?3",
+        params![image_path, content_hash, extractor],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("Failed to read image extraction cache: {e}"))
+}
+
+pub fn store_text(
+    conn: &Connection,
+    image_path: &str,
+    content_hash: &str,
+    extractor: &str,
+    text: &str,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO image_extractions (image_path, content_hash, extractor, text, extracted_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(image_path) DO UPDATE SET
+             content_hash = excluded.content_hash,
+             extractor = excluded.extractor,
+             text = excluded.text,
+             extracted_at = excluded.extracted_at",
+        params![image_path, content_hash, extractor, text, chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)],
+    )
+    .map_err(|e| format!("Failed to cache image extraction: {e}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_labels_from_svg_text_elements() {
+        let svg = r#"<svg><g><rect/><text x="1" y="2">Payment <tspan>gateway</tspan></text></g>
+            <text class="label">Ledger &amp; audit</text><text>  </text></svg>"#;
+        assert_eq!(extract_svg_text(svg), "Payment gateway\nLedger & audit");
+    }
+
+    #[test]
+    fn recognises_supported_extensions() {
+        assert!(is_supported_image(Path::new("docs/arch.PNG")));
+        assert!(is_supported_image(Path::new("flow.svg")));
+        assert!(!is_supported_image(Path::new("notes.md")));
+        assert!(!is_supported_image(Path::new("diagram")));
+    }
+}
@@ -0,0 +1,195 @@
+//! Lightweight per-chunk language detection, so mixed-language knowledge bases (for
+//! example English and Vietnamese docs side by side) can filter or boost search results
+//! by language. Non-Latin scripts are identified by their Unicode blocks, Vietnamese by
+//! its tone-marked letters, and other Latin languages by common function words.
+
+use serde::{Deserialize, Serialize};
+
+/// Chunks with fewer letters than this (code, tables, link lists) stay undetected.
+const MIN_LETTERS: usize = 20;
+
+/// Share of Latin letters that must be Vietnamese-specific for a chunk to count as `vi`.
+const VIETNAMESE_LETTER_SHARE: f32 = 0.04;
+
+/// Function words needed before a Latin-script chunk is assigned a language.
+const MIN_STOPWORD_HITS: usize = 2;
+
+/// Score multiplier for results in the preferred language when boosting.
+pub const LANGUAGE_BOOST: f32 = 1.5;
+
+const STOPWORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "of", "to", "is", "in", "that", "for", "with", "are", "this", "be", "it", "on", "from", "you", "not", "or"]),
+    ("fr", &["le", "la", "les", "et", "des", "est", "une", "du", "que", "pour", "dans", "pas", "sur", "avec", "qui", "au", "ce"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "mit", "zu", "den", "von", "ein", "eine", "auf", "für", "sich", "auch"]),
+    ("es", &["el", "los", "las", "y", "es", "que", "del", "una", "por", "con", "para", "se", "no", "como", "más"]),
+    ("pt", &["o", "os", "as", "e", "é", "que", "do", "da", "em", "um", "uma", "não", "para", "com", "dos"]),
+    ("it", &["il", "lo", "gli", "e", "è", "che", "di", "della", "per", "un", "una", "non", "con", "sono", "nel"]),
+    ("nl", &["de", "het", "een", "en", "is", "van", "dat", "niet", "op", "met", "voor", "zijn", "ook", "er"]),
+];
+
+/// How a search treats a language preference.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LanguageMode {
+    /// Only return chunks and documents in the language.
+    Filter,
+    /// Rank results in the language higher, keep the rest.
+    #[default]
+    Boost,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LanguagePreference {
+    /// ISO 639-1 code, e.g. `en` or `vi`.
+    pub language: String,
+    pub mode: LanguageMode,
+}
+
+impl LanguagePreference {
+    /// Accepts codes such as `vi`, `VI` or `vi-VN`; `None` for an empty code.
+    pub fn new(language: &str, mode: LanguageMode) -> Option<Self> {
+        let language = normalize_code(language)?;
+        Some(Self { language, mode })
+    }
+}
+
+/// Primary subtag of a language code, lowercased: `en-US` becomes `en`.
+pub fn normalize_code(code: &str) -> Option<String> {
+    let primary = code.trim().split(['-', '_']).next()?.to_lowercase();
+    (!primary.is_empty()).then_some(primary)
+}
+
+#[derive(Default)]
+struct ScriptCounts {
+    latin: usize,
+    vietnamese: usize,
+    cyrillic: usize,
+    ukrainian: usize,
+    greek: usize,
+    arabic: usize,
+    hebrew: usize,
+    devanagari: usize,
+    thai: usize,
+    hangul: usize,
+    kana: usize,
+    han: usize,
+}
+
+fn is_vietnamese_letter(c: char) -> bool {
+    matches!(c, 'đ' | 'Đ' | 'ơ' | 'Ơ' | 'ư' | 'Ư' | 'ă' | 'Ă') || ('\u{1EA0}'..='\u{1EF9}').contains(&c)
+}
+
+fn count_scripts(text: &str) -> ScriptCounts {
+    let mut counts = ScriptCounts::default();
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        match c {
+            'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}' => {
+                counts.latin += 1;
+                if is_vietnamese_letter(c) {
+                    counts.vietnamese += 1;
+                }
+            }
+            '\u{0400}'..='\u{04FF}' => {
+                counts.cyrillic += 1;
+                if matches!(c, 'і' | 'ї' | 'є' | 'ґ' | 'І' | 'Ї' | 'Є' | 'Ґ') {
+                    counts.ukrainian += 1;
+                }
+            }
+            '\u{0370}'..='\u{03FF}' => counts.greek += 1,
+            '\u{0600}'..='\u{06FF}' => counts.arabic += 1,
+            '\u{0590}'..='\u{05FF}' => counts.hebrew += 1,
+            '\u{0900}'..='\u{097F}' => counts.devanagari += 1,
+            '\u{0E00}'..='\u{0E7F}' => counts.thai += 1,
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => counts.hangul += 1,
+            '\u{3040}'..='\u{30FF}' => counts.kana += 1,
+            '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => counts.han += 1,
+            _ => {}
+        }
+    }
+    counts
+}
+
+/// Best guess at the language of `text` as an ISO 639-1 code, or `None` when there is
+/// too little prose to tell.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let counts = count_scripts(text);
+    // Japanese mixes kana with kanji, so any meaningful share of kana decides it.
+    let cjk = counts.hangul + counts.kana + counts.han;
+    let scripts = [
+        (counts.latin, "latin"),
+        (counts.cyrillic, "cyrillic"),
+        (counts.greek, "el"),
+        (counts.arabic, "ar"),
+        (counts.hebrew, "he"),
+        (counts.devanagari, "hi"),
+        (counts.thai, "th"),
+        (cjk, "cjk"),
+    ];
+    let (letters, script) = scripts.into_iter().max_by_key(|(count, _)| *count)?;
+    // Ideographs carry a word each, so far fewer of them make a sentence.
+    let min_letters = if script == "cjk" { MIN_LETTERS / 4 } else { MIN_LETTERS };
+    if letters < min_letters {
+        return None;
+    }
+
+    match script {
+        "latin" => {
+            if counts.vietnamese as f32 / counts.latin as f32 >= VIETNAMESE_LETTER_SHARE {
+                Some("vi")
+            } else {
+                detect_latin(text)
+            }
+        }
+        "cyrillic" if counts.ukrainian > 0 => Some("uk"),
+        "cyrillic" => Some("ru"),
+        "cjk" if counts.kana * 10 >= cjk => Some("ja"),
+        "cjk" if counts.hangul >= counts.han => Some("ko"),
+        "cjk" => Some("zh"),
+        other => Some(other),
+    }
+}
+
+fn detect_latin(text: &str) -> Option<&'static str> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    STOPWORDS
+        .iter()
+        .map(|(code, stopwords)| {
+            let hits = words.iter().filter(|w| stopwords.contains(&w.as_str())).count();
+            (hits, *code)
+        })
+        .filter(|(hits, _)| *hits >= MIN_STOPWORD_HITS)
+        // Earlier entries win ties, so English is preferred for ambiguous text.
+        .fold(None, |best: Option<(usize, &str)>, candidate| match best {
+            Some(best) if best.0 >= candidate.0 => Some(best),
+            _ => Some(candidate),
+        })
+        .map(|(_, code)| code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_languages_of_mixed_docs() {
+        assert_eq!(detect("The deploy pipeline runs on every merge to the main branch."), Some("en"));
+        assert_eq!(detect("Quy trình triển khai chạy mỗi khi hợp nhất vào nhánh chính."), Some("vi"));
+        assert_eq!(detect("Der Build läuft bei jedem Merge und ist nicht optional."), Some("de"));
+        assert_eq!(detect("Развёртывание запускается при каждом слиянии в основную ветку."), Some("ru"));
+        assert_eq!(detect("デプロイはメインブランチへのマージごとに実行されます。"), Some("ja"));
+        assert_eq!(detect("每次合并到主分支时都会运行部署流程。"), Some("zh"));
+        assert_eq!(detect("fn main() { x = 1; }"), None);
+    }
+
+    #[test]
+    fn normalizes_language_codes() {
+        assert_eq!(normalize_code(" vi-VN "), Some("vi".to_string()));
+        assert_eq!(normalize_code("EN_us"), Some("en".to_string()));
+        assert_eq!(normalize_code(""), None);
+    }
+}
@@ -0,0 +1,119 @@
+//! Cross-process leases stored in `kb_meta`, so that work which must not run twice
+//! (indexing workspaces from the filesystem) has a single owner even when the desktop
+//! app and several MCP servers share one database. A holder renews its lease well
+//! within `LEASE_TTL`; if it exits or hangs, the lease expires and another process
+//! may take over.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+
+/// Lease held by whichever process keeps file-backed documents in sync with disk.
+pub const INDEXER_LEASE: &str = "lease:indexer";
+
+/// How long a lease stays valid without being renewed.
+pub const LEASE_TTL: Duration = Duration::from_secs(30);
+
+/// How often holders renew, and followers retry.
+pub const LEASE_RENEW_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Lease {
+    holder: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// A holder id unique to this process, e.g. `mcp:4242:5f0c…`.
+pub fn holder_id(role: &str) -> String {
+    format!("{role}:{}:{}", std::process::id(), uuid::Uuid::new_v4())
+}
+
+fn read_lease(conn: &Connection, name: &str) -> Result<Option<Lease>, String> {
+    let value: Option<String> = conn
+        .query_row("SELECT value FROM kb_meta WHERE key = ?1", params![name], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to read lease {name}: {e}"))?;
+    // An unreadable lease is treated as expired rather than blocking everyone forever.
+    Ok(value.and_then(|value| serde_json::from_str(&value).ok()))
+}
+
+fn write_lease(conn: &Connection, name: &str, holder: &str, ttl: Duration) -> Result<(), String> {
+    let lease = Lease {
+        holder: holder.to_string(),
+        expires_at: Utc::now() + chrono::Duration::from_std(ttl).map_err(|e| e.to_string())?,
+    };
+    let value = serde_json::to_string(&lease).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO kb_meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![name, value],
+    )
+    .map_err(|e| format!("Failed to write lease {name}: {e}"))?;
+    Ok(())
+}
+
+/// Take or renew `name` for `holder`. Returns `false` while another holder's lease is
+/// still live. The check and the write happen in one `IMMEDIATE` transaction, so two
+/// processes racing for a free lease cannot both win.
+pub fn try_acquire(conn: &mut Connection, name: &str, holder: &str, ttl: Duration) -> Result<bool, String> {
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start lease transaction: {e}"))?;
+    if let Some(lease) = read_lease(&tx, name)? {
+        if lease.holder != holder && lease.expires_at > Utc::now() {
+            return Ok(false);
+        }
+    }
+    write_lease(&tx, name, holder, ttl)?;
+    tx.commit().map_err(|e| format!("Failed to commit lease: {e}"))?;
+    Ok(true)
+}
+
+/// Take `name` for `holder` whoever holds it now. The desktop app uses this: while it
+/// runs it is the primary writer, and other holders notice on their next renewal.
+pub fn claim(conn: &Connection, name: &str, holder: &str, ttl: Duration) -> Result<(), String> {
+    write_lease(conn, name, holder, ttl)
+}
+
+/// Give up `name` if `holder` still holds it, so a successor need not wait for expiry.
+pub fn release(conn: &Connection, name: &str, holder: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM kb_meta WHERE key = ?1 AND json_extract(value, '$.holder') = ?2",
+        params![name, holder],
+    )
+    .map_err(|e| format!("Failed to release lease {name}: {e}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE kb_meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);")
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn one_holder_at_a_time_until_expiry_or_release() {
+        let mut conn = meta_db();
+        assert!(try_acquire(&mut conn, INDEXER_LEASE, "mcp:a", LEASE_TTL).unwrap());
+        assert!(try_acquire(&mut conn, INDEXER_LEASE, "mcp:a", LEASE_TTL).unwrap(), "holder renews");
+        assert!(!try_acquire(&mut conn, INDEXER_LEASE, "mcp:b", LEASE_TTL).unwrap());
+
+        release(&conn, INDEXER_LEASE, "mcp:b").unwrap();
+        assert!(!try_acquire(&mut conn, INDEXER_LEASE, "mcp:b", LEASE_TTL).unwrap(), "only the holder releases");
+        release(&conn, INDEXER_LEASE, "mcp:a").unwrap();
+        assert!(try_acquire(&mut conn, INDEXER_LEASE, "mcp:b", Duration::ZERO).unwrap());
+
+        // b's lease expired immediately, so a may take it back.
+        assert!(try_acquire(&mut conn, INDEXER_LEASE, "mcp:a", LEASE_TTL).unwrap());
+        claim(&conn, INDEXER_LEASE, "app:c", LEASE_TTL).unwrap();
+        assert!(!try_acquire(&mut conn, INDEXER_LEASE, "mcp:a", LEASE_TTL).unwrap(), "the app preempts");
+    }
+}
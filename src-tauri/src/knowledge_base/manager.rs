@@ -1,11 +1,21 @@
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use rusqlite::{params, Connection};
+use tokio::sync::{OwnedMutexGuard, RwLock};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::Serialize;
 
+use crate::knowledge_base::duplicates::{self, ChunkEntry, DuplicateReport};
 use crate::knowledge_base::embedding::{Embedder, chunker::{chunk_text, ChunkOptions}};
-use crate::knowledge_base::parser::{extract_metadata, split_markdown_into_sections};
+use crate::knowledge_base::image_extractor::{self, ImageTextExtractor};
+use crate::knowledge_base::language::{self, LanguageMode, LanguagePreference};
+use crate::knowledge_base::parser::{extract_image_refs, extract_metadata, split_markdown_into_sections, ImageRef};
+use crate::knowledge_base::query_expansion::{self, QueryExpansion, TextGenerator};
+use crate::knowledge_base::revisions::{
+    content_hash, diff_lines, DiffLineKind, RevisionDiff, RevisionRecord, MAX_REVISIONS_PER_DOCUMENT,
+};
+use crate::knowledge_base::summaries;
+use crate::knowledge_base::vector_index::{self, VectorQuantization};
 
 #[derive(Serialize, Clone, Debug)]
 pub struct HybridSearchResult {
@@ -16,6 +26,14 @@ pub struct HybridSearchResult {
     pub score: f32,
     #[serde(rename = "matchedChunks")]
     pub matched_chunks: Vec<String>,
+    /// LLM-written summary, when one is up to date with the document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// Most common detected language among the document's chunks (ISO 639-1).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -30,6 +48,15 @@ pub struct GraphDocumentRecord {
     pub id: String,
     pub title: String,
     pub content: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<String>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<String>,
+    /// LLM-written summary, when one is up to date with `content`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -43,6 +70,31 @@ pub struct GraphEdgeRecord {
     pub edge_type: Option<String>,
 }
 
+/// A document on the other end of an edge.
+#[derive(Serialize, Clone, Debug)]
+pub struct LinkedDocumentRecord {
+    pub id: String,
+    pub title: String,
+    #[serde(rename = "edgeType")]
+    pub edge_type: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct NeighborDocumentRecord {
+    pub id: String,
+    pub title: String,
+    /// Number of edges between this document and the root.
+    pub hops: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GraphNeighborhoodRecord {
+    #[serde(rename = "rootId")]
+    pub root_id: String,
+    pub documents: Vec<NeighborDocumentRecord>,
+    pub edges: Vec<GraphEdgeRecord>,
+}
+
 #[derive(Serialize, Clone, Debug)]
 pub struct ProjectGraphRecord {
     #[serde(rename = "groupId")]
@@ -53,14 +105,378 @@ pub struct ProjectGraphRecord {
     pub edges: Vec<GraphEdgeRecord>,
 }
 
+#[derive(Serialize, Clone, Debug)]
+pub struct GroupRecord {
+    pub id: String,
+    pub title: String,
+    #[serde(rename = "parentId")]
+    pub parent_id: Option<String>,
+    #[serde(rename = "documentCount")]
+    pub document_count: i64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TagRecord {
+    pub tag: String,
+    #[serde(rename = "documentCount")]
+    pub document_count: i64,
+}
+
+/// One section of a markdown file stored as its own document.
+#[derive(Serialize, Clone, Debug)]
+pub struct DocumentSectionRecord {
+    pub id: String,
+    /// Slug after `#section:` in the id, e.g. `installation`.
+    pub anchor: String,
+    pub title: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DeleteGroupResult {
+    #[serde(rename = "deletedGroups")]
+    pub deleted_groups: usize,
+    #[serde(rename = "deletedDocuments")]
+    pub deleted_documents: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RecentDocumentRecord {
+    pub id: String,
+    pub title: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<String>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DocumentChangeRecord {
+    pub seq: i64,
+    #[serde(rename = "documentId")]
+    pub document_id: String,
+    /// `"upsert"` or `"delete"`.
+    #[serde(rename = "changeType")]
+    pub change_type: String,
+    #[serde(rename = "changedAt")]
+    pub changed_at: String,
+    /// Current title; `None` for deleted documents.
+    pub title: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ChangeFeedPage {
+    pub changes: Vec<DocumentChangeRecord>,
+    /// Pass back as `cursor` to continue from the last returned change.
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: i64,
+    #[serde(rename = "hasMore")]
+    pub has_more: bool,
+}
+
+/// Recursive CTE expanding a JSON array of group ids (bound as a single parameter)
+/// into those groups plus all of their descendants. Prepend to queries that need
+/// to scope results to a set of workspaces.
+const GROUP_SCOPE_CTE: &str = "
+    scope(id) AS (
+        SELECT value FROM json_each(?{param})
+        UNION
+        SELECT g.id FROM groups g INNER JOIN scope s ON g.parent_id = s.id
+    )";
+
+/// Upper bound on documents returned by `get_graph_neighbors`, however deep the walk.
+const MAX_GRAPH_NEIGHBORS: usize = 200;
+
+/// How many extra KNN candidates to fetch when results are filtered by group afterwards.
+/// When too few candidates pass the filter, the next round fetches this many times more.
+const SCOPED_KNN_OVERSAMPLE: usize = 4;
+
+/// Largest candidate count a filtered KNN search grows to. vec0 caps `k` at 4096, and
+/// binary quantization fetches 10 candidates per requested match.
+const MAX_SCOPED_KNN_K: usize = 400;
+
+/// Nearest chunks of other documents in the scanned group looked up per chunk in a
+/// duplicate scan. Near-duplicates sit at the top of the list.
+const DUPLICATE_NEIGHBORS: usize = 20;
+
 #[derive(Clone)]
 pub struct KbManager {
+    /// The single writer connection. Every insert, update and delete goes through it.
     db: Arc<tokio::sync::Mutex<Connection>>,
+    /// Read-only connections for lookups and searches. With WAL they read a consistent
+    /// snapshot while the writer is busy. When empty, reads fall back to the writer.
+    readers: Arc<Vec<Arc<tokio::sync::Mutex<Connection>>>>,
+    next_reader: Arc<AtomicUsize>,
     embedder: Arc<RwLock<Box<dyn Embedder>>>,
+    /// Optional stage that indexes text found in images referenced from markdown.
+    image_extractor: Arc<std::sync::RwLock<Option<Arc<dyn ImageTextExtractor>>>>,
+}
+
+pub(crate) fn group_scope_cte(param: usize) -> String {
+    GROUP_SCOPE_CTE.replace("{param}", &param.to_string())
+}
+
+fn group_title_from_id(group_id: &str) -> String {
+    Path::new(group_id)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(group_id)
+        .to_string()
+}
+
+/// Build the `(folder_id, parent_id)` chain of folder groups between a workspace
+/// root and the directory containing `file_path`, outermost first.
+/// Returns an empty list when the file is not inside `workspace_root`.
+fn folder_group_chain(workspace_root: &str, file_path: &str) -> Vec<(String, String)> {
+    let root = Path::new(workspace_root);
+    let Some(relative_dir) = Path::new(file_path)
+        .parent()
+        .and_then(|dir| dir.strip_prefix(root).ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut chain = Vec::new();
+    let mut parent = root.to_path_buf();
+    for component in relative_dir.components() {
+        let folder = parent.join(component);
+        chain.push((
+            folder.to_string_lossy().to_string(),
+            parent.to_string_lossy().to_string(),
+        ));
+        parent = folder;
+    }
+    chain
+}
+
+/// Current UTC time in the fixed-width RFC 3339 form stored in the database,
+/// so timestamps compare correctly as text.
+fn now_timestamp() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Append an entry to the change feed, keeping only the latest entry per document
+/// so the feed stays proportional to the number of documents.
+fn record_change(
+    conn: &Connection,
+    doc_id: &str,
+    change_type: &str,
+    changed_at: &str,
+    group_ids: Option<&str>,
+) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM document_changes WHERE document_id = ?1",
+        params![doc_id],
+    )
+    .map_err(|e| format!("Failed to compact change feed: {e}"))?;
+
+    conn.execute(
+        "INSERT INTO document_changes (document_id, change_type, changed_at, group_ids)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![doc_id, change_type, changed_at, group_ids],
+    )
+    .map_err(|e| format!("Failed to record change: {e}"))?;
+
+    Ok(())
+}
+
+/// Delete the given documents (exact ids) with their embeddings and record tombstones.
+fn delete_documents_by_id(conn: &Connection, ids: &[String]) -> Result<(), String> {
+    let now = now_timestamp();
+    for id in ids {
+        // Kept on the tombstone so group-scoped change feeds still see the deletion.
+        let group_ids: String = conn
+            .query_row(
+                "SELECT json_group_array(group_id) FROM document_groups WHERE document_id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to read document groups: {e}"))?;
+
+        // sqlite-vec's vec0 doesn't support FK cascading, so we delete embeddings manually
+        conn.execute(
+            "DELETE FROM documents_embeddings WHERE document_id = ?1",
+            params![id],
+        )
+        .map_err(|e| format!("Failed to delete embeddings: {e}"))?;
+
+        // Rest of tables will cascade properly from documents table (edges, chunks, tags, group links)
+        conn.execute("DELETE FROM documents WHERE id = ?1", params![id])
+            .map_err(|e| format!("Failed to delete document: {e}"))?;
+
+        record_change(conn, id, "delete", &now, Some(&group_ids))?;
+    }
+    Ok(())
+}
+
+/// Nearest chunks accepted by `lookup`, up to `limit`. Starts from `k` candidates and,
+/// while fewer than `limit` pass and the index has more, retries with a larger `k`.
+fn knn_filtered<T>(
+    conn: &Connection,
+    query: &[f32],
+    limit: usize,
+    mut k: usize,
+    mut lookup: impl FnMut(&vector_index::ChunkMatch) -> Result<Option<T>, String>,
+) -> Result<Vec<(vector_index::ChunkMatch, T)>, String> {
+    let quantization = vector_index::read_quantization(conn)?;
+    loop {
+        let matches = vector_index::knn(conn, quantization, query, k)?;
+        let exhausted = matches.len() < k;
+        let mut hits = Vec::new();
+        for m in matches {
+            if hits.len() == limit {
+                break;
+            }
+            if let Some(value) = lookup(&m)? {
+                hits.push((m, value));
+            }
+        }
+        if hits.len() == limit || exhausted || k >= MAX_SCOPED_KNN_K {
+            return Ok(hits);
+        }
+        k = (k * SCOPED_KNN_OVERSAMPLE).min(MAX_SCOPED_KNN_K);
+    }
+}
+
+/// Delete the image documents among `candidates` that no document links to any more.
+fn delete_orphaned_images(conn: &Connection, candidates: Vec<String>) -> Result<(), String> {
+    let mut orphaned = Vec::new();
+    for id in candidates {
+        let linked: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM edges WHERE target_id = ?1 AND type = 'image')",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !linked {
+            orphaned.push(id);
+        }
+    }
+    delete_documents_by_id(conn, &orphaned)
+}
+
+/// Add `doc_id` to each of `group_ids`, creating missing groups. Returns whether the
+/// document was added to a group it was not in yet.
+fn attach_groups(conn: &Connection, doc_id: &str, group_ids: &[String]) -> Result<bool, String> {
+    let mut added = false;
+    for group_id in group_ids {
+        if group_id.trim().is_empty() {
+            continue;
+        }
+        let group_title = group_title_from_id(group_id);
+
+        conn.execute(
+            "INSERT OR IGNORE INTO groups (id, title) VALUES (?1, ?2)",
+            params![group_id, group_title],
+        )
+        .map_err(|e| format!("Failed to ensure group: {e}"))?;
+
+        added |= conn
+            .execute(
+                "INSERT OR IGNORE INTO document_groups (document_id, group_id) VALUES (?1, ?2)",
+                params![doc_id, group_id],
+            )
+            .map_err(|e| format!("Failed to attach document to group: {e}"))?
+            > 0;
+    }
+    Ok(added)
+}
+
+/// Whether `doc_id` keeps a revision history. Section and image documents are derived
+/// from their file; a markdown file's history is kept under its `file:{path}` id by
+/// `index_markdown_document_sections`.
+fn keeps_revisions(doc_id: &str) -> bool {
+    if doc_id.contains("#section:") {
+        return false;
+    }
+    match doc_id.strip_prefix("file:") {
+        Some(path) => !image_extractor::is_supported_image(Path::new(path)),
+        None => true,
+    }
+}
+
+/// Append a revision for `doc_id` unless its latest revision already has the same
+/// content, then prune revisions beyond `MAX_REVISIONS_PER_DOCUMENT`.
+fn record_revision(conn: &Connection, doc_id: &str, title: &str, content: &str) -> Result<(), String> {
+    let hash = content_hash(content);
+
+    let latest_hash: Option<String> = conn
+        .query_row(
+            "SELECT content_hash FROM document_revisions
+             WHERE document_id = ?1 ORDER BY id DESC LIMIT 1",
+            params![doc_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read latest revision: {e}"))?;
+
+    if latest_hash.as_deref() == Some(hash.as_str()) {
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT INTO document_revisions (document_id, title, content, content_hash, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            doc_id,
+            title,
+            content,
+            hash,
+            now_timestamp()
+        ],
+    )
+    .map_err(|e| format!("Failed to insert revision: {e}"))?;
+
+    conn.execute(
+        "DELETE FROM document_revisions
+         WHERE document_id = ?1 AND id NOT IN (
+             SELECT id FROM document_revisions
+             WHERE document_id = ?1 ORDER BY id DESC LIMIT ?2
+         )",
+        params![doc_id, MAX_REVISIONS_PER_DOCUMENT as i64],
+    )
+    .map_err(|e| format!("Failed to prune revisions: {e}"))?;
+
+    Ok(())
 }
 
-fn f32_slice_to_bytes(v: &[f32]) -> Vec<u8> {
-    v.iter().flat_map(|f| f.to_le_bytes()).collect()
+/// Replace every chunk and chunk embedding of `doc_id`, storing embeddings in the
+/// database's current quantization mode.
+fn replace_chunks(
+    conn: &Connection,
+    doc_id: &str,
+    chunks: &[String],
+    embeddings: &[Vec<f32>],
+) -> Result<(), String> {
+    let quantization = vector_index::read_quantization(conn)?;
+
+    // Re-scoring vectors cascade with their chunks; vec0 rows must be removed explicitly.
+    conn.execute(
+        "DELETE FROM document_chunks WHERE document_id = ?1",
+        params![doc_id],
+    )
+    .map_err(|e| format!("Failed to delete old chunks: {e}"))?;
+
+    conn.execute(
+        "DELETE FROM documents_embeddings WHERE document_id = ?1",
+        params![doc_id],
+    )
+    .map_err(|e| format!("Failed to delete old embeddings: {e}"))?;
+
+    for (i, (chunk_content, embedding)) in chunks.iter().zip(embeddings.iter()).enumerate() {
+        let chunk_id = format!("{doc_id}#{i}");
+
+        conn.execute(
+            "INSERT INTO document_chunks (chunk_id, document_id, chunk_index, content, language)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![chunk_id, doc_id, i as i64, chunk_content, language::detect(chunk_content)],
+        )
+        .map_err(|e| format!("Failed to insert chunk {i}: {e}"))?;
+
+        vector_index::insert_embedding(conn, quantization, &chunk_id, doc_id, embedding)?;
+    }
+
+    Ok(())
 }
 
 fn resolve_link_id(source_id: &str, link: &str) -> String {
@@ -79,8 +495,46 @@ impl KbManager {
     pub fn new(conn: Connection, embedder: Box<dyn Embedder>) -> Self {
         Self {
             db: Arc::new(tokio::sync::Mutex::new(conn)),
+            readers: Arc::new(Vec::new()),
+            next_reader: Arc::new(AtomicUsize::new(0)),
             embedder: Arc::new(RwLock::new(embedder)),
+            image_extractor: Arc::new(std::sync::RwLock::new(None)),
+        }
+    }
+
+    /// Enable (or with `None`, disable) image text extraction for subsequent indexing.
+    pub fn set_image_extractor(&self, extractor: Option<Arc<dyn ImageTextExtractor>>) {
+        *self.image_extractor.write().unwrap() = extractor;
+    }
+
+    pub fn image_extraction_enabled(&self) -> bool {
+        self.image_extractor.read().unwrap().is_some()
+    }
+
+    /// Serve reads from `readers` (see `db::open_read_connections`) instead of the writer.
+    pub fn with_readers(mut self, readers: Vec<Connection>) -> Self {
+        self.readers = Arc::new(
+            readers
+                .into_iter()
+                .map(|conn| Arc::new(tokio::sync::Mutex::new(conn)))
+                .collect(),
+        );
+        self
+    }
+
+    /// Lock a connection for read-only work: an idle reader if there is one, otherwise
+    /// the next reader in turn, or the writer when no readers are configured.
+    pub async fn read_connection(&self) -> OwnedMutexGuard<Connection> {
+        if self.readers.is_empty() {
+            return self.db.clone().lock_owned().await;
+        }
+        for reader in self.readers.iter() {
+            if let Ok(guard) = reader.clone().try_lock_owned() {
+                return guard;
+            }
         }
+        let next = self.next_reader.fetch_add(1, Ordering::Relaxed) % self.readers.len();
+        self.readers[next].clone().lock_owned().await
     }
 
     /// Retrieve the SQLite database connection lock.
@@ -93,6 +547,12 @@ impl KbManager {
         self.embedder.clone()
     }
 
+    /// Embed each chunk with the active embedder.
+    async fn embed_chunks(&self, chunks: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let embedder = self.embedder.read().await;
+        embedder.embed_batch(chunks).await
+    }
+
     /// Insert or update a document, automatically parsing tags, links, and creating vector embeddings.
     pub async fn upsert_document(
         &self,
@@ -111,15 +571,7 @@ impl KbManager {
         let chunks = chunk_text(&content, &opts);
 
         // 3. Generate embeddings
-        let embeddings: Vec<Vec<f32>> = {
-            let embedder = self.embedder.read().await;
-            let mut results = Vec::new();
-            for chunk in &chunks {
-                let vec = embedder.embed(chunk).await?;
-                results.push(vec);
-            }
-            results
-        };
+        let embeddings = self.embed_chunks(&chunks).await?;
 
         // 4. Persist inside transaction
         let mut conn = self.db.lock().await;
@@ -127,52 +579,40 @@ impl KbManager {
             .transaction()
             .map_err(|e| format!("Failed to start transaction: {e}"))?;
 
+        let previous: Option<(String, String)> = tx
+            .query_row(
+                "SELECT title, content FROM documents WHERE id = ?1",
+                params![doc_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read existing document: {e}"))?;
+        let is_changed = previous.as_ref() != Some(&(title.clone(), content.clone()));
+        let now = now_timestamp();
+
+        // created_at is only set on insert; updated_at only moves when title or content change.
         tx.execute(
-            "INSERT INTO documents (id, title, content)
-             VALUES (?1, ?2, ?3)
+            "INSERT INTO documents (id, title, content, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?4)
              ON CONFLICT(id) DO UPDATE SET
                  title = excluded.title,
-                 content = excluded.content",
-            params![doc_id, title, content],
+                 content = excluded.content,
+                 updated_at = CASE WHEN ?5 THEN excluded.updated_at ELSE documents.updated_at END",
+            params![doc_id, title, content, now, is_changed],
         )
         .map_err(|e| format!("Failed to upsert document: {e}"))?;
 
-        // Handle groups
-        for group_id in &group_ids {
-            if group_id.trim().is_empty() {
-                continue;
-            }
-            let group_title = Path::new(group_id)
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or(group_id)
-                .to_string();
-
-            tx.execute(
-                "INSERT OR IGNORE INTO groups (id, title) VALUES (?1, ?2)",
-                params![group_id, group_title],
-            )
-            .map_err(|e| format!("Failed to ensure group: {e}"))?;
-
-            tx.execute(
-                "INSERT OR IGNORE INTO document_groups (document_id, group_id) VALUES (?1, ?2)",
-                params![doc_id, group_id],
-            )
-            .map_err(|e| format!("Failed to attach document to group: {e}"))?;
+        if keeps_revisions(&doc_id) {
+            record_revision(&tx, &doc_id, &title, &content)?;
         }
 
-        // Clean stale chunks, embeddings, and tags
-        tx.execute(
-            "DELETE FROM document_chunks WHERE document_id = ?1",
-            params![doc_id],
-        )
-        .map_err(|e| format!("Failed to delete old chunks: {e}"))?;
+        // Groups are only added, so a document shared by several folders stays in each.
+        let groups_added = attach_groups(&tx, &doc_id, &group_ids)?;
+        if is_changed || groups_added {
+            record_change(&tx, &doc_id, "upsert", &now, None)?;
+        }
 
-        tx.execute(
-            "DELETE FROM documents_embeddings WHERE document_id = ?1",
-            params![doc_id],
-        )
-        .map_err(|e| format!("Failed to delete old embeddings: {e}"))?;
+        replace_chunks(&tx, &doc_id, &chunks, &embeddings)?;
 
         tx.execute(
             "DELETE FROM document_tags WHERE document_id = ?1",
@@ -180,26 +620,6 @@ impl KbManager {
         )
         .map_err(|e| format!("Failed to delete old tags: {e}"))?;
 
-        // Insert new chunks and embeddings
-        for (i, (chunk_content, embedding)) in chunks.iter().zip(embeddings.iter()).enumerate() {
-            let chunk_id = format!("{doc_id}#{i}");
-
-            tx.execute(
-                "INSERT INTO document_chunks (chunk_id, document_id, chunk_index, content)
-                 VALUES (?1, ?2, ?3, ?4)",
-                params![chunk_id, doc_id, i as i64, chunk_content],
-            )
-            .map_err(|e| format!("Failed to insert chunk {i}: {e}"))?;
-
-            let bytes = f32_slice_to_bytes(embedding);
-            tx.execute(
-                "INSERT INTO documents_embeddings (chunk_id, document_id, embedding)
-                 VALUES (?1, ?2, ?3)",
-                params![chunk_id, doc_id, bytes],
-            )
-            .map_err(|e| format!("Failed to insert embedding chunk {i}: {e}"))?;
-        }
-
         // Insert tags
         for tag in &metadata.tags {
             tx.execute(
@@ -290,99 +710,422 @@ impl KbManager {
             rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?
         };
 
-        for id in existing_ids {
-            self.delete_document(id).await?;
+        // Read before stale sections are deleted, since that cascades their image edges.
+        let previous_images = self.image_documents_linked_from(&file_path).await?;
+        let sections = split_markdown_into_sections(&content);
+
+        // Only drop documents for sections that no longer exist; the rest are updated
+        // in place so they keep their creation time and incoming edges.
+        let new_ids: Vec<String> = if sections.is_empty() {
+            vec![base_document_id.clone()]
+        } else {
+            sections
+                .iter()
+                .map(|section| format!("{base_document_id}#section:{}", section.id))
+                .collect()
+        };
+        let stale_ids: Vec<String> = existing_ids
+            .into_iter()
+            .filter(|id| !new_ids.contains(id))
+            .collect();
+        if !stale_ids.is_empty() {
+            let mut conn = self.db.lock().await;
+            let tx = conn
+                .transaction()
+                .map_err(|e| format!("Failed to start delete transaction: {e}"))?;
+            delete_documents_by_id(&tx, &stale_ids)?;
+            tx.commit()
+                .map_err(|e| format!("Failed to commit delete transaction: {e}"))?;
         }
 
-        let sections = split_markdown_into_sections(&content);
+        let group_ids = self.ensure_folder_groups(&file_path, group_ids).await?;
+        let extractor = self.image_extractor.read().unwrap().clone();
+
         if sections.is_empty() {
+            let images = extract_image_refs(&content);
             self.upsert_document(
-                Some(base_document_id),
+                Some(base_document_id.clone()),
                 document_title,
                 content,
-                group_ids,
+                group_ids.clone(),
             )
             .await?;
+            if let Some(extractor) = &extractor {
+                self.index_images(extractor, &file_path, &base_document_id, &images, &group_ids)
+                    .await?;
+            }
+            self.delete_unreferenced_images(previous_images).await?;
             return Ok(1);
         }
 
         let mut indexed_count = 0;
-        for section in sections {
+        for (position, section) in sections.into_iter().enumerate() {
             let section_document_id = format!("{base_document_id}#section:{}", section.id);
             let section_title = format!("{document_title} - {}", section.title);
+            let images = extract_image_refs(&section.content);
 
             self.upsert_document(
-                Some(section_document_id),
+                Some(section_document_id.clone()),
                 section_title,
                 section.content,
                 group_ids.clone(),
             )
             .await?;
+            // Sections updated in place keep their rowid, so file order is stored explicitly.
+            self.db
+                .lock()
+                .await
+                .execute(
+                    "UPDATE documents SET section_position = ?2 WHERE id = ?1",
+                    params![section_document_id, position as i64],
+                )
+                .map_err(|e| format!("Failed to store section position: {e}"))?;
+            if let Some(extractor) = &extractor {
+                self.index_images(extractor, &file_path, &section_document_id, &images, &group_ids)
+                    .await?;
+            }
 
             indexed_count += 1;
         }
 
+        // Sections keep no history of their own, so the whole file is kept as one revision.
+        {
+            let conn = self.db.lock().await;
+            record_revision(&conn, &base_document_id, &document_title, &content)?;
+        }
+        self.delete_unreferenced_images(previous_images).await?;
         Ok(indexed_count)
     }
 
-    /// Delete a document (and any of its section documents) and its associated chunks and embeddings.
-    pub async fn delete_document(&self, id: String) -> Result<(), String> {
-        let mut conn = self.db.lock().await;
-        let tx = conn
-            .transaction()
-            .map_err(|e| format!("Failed to start delete transaction: {e}"))?;
+    /// Index the text of each image referenced by `source_id` as its own `file:{image}`
+    /// document, linked from the source with an `image` edge. Images that fail to
+    /// decode are logged and skipped so one bad file does not stop the markdown index.
+    async fn index_images(
+        &self,
+        extractor: &Arc<dyn ImageTextExtractor>,
+        markdown_path: &str,
+        source_id: &str,
+        images: &[ImageRef],
+        group_ids: &[String],
+    ) -> Result<(), String> {
+        {
+            let conn = self.db.lock().await;
+            conn.execute(
+                "DELETE FROM edges WHERE source_id = ?1 AND type = 'image'",
+                params![source_id],
+            )
+            .map_err(|e| format!("Failed to delete old image edges: {e}"))?;
+        }
 
-        let section_id_like = format!("{}#section:%", id);
+        let base_dir = Path::new(markdown_path).parent().unwrap_or(Path::new(""));
+        let mut seen = std::collections::HashSet::new();
+        for image in images {
+            // `img/a.png` and `./img/a.png` are the same image and must give one document.
+            let image_path = image_extractor::resolve_image_path(base_dir, &image.path);
+            if !image_extractor::is_supported_image(&image_path) || !seen.insert(image_path.clone()) {
+                continue;
+            }
+            let Ok(bytes) = std::fs::read(&image_path) else {
+                continue;
+            };
+            let image_path = image_path.to_string_lossy().to_string();
+            let hash = image_extractor::image_hash(&bytes);
 
-        // sqlite-vec's vec0 doesn't support FK cascading, so we delete embeddings manually
-        tx.execute(
-            "DELETE FROM documents_embeddings WHERE document_id = ?1 OR document_id LIKE ?2",
-            params![id, section_id_like],
-        )
-        .map_err(|e| format!("Failed to delete embeddings: {e}"))?;
+            let cached = {
+                let conn = self.read_connection().await;
+                image_extractor::cached_text(&conn, &image_path, &hash, extractor.name())?
+            };
+            let text = match cached {
+                Some(text) => text,
+                None => {
+                    let worker = extractor.clone();
+                    let path = image_path.clone();
+                    let extracted = tokio::task::spawn_blocking(move || worker.extract(Path::new(&path)))
+                        .await
+                        .map_err(|e| format!("Image extraction task failed: {e}"))?;
+                    let text = match extracted {
+                        Ok(text) => text,
+                        Err(e) => {
+                            eprintln!("[knowledge_base] skipping image {image_path}: {e}");
+                            continue;
+                        }
+                    };
+                    let conn = self.db.lock().await;
+                    image_extractor::store_text(&conn, &image_path, &hash, extractor.name(), &text)?;
+                    text
+                }
+            };
 
-        // Rest of tables will cascade properly from documents table (edges, chunks, tags, group links)
-        tx.execute("DELETE FROM documents WHERE id = ?1 OR id LIKE ?2", params![id, section_id_like])
-            .map_err(|e| format!("Failed to delete document: {e}"))?;
+            let alt = image.alt.trim();
+            if text.trim().is_empty() && alt.is_empty() {
+                continue;
+            }
+            let file_name = Path::new(&image_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| image_path.clone());
+            let title = if alt.is_empty() { file_name } else { format!("{alt} ({file_name})") };
+
+            let image_doc_id = self
+                .upsert_document(
+                    Some(format!("file:{image_path}")),
+                    title,
+                    format!("{alt}\n\n{}", text.trim()).trim().to_string(),
+                    group_ids.to_vec(),
+                )
+                .await?;
 
-        tx.commit()
-            .map_err(|e| format!("Failed to commit delete transaction: {e}"))?;
+            let conn = self.db.lock().await;
+            conn.execute(
+                "INSERT INTO edges (id, source_id, target_id, type)
+                 SELECT ?1, ?2, ?3, 'image'
+                 WHERE NOT EXISTS (
+                     SELECT 1 FROM edges WHERE source_id = ?2 AND target_id = ?3 AND type = 'image'
+                 )",
+                params![uuid::Uuid::new_v4().to_string(), source_id, image_doc_id],
+            )
+            .map_err(|e| format!("Failed to link image: {e}"))?;
+        }
 
         Ok(())
     }
 
-    /// Fetch a single document's metadata and content.
-    pub async fn get_document(&self, id: String) -> Result<Option<GraphDocumentRecord>, String> {
-        let conn = self.db.lock().await;
+    /// Image documents currently linked from any document of the markdown file.
+    async fn image_documents_linked_from(&self, markdown_path: &str) -> Result<Vec<String>, String> {
+        let base_document_id = format!("file:{markdown_path}");
+        let conn = self.read_connection().await;
         let mut stmt = conn
-            .prepare("SELECT id, title, content FROM documents WHERE id = ?1")
+            .prepare(
+                "SELECT DISTINCT target_id FROM edges
+                 WHERE type = 'image' AND (source_id = ?1 OR source_id LIKE ?2)",
+            )
             .map_err(|e| e.to_string())?;
-
-        let mut rows = stmt
-            .query_map(params![id], |row| {
-                Ok(GraphDocumentRecord {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    content: row.get(2)?,
-                })
-            })
+        let rows = stmt
+            .query_map(params![base_document_id, format!("{base_document_id}#section:%")], |row| row.get(0))
             .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    }
 
-        match rows.next() {
-            Some(Ok(record)) => Ok(Some(record)),
-            Some(Err(e)) => Err(e.to_string()),
-            None => Ok(None),
+    /// Drop image documents from `candidates` that no markdown document links to any more.
+    async fn delete_unreferenced_images(&self, candidates: Vec<String>) -> Result<(), String> {
+        if candidates.is_empty() {
+            return Ok(());
         }
+        let mut conn = self.db.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start delete transaction: {e}"))?;
+        delete_orphaned_images(&tx, candidates)?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit delete transaction: {e}"))?;
+        Ok(())
     }
 
-    /// Fetch a target chunk's text and $N$ preceding/succeeding siblings.
-    pub async fn get_chunk_context(&self, chunk_id: String, window: usize) -> Result<Vec<String>, String> {
-        let conn = self.db.lock().await;
+    /// Create the nested folder groups (workspace > folder > subfolder) leading to
+    /// `file_path` under each workspace group, and return `group_ids` extended with
+    /// the innermost folder group so the document is attached at every level it
+    /// can be searched from.
+    async fn ensure_folder_groups(
+        &self,
+        file_path: &str,
+        group_ids: Vec<String>,
+    ) -> Result<Vec<String>, String> {
+        let mut conn = self.db.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {e}"))?;
 
-        let (document_id, chunk_index): (String, i64) = conn
-            .query_row(
-                "SELECT document_id, chunk_index FROM document_chunks WHERE chunk_id = ?1",
-                params![chunk_id],
+        let mut all_group_ids = group_ids.clone();
+        for workspace_root in &group_ids {
+            if workspace_root.trim().is_empty() {
+                continue;
+            }
+            let chain = folder_group_chain(workspace_root, file_path);
+            if chain.is_empty() {
+                continue;
+            }
+
+            tx.execute(
+                "INSERT OR IGNORE INTO groups (id, title) VALUES (?1, ?2)",
+                params![workspace_root, group_title_from_id(workspace_root)],
+            )
+            .map_err(|e| format!("Failed to ensure group: {e}"))?;
+
+            for (folder_id, parent_id) in &chain {
+                tx.execute(
+                    "INSERT INTO groups (id, title, parent_id) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(id) DO UPDATE SET parent_id = excluded.parent_id
+                     WHERE groups.parent_id IS NULL",
+                    params![folder_id, group_title_from_id(folder_id), parent_id],
+                )
+                .map_err(|e| format!("Failed to ensure folder group: {e}"))?;
+            }
+
+            if let Some((innermost, _)) = chain.last() {
+                if !all_group_ids.contains(innermost) {
+                    all_group_ids.push(innermost.clone());
+                }
+            }
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {e}"))?;
+
+        Ok(all_group_ids)
+    }
+
+    /// Delete a document (and any of its section documents) and its associated chunks and embeddings.
+    pub async fn delete_document(&self, id: String) -> Result<(), String> {
+        let mut conn = self.db.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start delete transaction: {e}"))?;
+
+        let section_id_like = format!("{}#section:%", id);
+
+        // sqlite-vec's vec0 doesn't support FK cascading, so we delete embeddings manually
+        tx.execute(
+            "DELETE FROM documents_embeddings WHERE document_id = ?1 OR document_id LIKE ?2",
+            params![id, section_id_like],
+        )
+        .map_err(|e| format!("Failed to delete embeddings: {e}"))?;
+
+        let ids: Vec<String> = {
+            let mut stmt = tx
+                .prepare("SELECT id FROM documents WHERE id = ?1 OR id LIKE ?2")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![id, section_id_like], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?
+        };
+        // Images indexed from a markdown file go with it, unless another file shows them too.
+        let images: Vec<String> = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT DISTINCT target_id FROM edges
+                     WHERE type = 'image' AND (source_id = ?1 OR source_id LIKE ?2)",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![id, section_id_like], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?
+        };
+        delete_documents_by_id(&tx, &ids)?;
+        delete_orphaned_images(&tx, images)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit delete transaction: {e}"))?;
+
+        Ok(())
+    }
+
+    /// Add an edge from `source_id` to `target_id`; both documents must exist.
+    pub async fn connect_documents(
+        &self,
+        source_id: String,
+        target_id: String,
+        edge_type: Option<String>,
+    ) -> Result<String, String> {
+        let edge_id = uuid::Uuid::new_v4().to_string();
+        let conn = self.db.lock().await;
+
+        for (role, id) in [("Source", &source_id), ("Target", &target_id)] {
+            let exists: bool = conn
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM documents WHERE id = ?1)",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .unwrap_or(false);
+            if !exists {
+                return Err(format!("{role} document does not exist"));
+            }
+        }
+
+        conn.execute(
+            "INSERT INTO edges (id, source_id, target_id, type) VALUES (?1, ?2, ?3, ?4)",
+            params![edge_id, source_id, target_id, edge_type],
+        )
+        .map_err(|e| format!("Failed to insert edge: {e}"))?;
+
+        Ok(edge_id)
+    }
+
+    /// Remove every edge between two documents (or their sections), in either direction.
+    /// Returns the number of edges removed.
+    pub async fn disconnect_documents(&self, source_id: String, target_id: String) -> Result<usize, String> {
+        let conn = self.db.lock().await;
+
+        let source_like = format!("{}#section:%", source_id);
+        let target_like = format!("{}#section:%", target_id);
+
+        let forward = conn
+            .execute(
+                "DELETE FROM edges 
+                 WHERE (source_id = ?1 OR source_id LIKE ?2) 
+                   AND (target_id = ?3 OR target_id LIKE ?4)",
+                params![source_id, source_like, target_id, target_like],
+            )
+            .map_err(|e| format!("Failed to delete edge: {e}"))?;
+
+        // Also in reverse
+        let reverse = conn
+            .execute(
+                "DELETE FROM edges 
+                 WHERE (source_id = ?1 OR source_id LIKE ?2) 
+                   AND (target_id = ?3 OR target_id LIKE ?4)",
+                params![target_id, target_like, source_id, source_like],
+            )
+            .map_err(|e| format!("Failed to delete reverse edge: {e}"))?;
+
+        Ok(forward + reverse)
+    }
+
+    /// Fetch a single document's metadata and content.
+    pub async fn get_document(&self, id: String) -> Result<Option<GraphDocumentRecord>, String> {
+        let conn = self.read_connection().await;
+        let mut stmt = conn
+            .prepare("SELECT id, title, content, created_at, updated_at FROM documents WHERE id = ?1")
+            .map_err(|e| e.to_string())?;
+
+        let mut rows = stmt
+            .query_map(params![id], |row| {
+                Ok(GraphDocumentRecord {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    content: row.get(2)?,
+                    created_at: row.get(3)?,
+                    updated_at: row.get(4)?,
+                    summary: None,
+                    keywords: Vec::new(),
+                })
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut record = match rows.next() {
+            Some(Ok(record)) => record,
+            Some(Err(e)) => return Err(e.to_string()),
+            None => return Ok(None),
+        };
+        if let Some(found) = summaries::load_fresh(&conn, [(record.id.as_str(), record.content.as_str())])?
+            .remove(&record.id)
+        {
+            record.summary = Some(found.summary);
+            record.keywords = found.keywords;
+        }
+        Ok(Some(record))
+    }
+
+    /// Fetch a target chunk's text and $N$ preceding/succeeding siblings.
+    pub async fn get_chunk_context(&self, chunk_id: String, window: usize) -> Result<Vec<String>, String> {
+        let conn = self.read_connection().await;
+
+        let (document_id, chunk_index): (String, i64) = conn
+            .query_row(
+                "SELECT document_id, chunk_index FROM document_chunks WHERE chunk_id = ?1",
+                params![chunk_id],
                 |row| Ok((row.get(0)?, row.get(1)?)),
             )
             .map_err(|e| format!("Chunk not found: {e}"))?;
@@ -412,58 +1155,246 @@ impl KbManager {
         Ok(chunks)
     }
 
+    /// Documents linking to `id` or to one of its sections, excluding links from the
+    /// document's own sections.
+    pub async fn get_backlinks(&self, id: String) -> Result<Vec<LinkedDocumentRecord>, String> {
+        let conn = self.read_connection().await;
+        let section_like = format!("{id}#section:%");
+        let mut stmt = conn
+            .prepare(
+                "SELECT DISTINCT d.id, d.title, e.type
+                 FROM edges e
+                 INNER JOIN documents d ON d.id = e.source_id
+                 WHERE (e.target_id = ?1 OR e.target_id LIKE ?2)
+                   AND e.source_id != ?1 AND e.source_id NOT LIKE ?2
+                 ORDER BY d.title COLLATE NOCASE",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let rows = stmt
+            .query_map(params![id, section_like], |row| {
+                Ok(LinkedDocumentRecord {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    edge_type: row.get(2)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    }
+
+    /// The subset of `document_ids` that belong to `group_id` or one of its subgroups.
+    pub async fn documents_in_group(
+        &self,
+        group_id: String,
+        document_ids: Vec<String>,
+    ) -> Result<std::collections::HashSet<String>, String> {
+        let conn = self.read_connection().await;
+        let mut stmt = conn
+            .prepare(&format!(
+                "WITH RECURSIVE {}
+                 SELECT EXISTS(
+                     SELECT 1 FROM document_groups dg
+                     INNER JOIN scope s ON s.id = dg.group_id
+                     WHERE dg.document_id = ?2
+                 )",
+                group_scope_cte(1)
+            ))
+            .map_err(|e| e.to_string())?;
+        let scope_json = serde_json::json!([group_id]).to_string();
+
+        let mut found = std::collections::HashSet::new();
+        for id in document_ids {
+            let in_group: bool = stmt
+                .query_row(params![scope_json, id], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            if in_group {
+                found.insert(id);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Walk edges in both directions from `id`, up to `depth` hops.
+    pub async fn get_graph_neighbors(&self, id: String, depth: usize) -> Result<GraphNeighborhoodRecord, String> {
+        let conn = self.read_connection().await;
+        let mut edge_stmt = conn
+            .prepare(
+                "SELECT id, source_id, target_id, type FROM edges
+                 WHERE source_id = ?1 OR target_id = ?1
+                 ORDER BY id",
+            )
+            .map_err(|e| e.to_string())?;
+        let mut title_stmt = conn
+            .prepare("SELECT title FROM documents WHERE id = ?1")
+            .map_err(|e| e.to_string())?;
+
+        let mut visited = std::collections::HashSet::from([id.clone()]);
+        let mut documents = Vec::new();
+        let mut edges: Vec<GraphEdgeRecord> = Vec::new();
+        let mut frontier = vec![id.clone()];
+
+        'walk: for hop in 1..=depth {
+            let mut next = Vec::new();
+            for node in frontier {
+                let node_edges = edge_stmt
+                    .query_map(params![node], |row| {
+                        Ok(GraphEdgeRecord {
+                            id: row.get(0)?,
+                            source_id: row.get(1)?,
+                            target_id: row.get(2)?,
+                            edge_type: row.get(3)?,
+                        })
+                    })
+                    .map_err(|e| e.to_string())?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| e.to_string())?;
+
+                for edge in node_edges {
+                    let other = if edge.source_id == node { &edge.target_id } else { &edge.source_id };
+                    if !visited.contains(other) {
+                        if documents.len() == MAX_GRAPH_NEIGHBORS {
+                            break 'walk;
+                        }
+                        let title: String = title_stmt
+                            .query_row(params![other], |row| row.get(0))
+                            .map_err(|e| e.to_string())?;
+                        visited.insert(other.clone());
+                        documents.push(NeighborDocumentRecord { id: other.clone(), title, hops: hop });
+                        next.push(other.clone());
+                    }
+                    if !edges.iter().any(|e| e.id == edge.id) {
+                        edges.push(edge);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        Ok(GraphNeighborhoodRecord { root_id: id, documents, edges })
+    }
+
     /// Find documents semantically similar to `query` using KNN search on the `vec0` table.
     pub async fn search_similar(
         &self,
         query: String,
         limit: usize,
     ) -> Result<Vec<SearchResult>, String> {
+        self.search_similar_in_groups(query, limit, None).await
+    }
+
+    /// Semantic search restricted to documents in `group_ids` (and their subgroups).
+    /// `None` searches every group.
+    pub async fn search_similar_in_groups(
+        &self,
+        query: String,
+        limit: usize,
+        group_ids: Option<Vec<String>>,
+    ) -> Result<Vec<SearchResult>, String> {
+        let scope_json = match group_ids {
+            Some(ids) => Some(serde_json::to_string(&ids).map_err(|e| e.to_string())?),
+            None => None,
+        };
+        let knn_k = if scope_json.is_some() { limit * SCOPED_KNN_OVERSAMPLE } else { limit };
+
         let query_vector = {
             let embedder = self.embedder.read().await;
             embedder.embed(&query).await?
         };
-        let query_bytes = f32_slice_to_bytes(&query_vector);
-        let conn = self.db.lock().await;
-
-        let mut stmt = conn
-            .prepare(
-                "SELECT de.document_id, d.title, de.distance
-                 FROM documents_embeddings de
-                 LEFT JOIN documents d ON d.id = de.document_id
-                 WHERE de.embedding MATCH ?1 AND k = ?2
-                 ORDER BY de.distance ASC",
-            )
+        let conn = self.read_connection().await;
+        let mut title_stmt = conn
+            .prepare_cached(&format!(
+                "WITH RECURSIVE {}
+                 SELECT title FROM documents d
+                 WHERE d.id = ?1
+                   AND (?2 IS NULL OR d.id IN (
+                       SELECT dg.document_id FROM document_groups dg
+                       INNER JOIN scope s ON s.id = dg.group_id
+                   ))",
+                group_scope_cte(2)
+            ))
             .map_err(|e| format!("Failed to prepare search query: {e}"))?;
 
-        let rows = stmt
-            .query_map(params![query_bytes, limit as i64], |row| {
-                Ok(SearchResult {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    distance: row.get(2)?,
-                })
+        let matches = knn_filtered(&conn, &query_vector, limit, knn_k, |m| {
+            let title: Option<String> = title_stmt
+                .query_row(params![m.document_id, scope_json], |row| row.get(0))
+                .optional()
+                .map_err(|e| format!("Search query failed: {e}"))?;
+            Ok(match (title, &scope_json) {
+                (Some(title), _) => Some(title),
+                // Outside the requested groups.
+                (None, Some(_)) => None,
+                (None, None) => Some(String::new()),
             })
-            .map_err(|e| format!("Search query failed: {e}"))?;
-
-        let mut results = Vec::new();
-        for row in rows {
-            results.push(row.map_err(|e| e.to_string())?);
-        }
+        })?;
+        let results = matches
+            .into_iter()
+            .map(|(m, title)| SearchResult {
+                id: m.document_id,
+                title,
+                distance: m.distance,
+            })
+            .collect();
 
         Ok(results)
     }
 
     /// Execute a hybrid query combining FTS5 keyword scoring with sqlite-vec KNN search via Reciprocal Rank Fusion (RRF).
     pub async fn search_hybrid(&self, query: String, limit: usize) -> Result<Vec<HybridSearchResult>, String> {
+        self.search_hybrid_in_groups(query, limit, None).await
+    }
+
+    /// Hybrid search restricted to documents in `group_ids` (and their subgroups).
+    /// `None` searches every group; several workspaces can be searched at once.
+    pub async fn search_hybrid_in_groups(
+        &self,
+        query: String,
+        limit: usize,
+        group_ids: Option<Vec<String>>,
+    ) -> Result<Vec<HybridSearchResult>, String> {
+        self.search_hybrid_with_language(query, limit, group_ids, None).await
+    }
+
+    /// Hybrid search that filters to, or boosts, chunks detected as `language`.
+    pub async fn search_hybrid_with_language(
+        &self,
+        query: String,
+        limit: usize,
+        group_ids: Option<Vec<String>>,
+        language: Option<LanguagePreference>,
+    ) -> Result<Vec<HybridSearchResult>, String> {
         let limit_i64 = limit as i64;
-        let conn = self.db.lock().await;
+        let scope_json = match group_ids {
+            Some(ids) => Some(serde_json::to_string(&ids).map_err(|e| e.to_string())?),
+            None => None,
+        };
+        let filter_language = language
+            .as_ref()
+            .filter(|pref| pref.mode == LanguageMode::Filter)
+            .map(|pref| pref.language.clone());
+        let knn_k = if scope_json.is_some() || filter_language.is_some() {
+            limit * SCOPED_KNN_OVERSAMPLE
+        } else {
+            limit
+        };
+
+        // Embed before taking a connection so slow embedders don't hold one.
+        let query_vector = {
+            let embedder = self.embedder.read().await;
+            embedder.embed(&query).await?
+        };
+        let conn = self.read_connection().await;
 
         // 1. Keyword search (FTS5)
+        // Each term is quoted so punctuation such as `-` or `:` is not parsed as FTS syntax.
         let clean_query = query.replace('"', "").replace('\'', "");
         let fts_query = if clean_query.trim().is_empty() {
             "".to_string()
         } else {
-            let parts: Vec<&str> = clean_query.split_whitespace().collect();
+            let parts: Vec<String> = clean_query
+                .split_whitespace()
+                .map(|term| format!("\"{term}\""))
+                .collect();
             parts.join(" OR ")
         };
 
@@ -474,22 +1405,33 @@ impl KbManager {
             fts_rank: Option<usize>,
             vec_rank: Option<usize>,
             matched_chunks: Vec<String>,
+            chunk_languages: Vec<String>,
         }
 
         let mut doc_map: std::collections::HashMap<String, RawResult> = std::collections::HashMap::new();
 
         if !fts_query.trim().is_empty() {
             let mut fts_stmt = conn
-                .prepare(
-                    "SELECT d.id, d.title, d.content, f.rank
+                .prepare(&format!(
+                    "WITH RECURSIVE {}
+                     SELECT d.id, d.title, d.content, f.rank
                      FROM documents_fts f
                      INNER JOIN documents d ON d.rowid = f.rowid
                      WHERE documents_fts MATCH ?1
+                       AND (?3 IS NULL OR d.id IN (
+                           SELECT dg.document_id FROM document_groups dg
+                           INNER JOIN scope s ON s.id = dg.group_id
+                       ))
+                       AND (?4 IS NULL OR EXISTS (
+                           SELECT 1 FROM document_chunks lc
+                           WHERE lc.document_id = d.id AND lc.language = ?4
+                       ))
                      ORDER BY f.rank ASC LIMIT ?2",
-                )
+                    group_scope_cte(3)
+                ))
                 .map_err(|e| format!("FTS query prepare failed: {e}"))?;
 
-            let mut rows = fts_stmt.query(params![fts_query, limit_i64])
+            let mut rows = fts_stmt.query(params![fts_query, limit_i64, scope_json, filter_language])
                 .map_err(|e| format!("FTS query execution failed: {e}"))?;
             let mut idx = 0;
             while let Some(row) = rows.next().map_err(|e| e.to_string())? {
@@ -506,6 +1448,7 @@ impl KbManager {
                         fts_rank: Some(idx + 1),
                         vec_rank: None,
                         matched_chunks: Vec::new(),
+                        chunk_languages: Vec::new(),
                     },
                 );
                 idx += 1;
@@ -513,34 +1456,37 @@ impl KbManager {
         }
 
         // 2. Vector search (KNN)
-        let query_vector = {
-            let embedder = self.embedder.read().await;
-            embedder.embed(&query).await?
-        };
-        let query_bytes = f32_slice_to_bytes(&query_vector);
-
-        let mut vec_stmt = conn
-            .prepare(
-                "SELECT de.document_id, d.title, d.content, de.distance, dc.content
-                 FROM documents_embeddings de
-                 INNER JOIN documents d ON d.id = de.document_id
-                 INNER JOIN document_chunks dc ON dc.chunk_id = de.chunk_id
-                 WHERE de.embedding MATCH ?1 AND k = ?2
-                 ORDER BY de.distance ASC",
-            )
+        let mut chunk_stmt = conn
+            .prepare_cached(&format!(
+                "WITH RECURSIVE {}
+                 SELECT d.title, d.content, dc.content, dc.language
+                 FROM documents d
+                 INNER JOIN document_chunks dc ON dc.chunk_id = ?2
+                 WHERE d.id = ?1
+                   AND (?3 IS NULL OR d.id IN (
+                       SELECT dg.document_id FROM document_groups dg
+                       INNER JOIN scope s ON s.id = dg.group_id
+                   ))",
+                group_scope_cte(3)
+            ))
             .map_err(|e| format!("Vector query prepare failed: {e}"))?;
+        let matches = knn_filtered(&conn, &query_vector, limit, knn_k, |m| {
+            let row: Option<(String, String, String, Option<String>)> = chunk_stmt
+                .query_row(params![m.document_id, m.chunk_id, scope_json], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                })
+                .optional()
+                .map_err(|e| format!("Vector query execution failed: {e}"))?;
+            Ok(row.filter(|(_, _, _, chunk_language)| {
+                filter_language.is_none() || *chunk_language == filter_language
+            }))
+        })?;
 
-        let mut rows = vec_stmt.query(params![query_bytes, limit_i64])
-            .map_err(|e| format!("Vector query execution failed: {e}"))?;
         let mut seen_docs = std::collections::HashSet::new();
         let mut vec_counter = 0;
 
-        while let Some(row) = rows.next().map_err(|e| e.to_string())? {
-            let id: String = row.get(0).map_err(|e| e.to_string())?;
-            let title: String = row.get(1).map_err(|e| e.to_string())?;
-            let content: String = row.get(2).map_err(|e| e.to_string())?;
-            let _distance: f32 = row.get(3).map_err(|e| e.to_string())?;
-            let chunk_content: String = row.get(4).map_err(|e| e.to_string())?;
+        for (m, (title, content, chunk_content, chunk_language)) in matches {
+            let id = m.document_id;
 
             let is_new = seen_docs.insert(id.clone());
             if is_new {
@@ -554,17 +1500,26 @@ impl KbManager {
                 fts_rank: None,
                 vec_rank: None,
                 matched_chunks: Vec::new(),
+                chunk_languages: Vec::new(),
             });
 
             if is_new {
                 entry.vec_rank = Some(vec_counter);
             }
             entry.matched_chunks.push(chunk_content);
+            entry.chunk_languages.extend(chunk_language);
         }
 
         // 3. Compute Reciprocal Rank Fusion (RRF)
         let mut scored_results = Vec::new();
         let rrf_k = 60.0f32;
+        let mut language_stmt = conn
+            .prepare_cached(
+                "SELECT language FROM document_chunks
+                 WHERE document_id = ?1 AND language IS NOT NULL
+                 GROUP BY language ORDER BY COUNT(*) DESC, language LIMIT 1",
+            )
+            .map_err(|e| format!("Language query prepare failed: {e}"))?;
 
         for (_, doc) in doc_map {
             let fts_term = match doc.fts_rank {
@@ -575,7 +1530,19 @@ impl KbManager {
                 Some(r) => 1.0 / (rrf_k + r as f32),
                 None => 0.0,
             };
-            let score = fts_term + vec_term;
+            let mut score = fts_term + vec_term;
+
+            let document_language: Option<String> = language_stmt
+                .query_row(params![doc.id], |row| row.get(0))
+                .optional()
+                .map_err(|e| format!("Language query failed: {e}"))?;
+            if let Some(pref) = language.as_ref().filter(|pref| pref.mode == LanguageMode::Boost) {
+                let in_language = document_language.as_deref() == Some(pref.language.as_str())
+                    || doc.chunk_languages.contains(&pref.language);
+                if in_language {
+                    score *= language::LANGUAGE_BOOST;
+                }
+            }
 
             scored_results.push(HybridSearchResult {
                 document_id: doc.id,
@@ -583,6 +1550,9 @@ impl KbManager {
                 content: doc.content,
                 score,
                 matched_chunks: doc.matched_chunks,
+                summary: None,
+                keywords: Vec::new(),
+                language: document_language,
             });
         }
 
@@ -590,36 +1560,124 @@ impl KbManager {
         scored_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
         scored_results.truncate(limit);
 
+        let mut fresh = summaries::load_fresh(
+            &conn,
+            scored_results.iter().map(|r| (r.document_id.as_str(), r.content.as_str())),
+        )?;
+        for result in &mut scored_results {
+            if let Some(found) = fresh.remove(&result.document_id) {
+                result.summary = Some(found.summary);
+                result.keywords = found.keywords;
+            }
+        }
+
         Ok(scored_results)
     }
 
-    /// Retrieve the documents and edges associated with a project group.
-    pub async fn get_project_graph(&self, group_id: String) -> Result<ProjectGraphRecord, String> {
-        let conn = self.db.lock().await;
+    /// Hybrid search over the query plus LLM-generated rewrites (see `query_expansion`),
+    /// fused with Reciprocal Rank Fusion. Rewrites are cached per query and model. When
+    /// the LLM fails, this degrades to a plain hybrid search instead of erroring.
+    pub async fn search_expanded(
+        &self,
+        query: String,
+        limit: usize,
+        group_ids: Option<Vec<String>>,
+        language: Option<LanguagePreference>,
+        mode: QueryExpansion,
+        generator: &dyn TextGenerator,
+    ) -> Result<Vec<HybridSearchResult>, String> {
+        let model_id = generator.model_id();
+        let cached = {
+            let conn = self.read_connection().await;
+            query_expansion::read_cached(&conn, mode, &model_id, &query)?
+        };
+        let expansions = match cached {
+            Some(expansions) => expansions,
+            None => match generator.generate(&query_expansion::build_prompt(mode, &query)).await {
+                Ok(response) => {
+                    let expansions = query_expansion::parse_response(mode, &query, &response);
+                    let conn = self.db.lock().await;
+                    query_expansion::store_cached(&conn, mode, &model_id, &query, &expansions)?;
+                    expansions
+                }
+                Err(e) => {
+                    eprintln!("[knowledge_base] query expansion failed, searching without it: {e}");
+                    Vec::new()
+                }
+            },
+        };
 
-        let group_title = Path::new(&group_id)
-            .file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or(&group_id)
-            .to_string();
+        let mut result_lists = Vec::with_capacity(expansions.len() + 1);
+        for q in std::iter::once(query).chain(expansions) {
+            result_lists.push(
+                self.search_hybrid_with_language(q, limit, group_ids.clone(), language.clone())
+                    .await?,
+            );
+        }
+        Ok(query_expansion::fuse(result_lists, limit))
+    }
 
-        conn.execute(
-            "INSERT OR IGNORE INTO groups (id, title) VALUES (?1, ?2)",
-            params![group_id, group_title],
-        )
-        .map_err(|e| format!("Failed to ensure group: {e}"))?;
+    /// Write summaries for up to `batch` documents whose summary is missing or stale.
+    /// Returns how many were written. A document the LLM fails on is recorded as failed
+    /// so it cannot hold up the queue; an LLM error also ends the pass with that error.
+    pub async fn summarize_pending(&self, generator: &dyn TextGenerator, batch: usize) -> Result<usize, String> {
+        let pending = {
+            let conn = self.read_connection().await;
+            summaries::pending_documents(&conn, batch)?
+        };
 
+        let model_id = generator.model_id();
+        let mut written = 0;
+        for document in pending {
+            let response = generator
+                .generate(&summaries::build_prompt(&document.title, &document.content))
+                .await;
+            let summary = match &response {
+                Ok(response) => summaries::parse_response(response),
+                Err(_) => None,
+            };
+            if summary.is_none() {
+                eprintln!("[knowledge_base] no usable summary for {}, skipping", document.id);
+            }
+
+            let conn = self.db.lock().await;
+            // The document may have been deleted while the LLM was busy.
+            let exists: bool = conn
+                .query_row("SELECT EXISTS(SELECT 1 FROM documents WHERE id = ?1)", params![document.id], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            if exists {
+                match &summary {
+                    Some(summary) => {
+                        summaries::store_summary(&conn, &document.id, &document.content, summary, &model_id)?;
+                        written += 1;
+                    }
+                    None => summaries::store_failure(&conn, &document.id, &document.content, &model_id)?,
+                }
+            }
+            response?;
+        }
+        Ok(written)
+    }
+
+    /// Retrieve the documents and edges associated with a project group.
+    pub async fn get_project_graph(&self, group_id: String) -> Result<ProjectGraphRecord, String> {
+        let conn = self.read_connection().await;
+
+        // Read connections are read-only, so an unknown group is reported under the
+        // title it would get rather than created here.
         let group_title: String = conn
             .query_row(
                 "SELECT title FROM groups WHERE id = ?1",
                 params![group_id],
                 |row| row.get(0),
             )
-            .map_err(|e| e.to_string())?;
+            .optional()
+            .map_err(|e| e.to_string())?
+            .unwrap_or_else(|| group_title_from_id(&group_id));
 
         let mut doc_stmt = conn
             .prepare(
-                "SELECT d.id, d.title, d.content
+                "SELECT d.id, d.title, d.content, d.created_at, d.updated_at
                  FROM documents d
                  INNER JOIN document_groups dg ON dg.document_id = d.id
                  WHERE dg.group_id = ?1
@@ -627,18 +1685,30 @@ impl KbManager {
             )
             .map_err(|e| e.to_string())?;
 
-        let documents = doc_stmt
+        let mut documents = doc_stmt
             .query_map(params![group_id], |row| {
                 Ok(GraphDocumentRecord {
                     id: row.get(0)?,
                     title: row.get(1)?,
                     content: row.get(2)?,
+                    created_at: row.get(3)?,
+                    updated_at: row.get(4)?,
+                    summary: None,
+                    keywords: Vec::new(),
                 })
             })
             .map_err(|e| e.to_string())?
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| e.to_string())?;
 
+        let mut fresh = summaries::load_fresh(&conn, documents.iter().map(|d| (d.id.as_str(), d.content.as_str())))?;
+        for document in &mut documents {
+            if let Some(found) = fresh.remove(&document.id) {
+                document.summary = Some(found.summary);
+                document.keywords = found.keywords;
+            }
+        }
+
         let mut edge_stmt = conn
             .prepare(
                 "SELECT e.id, e.source_id, e.target_id, e.type
@@ -670,6 +1740,662 @@ impl KbManager {
         })
     }
 
+    /// List every group with its parent and the number of documents attached directly to it.
+    pub async fn list_groups(&self) -> Result<Vec<GroupRecord>, String> {
+        let conn = self.read_connection().await;
+        let mut stmt = conn
+            .prepare(
+                "SELECT g.id, g.title, g.parent_id, COUNT(dg.document_id)
+                 FROM groups g
+                 LEFT JOIN document_groups dg ON dg.group_id = g.id
+                 GROUP BY g.id
+                 ORDER BY g.title COLLATE NOCASE",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let groups = stmt
+            .query_map([], |row| {
+                Ok(GroupRecord {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    parent_id: row.get(2)?,
+                    document_count: row.get(3)?,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        Ok(groups)
+    }
+
+    /// Every tag with the number of documents carrying it, most used first. With a
+    /// `group_id`, only documents in that group and its subgroups are counted.
+    pub async fn list_tags(&self, group_id: Option<String>) -> Result<Vec<TagRecord>, String> {
+        let conn = self.read_connection().await;
+        let (sql, scope_json) = match group_id {
+            Some(group_id) => (
+                format!(
+                    "WITH RECURSIVE {}
+                     SELECT t.tag, COUNT(DISTINCT t.document_id)
+                     FROM document_tags t
+                     INNER JOIN document_groups dg ON dg.document_id = t.document_id
+                     INNER JOIN scope s ON s.id = dg.group_id
+                     GROUP BY t.tag
+                     ORDER BY 2 DESC, t.tag",
+                    group_scope_cte(1)
+                ),
+                Some(serde_json::json!([group_id]).to_string()),
+            ),
+            None => (
+                "SELECT tag, COUNT(*) FROM document_tags
+                 GROUP BY tag
+                 ORDER BY 2 DESC, tag"
+                    .to_string(),
+                None,
+            ),
+        };
+
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let map_row = |row: &rusqlite::Row| {
+            Ok(TagRecord {
+                tag: row.get(0)?,
+                document_count: row.get(1)?,
+            })
+        };
+        let tags = match scope_json {
+            Some(scope_json) => stmt.query_map(params![scope_json], map_row),
+            None => stmt.query_map([], map_row),
+        }
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+        Ok(tags)
+    }
+
+    /// Section documents of a markdown file indexed section by section, in file order.
+    /// Empty for documents that were not split.
+    pub async fn get_document_sections(&self, id: String) -> Result<Vec<DocumentSectionRecord>, String> {
+        let conn = self.read_connection().await;
+        let prefix = format!("{id}#section:");
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, title FROM documents
+                 WHERE substr(id, 1, length(?1)) = ?1
+                 ORDER BY section_position, rowid",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let sections = stmt
+            .query_map(params![prefix], |row| {
+                let id: String = row.get(0)?;
+                Ok(DocumentSectionRecord {
+                    anchor: id[prefix.len()..].to_string(),
+                    id,
+                    title: row.get(1)?,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        Ok(sections)
+    }
+
+    /// Replace the groups of `doc_id` with `group_ids`. `upsert_document` only adds
+    /// groups; this also detaches the document from the ones not listed. Returns whether
+    /// the groups changed, in which case the change feed records it.
+    pub async fn set_document_groups(&self, doc_id: String, group_ids: Vec<String>) -> Result<bool, String> {
+        let wanted: std::collections::BTreeSet<String> =
+            group_ids.into_iter().filter(|id| !id.trim().is_empty()).collect();
+
+        let mut conn = self.db.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {e}"))?;
+
+        let exists: bool = tx
+            .query_row("SELECT EXISTS(SELECT 1 FROM documents WHERE id = ?1)", params![doc_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        if !exists {
+            return Err(format!("Document not found: {doc_id}"));
+        }
+
+        let current: std::collections::BTreeSet<String> = {
+            let mut stmt = tx
+                .prepare("SELECT group_id FROM document_groups WHERE document_id = ?1")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![doc_id], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+        if current == wanted {
+            return Ok(false);
+        }
+
+        tx.execute("DELETE FROM document_groups WHERE document_id = ?1", params![doc_id])
+            .map_err(|e| format!("Failed to detach document from groups: {e}"))?;
+        attach_groups(&tx, &doc_id, &wanted.into_iter().collect::<Vec<_>>())?;
+        record_change(&tx, &doc_id, "upsert", &now_timestamp(), None)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {e}"))?;
+        Ok(true)
+    }
+
+    /// Change the display title of a group. Its id (the workspace/folder path) is unchanged.
+    pub async fn rename_group(&self, group_id: String, title: String) -> Result<(), String> {
+        let title = title.trim().to_string();
+        if title.is_empty() {
+            return Err("Group title cannot be empty".to_string());
+        }
+
+        let conn = self.db.lock().await;
+        let updated = conn
+            .execute(
+                "UPDATE groups SET title = ?2 WHERE id = ?1",
+                params![group_id, title],
+            )
+            .map_err(|e| format!("Failed to rename group: {e}"))?;
+
+        if updated == 0 {
+            return Err(format!("Group not found: {group_id}"));
+        }
+        Ok(())
+    }
+
+    /// Move every document and subgroup of `source_id` into `target_id`, then remove `source_id`.
+    pub async fn merge_groups(&self, source_id: String, target_id: String) -> Result<(), String> {
+        if source_id == target_id {
+            return Err("Cannot merge a group into itself".to_string());
+        }
+
+        let mut conn = self.db.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start merge transaction: {e}"))?;
+
+        let both_exist: i64 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM groups WHERE id IN (?1, ?2)",
+                params![source_id, target_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if both_exist != 2 {
+            return Err("Both groups must exist to merge them".to_string());
+        }
+
+        let target_is_descendant: bool = tx
+            .query_row(
+                &format!(
+                    "WITH RECURSIVE {} SELECT EXISTS(SELECT 1 FROM scope WHERE id = ?2)",
+                    group_scope_cte(1)
+                ),
+                params![serde_json::json!([source_id]).to_string(), target_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if target_is_descendant {
+            return Err("Cannot merge a group into one of its own subgroups".to_string());
+        }
+
+        tx.execute(
+            "INSERT OR IGNORE INTO document_groups (document_id, group_id)
+             SELECT document_id, ?2 FROM document_groups WHERE group_id = ?1",
+            params![source_id, target_id],
+        )
+        .map_err(|e| format!("Failed to move documents: {e}"))?;
+
+        tx.execute(
+            "UPDATE groups SET parent_id = ?2 WHERE parent_id = ?1",
+            params![source_id, target_id],
+        )
+        .map_err(|e| format!("Failed to move subgroups: {e}"))?;
+
+        tx.execute("DELETE FROM groups WHERE id = ?1", params![source_id])
+            .map_err(|e| format!("Failed to delete merged group: {e}"))?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit merge transaction: {e}"))?;
+
+        Ok(())
+    }
+
+    /// Delete a group and all of its subgroups. When `delete_exclusive_documents` is set,
+    /// documents that belong to no group outside the deleted subtree are removed as well.
+    pub async fn delete_group(
+        &self,
+        group_id: String,
+        delete_exclusive_documents: bool,
+    ) -> Result<DeleteGroupResult, String> {
+        let mut conn = self.db.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start delete transaction: {e}"))?;
+
+        let scope_json = serde_json::json!([group_id]).to_string();
+
+        let exclusive_ids: Vec<String> = if delete_exclusive_documents {
+            let mut stmt = tx
+                .prepare(&format!(
+                    "WITH RECURSIVE {}
+                     SELECT DISTINCT dg.document_id
+                     FROM document_groups dg
+                     INNER JOIN scope s ON s.id = dg.group_id
+                     WHERE NOT EXISTS (
+                         SELECT 1 FROM document_groups other
+                         WHERE other.document_id = dg.document_id
+                           AND other.group_id NOT IN (SELECT id FROM scope)
+                     )",
+                    group_scope_cte(1)
+                ))
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![scope_json], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?
+        } else {
+            Vec::new()
+        };
+
+        let deleted_groups: i64 = tx
+            .query_row(
+                &format!("WITH RECURSIVE {} SELECT COUNT(*) FROM scope", group_scope_cte(1)),
+                params![scope_json],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+
+        delete_documents_by_id(&tx, &exclusive_ids)?;
+
+        // Subgroups and document links cascade from the group row.
+        let removed = tx
+            .execute("DELETE FROM groups WHERE id = ?1", params![group_id])
+            .map_err(|e| format!("Failed to delete group: {e}"))?;
+        if removed == 0 {
+            return Err(format!("Group not found: {group_id}"));
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit delete transaction: {e}"))?;
+
+        Ok(DeleteGroupResult {
+            deleted_groups: deleted_groups as usize,
+            deleted_documents: exclusive_ids.len(),
+        })
+    }
+
+    /// Documents in `group_id` (or its subgroups), or every document with `None`, ordered
+    /// by most recently updated, optionally only those updated at or after `since` (RFC 3339).
+    pub async fn list_recent_documents(
+        &self,
+        group_id: Option<String>,
+        since: Option<String>,
+        limit: usize,
+    ) -> Result<Vec<RecentDocumentRecord>, String> {
+        let conn = self.read_connection().await;
+        let mut stmt = conn
+            .prepare(&format!(
+                "WITH RECURSIVE {}
+                 SELECT d.id, d.title, d.created_at, d.updated_at
+                 FROM documents d
+                 WHERE (?1 IS NULL OR d.id IN (
+                     SELECT dg.document_id FROM document_groups dg
+                     INNER JOIN scope s ON s.id = dg.group_id
+                 ))
+                   AND (?2 IS NULL OR d.updated_at >= ?2)
+                 ORDER BY d.updated_at DESC
+                 LIMIT ?3",
+                group_scope_cte(1)
+            ))
+            .map_err(|e| e.to_string())?;
+
+        let scope_json = group_id.map(|id| serde_json::json!([id]).to_string());
+        let documents = stmt
+            .query_map(params![scope_json, since, limit as i64], |row| {
+                Ok(RecentDocumentRecord {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    created_at: row.get(2)?,
+                    updated_at: row.get(3)?,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        Ok(documents)
+    }
+
+    /// Report exact duplicate chunks and near-duplicate document pairs within a group
+    /// (including its sub-groups), comparing the stored chunk embeddings.
+    pub async fn find_duplicates(
+        &self,
+        group_id: String,
+        similarity_threshold: f32,
+        min_overlap: f32,
+    ) -> Result<DuplicateReport, String> {
+        let chunks = {
+            let conn = self.read_connection().await;
+            let quantization = vector_index::read_quantization(&conn)?;
+            let mut stmt = conn
+                .prepare(&format!(
+                    "WITH RECURSIVE {}
+                     SELECT c.chunk_id, c.document_id, d.title, c.content
+                     FROM document_chunks c
+                     INNER JOIN documents d ON d.id = c.document_id
+                     WHERE c.document_id IN (
+                         SELECT dg.document_id FROM document_groups dg
+                         INNER JOIN scope s ON s.id = dg.group_id
+                     )
+                     ORDER BY c.document_id, c.chunk_index",
+                    group_scope_cte(1)
+                ))
+                .map_err(|e| e.to_string())?;
+
+            let scope_json = serde_json::json!([group_id]).to_string();
+            let rows = stmt
+                .query_map(params![scope_json], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+                })
+                .map_err(|e| e.to_string())?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+
+            // Copies outside the group, and the chunk's own document, must not take the
+            // neighbor slots of the in-group duplicates.
+            let group_chunks: std::collections::HashSet<String> = rows.iter().map(|(chunk_id, ..)| chunk_id.clone()).collect();
+            let mut chunks = Vec::with_capacity(rows.len());
+            for (chunk_id, document_id, title, content) in rows {
+                let vector = vector_index::chunk_vector(&conn, quantization, &chunk_id)?;
+                let neighbors = match &vector {
+                    Some(vector) => knn_filtered(
+                        &conn,
+                        vector,
+                        DUPLICATE_NEIGHBORS,
+                        DUPLICATE_NEIGHBORS * SCOPED_KNN_OVERSAMPLE,
+                        |m| Ok((m.document_id != document_id && group_chunks.contains(&m.chunk_id)).then_some(())),
+                    )?
+                    .into_iter()
+                    .map(|(m, ())| m.chunk_id)
+                    .collect(),
+                    None => Vec::new(),
+                };
+                chunks.push(ChunkEntry { chunk_id, document_id, title, content, vector, neighbors });
+            }
+            chunks
+        };
+
+        tokio::task::spawn_blocking(move || DuplicateReport {
+            exact_duplicates: duplicates::find_exact_duplicates(&chunks),
+            near_duplicates: duplicates::find_near_duplicates(&chunks, similarity_threshold, min_overlap),
+            group_id,
+            similarity_threshold,
+            min_overlap,
+        })
+        .await
+        .map_err(|e| format!("Duplicate scan failed: {e}"))
+    }
+
+    /// Return document changes recorded after `cursor` (0 for the full feed), oldest first.
+    /// When `group_id` is given, changes are limited to that group and its subgroups.
+    /// Deletions are matched by the groups stored on the tombstone; tombstones written
+    /// before those were recorded are always included.
+    pub async fn get_changes_since(
+        &self,
+        cursor: i64,
+        limit: usize,
+        group_id: Option<String>,
+    ) -> Result<ChangeFeedPage, String> {
+        let conn = self.read_connection().await;
+        let mut stmt = conn
+            .prepare(&format!(
+                "WITH RECURSIVE {}
+                 SELECT c.seq, c.document_id, c.change_type, c.changed_at, d.title
+                 FROM document_changes c
+                 LEFT JOIN documents d ON d.id = c.document_id
+                 WHERE c.seq > ?2
+                   AND (?1 IS NULL OR c.document_id IN (
+                       SELECT dg.document_id FROM document_groups dg
+                       INNER JOIN scope s ON s.id = dg.group_id
+                   ) OR (c.change_type = 'delete' AND (
+                       -- Tombstones written before deletions kept their groups.
+                       c.group_ids IS NULL OR EXISTS (
+                           SELECT 1 FROM json_each(c.group_ids) j
+                           INNER JOIN scope s ON s.id = j.value
+                       )
+                   )))
+                 ORDER BY c.seq ASC
+                 LIMIT ?3",
+                group_scope_cte(1)
+            ))
+            .map_err(|e| e.to_string())?;
+
+        let scope_json = group_id.map(|id| serde_json::json!([id]).to_string());
+        // Fetch one extra row to know whether another page follows.
+        let mut changes = stmt
+            .query_map(params![scope_json, cursor, limit as i64 + 1], |row| {
+                Ok(DocumentChangeRecord {
+                    seq: row.get(0)?,
+                    document_id: row.get(1)?,
+                    change_type: row.get(2)?,
+                    changed_at: row.get(3)?,
+                    title: row.get(4)?,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        let has_more = changes.len() > limit;
+        changes.truncate(limit);
+        let next_cursor = changes.last().map(|c| c.seq).unwrap_or(cursor);
+
+        Ok(ChangeFeedPage {
+            changes,
+            next_cursor,
+            has_more,
+        })
+    }
+
+    /// List the stored revisions of a document, newest first (content omitted).
+    pub async fn list_revisions(&self, document_id: String) -> Result<Vec<RevisionRecord>, String> {
+        let conn = self.read_connection().await;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, document_id, title, content_hash, created_at, length(content)
+                 FROM document_revisions
+                 WHERE document_id = ?1
+                 ORDER BY id DESC",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let revisions = stmt
+            .query_map(params![document_id], |row| {
+                Ok(RevisionRecord {
+                    id: row.get(0)?,
+                    document_id: row.get(1)?,
+                    title: row.get(2)?,
+                    content_hash: row.get(3)?,
+                    created_at: row.get(4)?,
+                    size: row.get(5)?,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        Ok(revisions)
+    }
+
+    /// Fetch a revision's metadata together with its full content.
+    async fn get_revision(&self, revision_id: i64) -> Result<(RevisionRecord, String), String> {
+        let conn = self.read_connection().await;
+        conn.query_row(
+            "SELECT id, document_id, title, content_hash, created_at, length(content), content
+             FROM document_revisions WHERE id = ?1",
+            params![revision_id],
+            |row| {
+                Ok((
+                    RevisionRecord {
+                        id: row.get(0)?,
+                        document_id: row.get(1)?,
+                        title: row.get(2)?,
+                        content_hash: row.get(3)?,
+                        created_at: row.get(4)?,
+                        size: row.get(5)?,
+                    },
+                    row.get(6)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Revision not found: {revision_id}"))
+    }
+
+    /// Line diff from revision `from_id` to revision `to_id`.
+    pub async fn diff_revisions(&self, from_id: i64, to_id: i64) -> Result<RevisionDiff, String> {
+        let (from, from_content) = self.get_revision(from_id).await?;
+        let (to, to_content) = self.get_revision(to_id).await?;
+        if from.document_id != to.document_id {
+            return Err(format!(
+                "Revisions {from_id} and {to_id} belong to different documents"
+            ));
+        }
+
+        let lines = diff_lines(&from_content, &to_content);
+        let added = lines
+            .iter()
+            .filter(|l| l.kind == DiffLineKind::Added)
+            .count();
+        let removed = lines
+            .iter()
+            .filter(|l| l.kind == DiffLineKind::Removed)
+            .count();
+
+        Ok(RevisionDiff {
+            from,
+            to,
+            added,
+            removed,
+            lines,
+        })
+    }
+
+    /// Write a revision's title and content back as the current document version.
+    /// The document keeps its current groups; the restore itself is recorded as a new revision.
+    /// Documents indexed from a file are rejected, as the next re-index would overwrite them.
+    pub async fn restore_revision(&self, revision_id: i64) -> Result<String, String> {
+        let (revision, content) = self.get_revision(revision_id).await?;
+        if revision.document_id.starts_with("file:") {
+            return Err(format!(
+                "{} is indexed from a file; edit the file to restore an earlier version",
+                revision.document_id
+            ));
+        }
+
+        let group_ids: Vec<String> = {
+            let conn = self.read_connection().await;
+            let mut stmt = conn
+                .prepare("SELECT group_id FROM document_groups WHERE document_id = ?1")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![revision.document_id], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?
+        };
+
+        self.upsert_document(Some(revision.document_id), revision.title, content, group_ids)
+            .await
+    }
+
+    /// Quantization mode currently used for chunk embeddings.
+    pub async fn get_vector_quantization(&self) -> Result<VectorQuantization, String> {
+        let conn = self.read_connection().await;
+        vector_index::read_quantization(&conn)
+    }
+
+    /// Switch the embedding storage mode. The vec0 table is rebuilt with the new column
+    /// type from the stored float32 vectors in one transaction, so searches never see a
+    /// partial index. Returns the number of documents in the rebuilt index.
+    pub async fn set_vector_quantization(&self, quantization: VectorQuantization) -> Result<usize, String> {
+        let dims = self.embedder.read().await.dimensions();
+        let mut conn = self.db.lock().await;
+        // The stored vectors keep the size they were embedded with.
+        let dims = vector_index::table_dimensions(&conn)?.unwrap_or(dims);
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {e}"))?;
+        let documents = vector_index::requantize(&tx, dims, quantization)?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {e}"))?;
+        Ok(documents)
+    }
+
+    /// Make the embeddings table match an embedder with `dims` dimensions. An empty
+    /// table is recreated; one that already holds vectors of another size is an error,
+    /// as only re-indexing can replace them.
+    pub async fn fit_embeddings_table(&self, dims: usize) -> Result<(), String> {
+        let mut conn = self.db.lock().await;
+        if vector_index::table_dimensions(&conn)? == Some(dims) {
+            return Ok(());
+        }
+        let chunks: i64 = conn
+            .query_row("SELECT COUNT(*) FROM document_chunks", [], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        if chunks > 0 {
+            return Err(format!(
+                "The knowledge base was embedded with a different model; the current one returns \
+                 {dims} dimensions. Re-index from Settings to use it."
+            ));
+        }
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {e}"))?;
+        tx.execute_batch("DROP TABLE IF EXISTS documents_embeddings;")
+            .map_err(|e| format!("Failed to clear embeddings: {e}"))?;
+        let quantization = vector_index::read_quantization(&tx)?;
+        vector_index::create_embeddings_table(&tx, dims, quantization)?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {e}"))
+    }
+
+    /// Re-chunk and re-embed every document with the active embedder and quantization mode,
+    /// leaving titles, content, groups, edges and timestamps untouched.
+    pub async fn reembed_all_documents(&self) -> Result<usize, String> {
+        let documents: Vec<(String, String)> = {
+            let conn = self.read_connection().await;
+            let mut stmt = conn
+                .prepare("SELECT id, content FROM documents")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?
+        };
+
+        let opts = ChunkOptions::default();
+        for (doc_id, content) in &documents {
+            let chunks = chunk_text(content, &opts);
+            let embeddings = self.embed_chunks(&chunks).await?;
+
+            let mut conn = self.db.lock().await;
+            let tx = conn
+                .transaction()
+                .map_err(|e| format!("Failed to start transaction: {e}"))?;
+            replace_chunks(&tx, doc_id, &chunks, &embeddings)?;
+            tx.commit()
+                .map_err(|e| format!("Failed to commit transaction: {e}"))?;
+        }
+
+        Ok(documents.len())
+    }
+
     /// Re-evaluate all document contents, resolve wikilinks, and reconstruct the graph edges.
     pub async fn rebuild_all_edges(&self) -> Result<(), String> {
         let mut conn = self.db.lock().await;
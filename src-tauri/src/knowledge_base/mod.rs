@@ -1,14 +1,25 @@
 use std::sync::Mutex;
-use tauri::Manager;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
 use tauri_plugin_store::StoreExt;
 
 pub mod commands;
 pub mod db;
+pub mod duplicates;
 pub mod embedding;
+pub mod image_extractor;
+pub mod language;
+pub mod lease;
 pub mod manager;
 pub mod parser;
+pub mod query_expansion;
+pub mod rag;
+pub mod recovery;
+pub mod revisions;
+pub mod summaries;
+pub mod vector_index;
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 
 /// Tauri managed state wrapping the shared knowledge base manager.
 pub struct KbState(pub manager::KbManager);
@@ -16,6 +27,155 @@ pub struct KbState(pub manager::KbManager);
 /// Current project/group selected by the UI.
 pub struct CurrentProjectGroup(pub Mutex<Option<String>>);
 
+/// Map the stored model name to what `init_embedder_with_config` expects.
+/// GGUF models are stored by filename and live in the LLM models directory.
+pub(crate) fn resolve_embedding_model(
+    app_handle: &tauri::AppHandle,
+    model_type: &str,
+    model_name: &str,
+) -> Result<String, String> {
+    if model_type == "gguf" {
+        let path = crate::llm::models::resolve_model_path(app_handle, model_name)?;
+        Ok(path.to_string_lossy().to_string())
+    } else {
+        Ok(model_name.to_string())
+    }
+}
+
+/// First wait before retrying an embedding server that did not answer at startup.
+/// Doubles after every failure, up to `EMBEDDING_SERVER_MAX_RETRY`.
+const EMBEDDING_SERVER_RETRY: Duration = Duration::from_secs(5);
+const EMBEDDING_SERVER_MAX_RETRY: Duration = Duration::from_secs(120);
+
+/// Tauri event carrying the state of the background connection to an embedding server.
+const EMBEDDER_STATUS_EVENT: &str = "kb-embedder-status";
+
+/// How often the summary job looks for new or changed documents.
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(120);
+
+/// Documents summarized per `summarize_pending` call, between checks of the setting.
+const SUMMARY_BATCH: usize = 10;
+
+/// Whether the background summary job is switched on (`kb_auto_summaries`, off by default).
+pub(crate) fn auto_summaries_enabled(app_handle: &tauri::AppHandle) -> bool {
+    app_handle
+        .store("store.json")
+        .ok()
+        .and_then(|store| store.get("kb_auto_summaries"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// Summarize new and changed documents with the configured LLM provider while the
+/// setting is on. Failures (provider offline, no model) are retried on the next tick.
+fn spawn_summary_task(app_handle: tauri::AppHandle, kb_manager: manager::KbManager) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(SUMMARY_INTERVAL).await;
+            if !auto_summaries_enabled(&app_handle) {
+                continue;
+            }
+            let Some(llm_state) = app_handle.try_state::<crate::llm::LlmState>() else {
+                continue;
+            };
+            let config = crate::llm::settings::load_config_internal(&app_handle, &llm_state);
+            let generator = match crate::llm::providers::ProviderTextGenerator::new(config, &llm_state).await {
+                Ok(generator) => generator,
+                Err(e) => {
+                    eprintln!("[knowledge_base] summaries paused, LLM unavailable: {e}");
+                    continue;
+                }
+            };
+
+            while auto_summaries_enabled(&app_handle) {
+                match kb_manager.summarize_pending(&generator, SUMMARY_BATCH).await {
+                    Ok(0) => break,
+                    Ok(written) => println!("[knowledge_base] summarized {written} documents"),
+                    Err(e) => {
+                        eprintln!("[knowledge_base] summary pass failed: {e}");
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Connect to the configured embedding server without holding up startup. The
+/// placeholder embedder serves until the server answers; failures are retried with
+/// backoff and reported to the UI through `EMBEDDER_STATUS_EVENT`.
+fn spawn_embedding_server_connect(
+    app_handle: tauri::AppHandle,
+    kb_manager: manager::KbManager,
+    endpoint: String,
+    model_name: String,
+    api_key: Option<String>,
+) {
+    tauri::async_runtime::spawn(async move {
+        let mut delay = EMBEDDING_SERVER_RETRY;
+        loop {
+            let (endpoint, model_name, api_key) = (endpoint.clone(), model_name.clone(), api_key.clone());
+            let connected = tauri::async_runtime::spawn_blocking(move || {
+                embedding::openai_compatible::OpenAiCompatibleProvider::connect(&endpoint, &model_name, api_key)
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+
+            let provider = match connected {
+                Ok(provider) => provider,
+                Err(e) => {
+                    eprintln!("[knowledge_base] embedding server unavailable, retrying in {delay:?}: {e}");
+                    let _ = app_handle.emit(
+                        EMBEDDER_STATUS_EVENT,
+                        serde_json::json!({ "status": "unavailable", "error": e, "retryInSeconds": delay.as_secs() }),
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(EMBEDDING_SERVER_MAX_RETRY);
+                    continue;
+                }
+            };
+
+            let embedder = kb_manager.embedder_lock();
+            let mut active = embedder.write().await;
+            // A model picked in Settings meanwhile has already replaced the placeholder.
+            if active.name() != "dummy" {
+                return;
+            }
+            let status = match kb_manager.fit_embeddings_table(provider.dimensions()).await {
+                Ok(()) => {
+                    *active = Box::new(provider);
+                    serde_json::json!({ "status": "ready" })
+                }
+                Err(e) => {
+                    eprintln!("[knowledge_base] {e}");
+                    serde_json::json!({ "status": "error", "error": e })
+                }
+            };
+            let _ = app_handle.emit(EMBEDDER_STATUS_EVENT, status);
+            return;
+        }
+    });
+}
+
+/// Hold the indexer lease while the app runs. The app is the primary writer, so MCP
+/// servers started with `--watch` stay passive until it closes and the lease expires.
+fn spawn_indexer_lease_task(kb_manager: manager::KbManager) {
+    tauri::async_runtime::spawn(async move {
+        let holder = lease::holder_id("app");
+        loop {
+            {
+                let db = kb_manager.db_lock();
+                let conn = db.lock().await;
+                if let Err(e) = lease::claim(&conn, lease::INDEXER_LEASE, &holder, lease::LEASE_TTL) {
+                    eprintln!("[knowledge_base] {e}");
+                }
+            }
+            tokio::time::sleep(lease::LEASE_RENEW_INTERVAL).await;
+        }
+    });
+}
+
 /// Initialise both the database and the embedder, returning the two managed
 /// states to be registered with `app.manage(...)` inside Tauri's `.setup()`.
 pub fn init_knowledge_base(
@@ -25,7 +185,7 @@ pub fn init_knowledge_base(
     let cache_dir = app_handle.path().app_cache_dir().ok();
 
     // Read saved configuration
-    let (model_type, model_name, openai_key) = if let Ok(store) = app_handle.store("store.json") {
+    let (model_type, model_name, openai_key, endpoint) = if let Ok(store) = app_handle.store("store.json") {
         let m_type = store.get("embedding_model_type")
             .and_then(|v| v.as_str().map(|s| s.to_string()))
             .unwrap_or_else(|| "local".to_string());
@@ -34,19 +194,77 @@ pub fn init_knowledge_base(
             .unwrap_or_else(|| "all-MiniLM-L6-v2".to_string());
         let api_key = store.get("openai_api_key")
             .and_then(|v| v.as_str().map(|s| s.to_string()));
-        (m_type, m_name, api_key)
+        let endpoint = store.get("embedding_endpoint")
+            .and_then(|v| v.as_str().map(|s| s.to_string()));
+        (m_type, m_name, api_key, endpoint)
     } else {
-        ("local".to_string(), "all-MiniLM-L6-v2".to_string(), None)
+        ("local".to_string(), "all-MiniLM-L6-v2".to_string(), None, None)
     };
 
-    let embedder = embedding::init_embedder_with_config(cache_dir, &model_type, &model_name, openai_key, false)?;
+    // Probing the server can take as long as its timeout, so it connects in the
+    // background (see `spawn_embedding_server_connect`) behind the placeholder.
+    let server = (model_type == "server").then(|| (endpoint.clone().unwrap_or_default(), openai_key.clone()));
+    let embedder: Box<dyn embedding::Embedder> = if server.is_some() {
+        Box::new(embedding::DummyEmbedder)
+    } else {
+        match resolve_embedding_model(app_handle, &model_type, &model_name).and_then(|model_ref| {
+            embedding::init_embedder_with_config(cache_dir, &model_type, &model_ref, openai_key, endpoint, false)
+        }) {
+            Ok(embedder) => embedder,
+            // A GGUF file that was deleted must not take the whole knowledge base down.
+            Err(e) if model_type == "gguf" => {
+                eprintln!("[knowledge_base] embedding model unavailable: {e}");
+                Box::new(embedding::DummyEmbedder)
+            }
+            Err(e) => return Err(e),
+        }
+    };
     let dims = embedder.dimensions();
 
-    let conn = db::init_database(app_handle, dims)?;
-    
-    let kb_manager = manager::KbManager::new(conn, embedder);
+    let db_path = db::database_path(app_handle)?;
+    let conn = db::open_app_database(&db_path, dims)?;
+    let readers = db::open_read_connections(&db_path, db::READ_POOL_SIZE)?;
+
+    let kb_manager = manager::KbManager::new(conn, embedder).with_readers(readers);
     let shared_embedder = kb_manager.embedder_lock();
 
+    // Only the app recovers a corrupt database, so only the app finishes the job, also
+    // when an earlier run was closed before re-embedding completed.
+    {
+        let kb_manager = kb_manager.clone();
+        let db_path = db_path.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = recovery::finish_recovery(&kb_manager, &db_path).await {
+                eprintln!("[knowledge_base] re-embedding after recovery failed: {e}");
+            }
+        });
+    }
+    recovery::spawn_backup_task(kb_manager.clone(), db_path);
+    spawn_summary_task(app_handle.clone(), kb_manager.clone());
+    spawn_indexer_lease_task(kb_manager.clone());
+    if let Some((endpoint, api_key)) = server {
+        spawn_embedding_server_connect(app_handle.clone(), kb_manager.clone(), endpoint, model_name, api_key);
+    }
+
+    let image_extraction = app_handle
+        .store("store.json")
+        .ok()
+        .and_then(|store| store.get("kb_image_extraction"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if image_extraction {
+        if let Some(cache_dir) = app_handle.path().app_cache_dir().ok() {
+            let kb_manager = kb_manager.clone();
+            // Loading may download the OCR models, so keep it off the setup path.
+            tauri::async_runtime::spawn_blocking(move || {
+                match image_extractor::OcrsExtractor::load(&cache_dir) {
+                    Ok(extractor) => kb_manager.set_image_extractor(Some(std::sync::Arc::new(extractor))),
+                    Err(e) => eprintln!("[knowledge_base] image extraction unavailable: {e}"),
+                }
+            });
+        }
+    }
+
     Ok((
         KbState(kb_manager),
         embedding::EmbedderState(shared_embedder),
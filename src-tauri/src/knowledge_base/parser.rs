@@ -121,6 +121,50 @@ pub fn split_markdown_into_sections(content: &str) -> Vec<ParsedSection> {
     sections
 }
 
+/// An image embedded with `![alt](path)` that points at a local file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageRef {
+    /// Percent-decoded path as written in the markdown, usually relative to the file.
+    pub path: String,
+    pub alt: String,
+}
+
+/// Extract local image references, skipping remote URLs and inline `data:` images.
+pub fn extract_image_refs(content: &str) -> Vec<ImageRef> {
+    let mut images: Vec<ImageRef> = Vec::new();
+    let mut current: Option<ImageRef> = None;
+
+    for event in Parser::new(content) {
+        match event {
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                let url = dest_url.to_string();
+                if url.contains("://") || url.starts_with("data:") || url.is_empty() {
+                    continue;
+                }
+                let path = urlencoding::decode(&url)
+                    .map(|p| p.into_owned())
+                    .unwrap_or(url);
+                current = Some(ImageRef { path, alt: String::new() });
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(image) = current.as_mut() {
+                    image.alt.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Image) => {
+                if let Some(image) = current.take() {
+                    if !images.iter().any(|i| i.path == image.path) {
+                        images.push(image);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    images
+}
+
 /// Extract tags and links (both markdown links and wikilinks) from markdown content.
 pub fn extract_metadata(content: &str) -> ExtractedMetadata {
     let parser = Parser::new(content);
@@ -0,0 +1,235 @@
+//! LLM-assisted search: rewrite a short query into several queries ("expand") or into a
+//! hypothetical answer passage ("hyde", Hypothetical Document Embeddings), run hybrid
+//! retrieval for each, and fuse the ranked lists. Generated text is cached in
+//! `query_expansions` so a repeated query never calls the LLM twice.
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::manager::HybridSearchResult;
+
+/// Upper bound on alternative queries taken from an expansion response.
+const MAX_EXPANSIONS: usize = 4;
+
+/// Same constant as the keyword/vector fusion in `search_hybrid_in_groups`.
+const RRF_K: f32 = 60.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryExpansion {
+    /// Synonyms, related terms and sub-questions, one query each.
+    Expand,
+    /// A short passage written as if it answered the query.
+    Hyde,
+}
+
+impl QueryExpansion {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Expand => "expand",
+            Self::Hyde => "hyde",
+        }
+    }
+}
+
+/// Single-shot text generation used to rewrite queries. Implemented on top of the
+/// configured `LlmProvider`; kept as a trait so the knowledge base stays LLM-agnostic.
+#[async_trait]
+pub trait TextGenerator: Send + Sync {
+    async fn generate(&self, prompt: &str) -> Result<String, String>;
+    /// Identifies provider and model in the cache key, so switching models regenerates.
+    fn model_id(&self) -> String;
+}
+
+pub fn build_prompt(mode: QueryExpansion, query: &str) -> String {
+    match mode {
+        QueryExpansion::Expand => format!(
+            "Rewrite the search query below into up to {MAX_EXPANSIONS} alternative search queries \
+             for a project knowledge base. Use synonyms, related technical terms and more specific \
+             sub-questions. Reply with one query per line and nothing else.\n\nQuery: {query}"
+        ),
+        QueryExpansion::Hyde => format!(
+            "Write a short passage (3 to 5 sentences) from internal project documentation that \
+             answers the question below. Be specific and use the terms such a document would use. \
+             Reply with the passage only.\n\nQuestion: {query}"
+        ),
+    }
+}
+
+/// Turn the model's reply into search queries, dropping reasoning blocks, list markers,
+/// blank lines and repeats of the original query.
+pub fn parse_response(mode: QueryExpansion, query: &str, response: &str) -> Vec<String> {
+    let text = strip_think_blocks(response);
+    match mode {
+        QueryExpansion::Hyde => {
+            let passage = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if passage.is_empty() { Vec::new() } else { vec![passage] }
+        }
+        QueryExpansion::Expand => {
+            let mut queries: Vec<String> = Vec::new();
+            for line in text.lines() {
+                let line = strip_list_marker(line.trim()).trim_matches('"').trim();
+                if line.is_empty()
+                    || line.eq_ignore_ascii_case(query.trim())
+                    || line.to_lowercase().starts_with("query:")
+                    || queries.iter().any(|q| q.eq_ignore_ascii_case(line))
+                {
+                    continue;
+                }
+                queries.push(line.to_string());
+                if queries.len() == MAX_EXPANSIONS {
+                    break;
+                }
+            }
+            queries
+        }
+    }
+}
+
+/// Remove a leading `-`, `*`, `•`, `1.` or `1)` marker.
+fn strip_list_marker(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix(['-', '*', '•']) {
+        return rest.trim_start();
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits > 0 {
+        if let Some(rest) = line[digits..].strip_prefix(['.', ')']) {
+            return rest.trim_start();
+        }
+    }
+    line
+}
+
+fn strip_think_blocks(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<think>") {
+        out.push_str(&rest[..start]);
+        match rest[start..].find("</think>") {
+            Some(end) => rest = &rest[start + end + "</think>".len()..],
+            None => {
+                rest = "";
+                break;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn cache_key(mode: QueryExpansion, model_id: &str, query: &str) -> String {
+    let normalized = query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let digest = Sha256::digest(format!("{}\n{}\n{}", mode.as_str(), model_id, normalized).as_bytes());
+    format!("{digest:x}")
+}
+
+pub fn read_cached(
+    conn: &Connection,
+    mode: QueryExpansion,
+    model_id: &str,
+    query: &str,
+) -> Result<Option<Vec<String>>, String> {
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT expansions FROM query_expansions WHERE cache_key = ?1",
+            params![cache_key(mode, model_id, query)],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read query expansion cache: {e}"))?;
+    json.map(|j| serde_json::from_str(&j).map_err(|e| format!("Corrupt query expansion cache entry: {e}")))
+        .transpose()
+}
+
+pub fn store_cached(
+    conn: &Connection,
+    mode: QueryExpansion,
+    model_id: &str,
+    query: &str,
+    expansions: &[String],
+) -> Result<(), String> {
+    let json = serde_json::to_string(expansions).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO query_expansions (cache_key, mode, query, expansions, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            cache_key(mode, model_id, query),
+            mode.as_str(),
+            query,
+            json,
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+        ],
+    )
+    .map_err(|e| format!("Failed to cache query expansion: {e}"))?;
+    Ok(())
+}
+
+/// Reciprocal Rank Fusion across the result lists of every query. Matched chunks are
+/// merged so the caller still sees which passages matched.
+pub fn fuse(result_lists: Vec<Vec<HybridSearchResult>>, limit: usize) -> Vec<HybridSearchResult> {
+    let mut fused: Vec<HybridSearchResult> = Vec::new();
+    let mut positions: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+
+    for results in result_lists {
+        for (rank, result) in results.into_iter().enumerate() {
+            let term = 1.0 / (RRF_K + (rank + 1) as f32);
+            match positions.get(&result.document_id) {
+                Some(&i) => {
+                    let existing = &mut fused[i];
+                    existing.score += term;
+                    for chunk in result.matched_chunks {
+                        if !existing.matched_chunks.contains(&chunk) {
+                            existing.matched_chunks.push(chunk);
+                        }
+                    }
+                }
+                None => {
+                    positions.insert(result.document_id.clone(), fused.len());
+                    fused.push(HybridSearchResult { score: term, ..result });
+                }
+            }
+        }
+    }
+
+    fused.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    fused.truncate(limit);
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_expansion_lines() {
+        let response = "<think>user wants retries</think>\n1. backoff strategy\n- retry policy\n\
+                        * How are failed HTTP calls retried?\n\n\"exponential backoff\"\nbackoff strategy\n2FA retries\nsixth";
+        assert_eq!(
+            parse_response(QueryExpansion::Expand, "retry policy", response),
+            vec!["backoff strategy", "How are failed HTTP calls retried?", "exponential backoff", "2FA retries"]
+        );
+    }
+
+    #[test]
+    fn hyde_keeps_one_passage() {
+        let response = "Requests are retried\n  three times with exponential backoff.";
+        assert_eq!(
+            parse_response(QueryExpansion::Hyde, "retry policy", response),
+            vec!["Requests are retried three times with exponential backoff."]
+        );
+    }
+
+    #[test]
+    fn cache_key_ignores_case_and_spacing() {
+        assert_eq!(
+            cache_key(QueryExpansion::Expand, "m", "Retry  Policy"),
+            cache_key(QueryExpansion::Expand, "m", "retry policy")
+        );
+        assert_ne!(
+            cache_key(QueryExpansion::Expand, "m", "retry policy"),
+            cache_key(QueryExpansion::Hyde, "m", "retry policy")
+        );
+    }
+}
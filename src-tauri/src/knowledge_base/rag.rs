@@ -0,0 +1,190 @@
+//! Retrieval for knowledge-base grounded chat: pick the best matching chunks of the
+//! current project for a user turn, fit them into a token budget, and render them as a
+//! context block the model can cite as `file:line`.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::manager::{HybridSearchResult, KbManager};
+
+/// Documents fetched per user turn before the budget is applied.
+const RETRIEVAL_LIMIT: usize = 8;
+
+/// Rough size of a token for budgeting; exact counts depend on the provider's tokenizer.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Excerpts shorter than this are not worth truncating into the remaining budget.
+const MIN_EXCERPT_CHARS: usize = 200;
+
+/// A retrieved passage shown to the model and persisted with the chat message.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChatSource {
+    pub document_id: String,
+    pub title: String,
+    /// Source file on disk, for documents indexed from files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// 1-based line where the excerpt starts in `path`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// The `file:line` label the model is asked to cite.
+    pub citation: String,
+    pub excerpt: String,
+}
+
+/// Tokens available for retrieved context: half of what the context window leaves after
+/// the reply, so the conversation history keeps the other half.
+pub fn context_budget(ctx_size: u32, max_tokens: i32) -> usize {
+    let reply = max_tokens.max(0) as usize;
+    (ctx_size as usize).saturating_sub(reply).max(1024) / 2
+}
+
+/// Retrieve passages for `query` from `group_id` that fit into `token_budget`.
+pub async fn retrieve_sources(
+    kb: &KbManager,
+    query: &str,
+    group_id: &str,
+    token_budget: usize,
+) -> Result<Vec<ChatSource>, String> {
+    let results = kb
+        .search_hybrid_in_groups(query.to_string(), RETRIEVAL_LIMIT, Some(vec![group_id.to_string()]))
+        .await?;
+    Ok(select_sources(results, group_id, token_budget))
+}
+
+fn select_sources(results: Vec<HybridSearchResult>, workspace_root: &str, token_budget: usize) -> Vec<ChatSource> {
+    let mut remaining = token_budget * CHARS_PER_TOKEN;
+    let mut sources: Vec<ChatSource> = Vec::new();
+
+    'results: for result in results {
+        let path = source_path(&result.document_id).map(str::to_string);
+        let file_content = path.as_deref().and_then(|p| std::fs::read_to_string(p).ok());
+        let excerpts = if result.matched_chunks.is_empty() {
+            vec![result.content.clone()]
+        } else {
+            result.matched_chunks.clone()
+        };
+
+        for excerpt in excerpts {
+            let excerpt = excerpt.trim();
+            if excerpt.is_empty() || sources.iter().any(|s| s.excerpt == excerpt) {
+                continue;
+            }
+            let excerpt = if excerpt.len() > remaining {
+                if remaining < MIN_EXCERPT_CHARS {
+                    break 'results;
+                }
+                truncate_at_char_boundary(excerpt, remaining)
+            } else {
+                excerpt
+            };
+            remaining -= excerpt.len();
+
+            let line = file_content.as_deref().and_then(|content| locate_line(content, excerpt));
+            let citation = match &path {
+                Some(p) => citation_label(p, workspace_root, line),
+                None => result.title.clone(),
+            };
+            sources.push(ChatSource {
+                document_id: result.document_id.clone(),
+                title: result.title.clone(),
+                path: path.clone(),
+                line,
+                citation,
+                excerpt: excerpt.to_string(),
+            });
+        }
+    }
+    sources
+}
+
+/// File path behind a `file:` document id, without the `#section:` suffix.
+fn source_path(document_id: &str) -> Option<&str> {
+    let path = document_id.strip_prefix("file:")?;
+    Some(path.split_once("#section:").map_or(path, |(file, _)| file))
+}
+
+/// Line of `content` on which the excerpt's first non-blank line appears.
+fn locate_line(content: &str, excerpt: &str) -> Option<usize> {
+    let first = excerpt.lines().map(str::trim).find(|l| !l.is_empty())?;
+    content
+        .lines()
+        .position(|line| line.contains(first))
+        .map(|index| index + 1)
+}
+
+fn citation_label(path: &str, workspace_root: &str, line: Option<usize>) -> String {
+    let relative = Path::new(path)
+        .strip_prefix(workspace_root)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string());
+    match line {
+        Some(line) => format!("{relative}:{line}"),
+        None => relative,
+    }
+}
+
+fn truncate_at_char_boundary(text: &str, max_len: usize) -> &str {
+    let mut end = max_len.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// The user turn as sent to the model: retrieved excerpts, citation instructions, then
+/// the question itself. Only the outgoing copy is rewritten; history keeps the original.
+pub fn build_grounded_prompt(question: &str, sources: &[ChatSource]) -> String {
+    let mut prompt = String::from(
+        "Answer using the knowledge base excerpts below. Cite every claim with the \
+         excerpt's label in square brackets, for example [docs/setup.md:12]. If the \
+         excerpts do not contain the answer, say so instead of guessing.\n\n",
+    );
+    for source in sources {
+        prompt.push_str(&format!("[{}] {}\n{}\n\n", source.citation, source.title, source.excerpt));
+    }
+    prompt.push_str("Question: ");
+    prompt.push_str(question);
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_section_suffix_from_document_ids() {
+        assert_eq!(source_path("file:/ws/docs/a.md#section:setup"), Some("/ws/docs/a.md"));
+        assert_eq!(source_path("file:/ws/img/flow.png"), Some("/ws/img/flow.png"));
+        assert_eq!(source_path("note-1"), None);
+    }
+
+    #[test]
+    fn locates_excerpt_line_and_labels_relative_to_workspace() {
+        let content = "# Setup\n\nInstall the CLI.\n\n## Retries\nCalls back off exponentially.\n";
+        let line = locate_line(content, "\n  Calls back off exponentially.\nMore text");
+        assert_eq!(line, Some(6));
+        assert_eq!(citation_label("/ws/docs/a.md", "/ws", line), "docs/a.md:6");
+        assert_eq!(citation_label("/elsewhere/a.md", "/ws", None), "/elsewhere/a.md");
+    }
+
+    #[test]
+    fn budget_limits_selected_excerpts() {
+        let result = |id: &str, chunk: String| HybridSearchResult {
+            document_id: id.to_string(),
+            title: id.to_string(),
+            content: String::new(),
+            score: 1.0,
+            matched_chunks: vec![chunk],
+            summary: None,
+            keywords: Vec::new(),
+            language: None,
+        };
+        let results = vec![result("a", "x".repeat(300)), result("b", "y".repeat(300)), result("c", "z".repeat(300))];
+        // 150 tokens = 600 chars: two full excerpts fit, the third is dropped.
+        let sources = select_sources(results, "/ws", 150);
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].citation, "a");
+    }
+}
@@ -0,0 +1,522 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use rusqlite::{types::Value, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+
+use super::manager::KbManager;
+use super::vector_index::{self, VectorQuantization};
+
+/// How often the desktop app snapshots the knowledge base.
+pub const BACKUP_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Number of backups kept; older ones are pruned after each new backup.
+const MAX_BACKUPS: usize = 5;
+
+/// SQLite shell used for `.recover`. macOS ships one; elsewhere it must be on `PATH`.
+const SQLITE_SHELL: &str = "sqlite3";
+
+/// Plain tables copied out of a corrupt database, in foreign-key order.
+/// Chunks and embeddings are skipped because they are rebuilt by re-embedding, and
+/// `kb_meta` because it also holds leases; its quantization mode is carried over by
+/// `salvage_quantization`.
+const SALVAGE_TABLES: &[&str] = &[
+    "groups",
+    "documents",
+    "document_groups",
+    "edges",
+    "document_tags",
+    "document_revisions",
+    "document_changes",
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SalvagedTable {
+    pub table: String,
+    pub rows: usize,
+    /// False when reading stopped early at a corrupt page.
+    pub complete: bool,
+    pub error: Option<String>,
+}
+
+/// Written next to the database after a corrupt file was replaced, and shown by the UI
+/// until dismissed.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecoveryReport {
+    #[serde(rename = "recoveredAt")]
+    pub recovered_at: String,
+    pub reason: String,
+    #[serde(rename = "quarantinedPath")]
+    pub quarantined_path: String,
+    pub tables: Vec<SalvagedTable>,
+    /// How the rows were read from the corrupt file.
+    #[serde(default)]
+    pub method: SalvageMethod,
+    /// Salvaged documents have no chunks or embeddings until they are re-embedded.
+    #[serde(rename = "reembedPending")]
+    pub reembed_pending: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SalvageMethod {
+    /// The SQLite shell's `.recover`, which reads page by page and skips damaged pages.
+    Recover,
+    /// Plain `SELECT`s, used when the shell is unavailable; a table stops at its first
+    /// unreadable page.
+    #[default]
+    Copy,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BackupRecord {
+    pub name: String,
+    pub path: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    pub size: u64,
+}
+
+fn file_timestamp() -> String {
+    chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ").to_string()
+}
+
+/// Path of a SQLite side file such as `knowledge_base.db-wal`.
+fn side_file(db_path: &Path, suffix: &str) -> PathBuf {
+    PathBuf::from(format!("{}{suffix}", db_path.display()))
+}
+
+fn report_path(db_path: &Path) -> PathBuf {
+    side_file(db_path, ".recovery.json")
+}
+
+fn pending_restore_path(db_path: &Path) -> PathBuf {
+    side_file(db_path, ".restore")
+}
+
+pub fn backups_dir(db_path: &Path) -> PathBuf {
+    db_path
+        .parent()
+        .map(|dir| dir.join("backups"))
+        .unwrap_or_else(|| PathBuf::from("backups"))
+}
+
+/// Move the database and its WAL/SHM files aside as
+/// `knowledge_base.{label}-{timestamp}.db` instead of deleting them.
+pub fn quarantine(db_path: &Path, label: &str) -> Result<PathBuf, String> {
+    let stem = db_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("knowledge_base");
+    let target = db_path.with_file_name(format!("{stem}.{label}-{}.db", file_timestamp()));
+
+    std::fs::rename(db_path, &target)
+        .map_err(|e| format!("Failed to quarantine {}: {e}", db_path.display()))?;
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let from = side_file(db_path, suffix);
+        if from.exists() {
+            std::fs::rename(&from, side_file(&target, suffix))
+                .map_err(|e| format!("Failed to quarantine {}: {e}", from.display()))?;
+        }
+    }
+
+    Ok(target)
+}
+
+/// Rebuild `corrupt` next to itself with the SQLite shell's `.recover`, which walks
+/// every page it can read, skips damaged ones and writes the rows out as SQL. Errors
+/// replaying individual statements are expected and do not fail the recovery.
+fn recover_with_sqlite_shell(corrupt: &Path) -> Result<PathBuf, String> {
+    let recovered = side_file(corrupt, ".recovered");
+    let _ = std::fs::remove_file(&recovered);
+
+    let mut dump = Command::new(SQLITE_SHELL)
+        .arg(corrupt)
+        .arg(".recover")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run {SQLITE_SHELL}: {e}"))?;
+    let sql = dump
+        .stdout
+        .take()
+        .ok_or_else(|| format!("{SQLITE_SHELL} produced no output"))?;
+    Command::new(SQLITE_SHELL)
+        .arg(&recovered)
+        .stdin(Stdio::from(sql))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| format!("Failed to run {SQLITE_SHELL}: {e}"))?;
+
+    let status = dump.wait().map_err(|e| e.to_string())?;
+    if !status.success() || !recovered.exists() {
+        let _ = std::fs::remove_file(&recovered);
+        return Err(format!(".recover failed ({status})"));
+    }
+    Ok(recovered)
+}
+
+/// Copy every readable row of `SALVAGE_TABLES`, and the vector quantization mode, from
+/// `source` into the freshly created database. Rows are read in storage order; a table stops at the first unreadable page
+/// and keeps the rows read so far.
+pub fn salvage(source: &Path, target: &mut Connection) -> Vec<SalvagedTable> {
+    let source = match Connection::open_with_flags(source, OpenFlags::SQLITE_OPEN_READ_ONLY) {
+        Ok(conn) => conn,
+        Err(e) => {
+            return SALVAGE_TABLES
+                .iter()
+                .map(|table| SalvagedTable {
+                    table: table.to_string(),
+                    rows: 0,
+                    complete: false,
+                    error: Some(format!("Failed to open quarantined database: {e}")),
+                })
+                .collect();
+        }
+    };
+
+    // Groups reference their parents and rows arrive in storage order, so foreign keys
+    // are checked by the application rather than per insert.
+    let _ = target.execute_batch("PRAGMA foreign_keys = OFF;");
+    let mut tables: Vec<SalvagedTable> = SALVAGE_TABLES
+        .iter()
+        .map(|table| salvage_table(&source, target, table))
+        .collect();
+    tables.push(salvage_quantization(&source, target));
+    let _ = target.execute_batch(
+        "DELETE FROM document_groups WHERE document_id NOT IN (SELECT id FROM documents)
+            OR group_id NOT IN (SELECT id FROM groups);
+         DELETE FROM edges WHERE source_id NOT IN (SELECT id FROM documents)
+            OR target_id NOT IN (SELECT id FROM documents);
+         DELETE FROM document_tags WHERE document_id NOT IN (SELECT id FROM documents);
+         UPDATE groups SET parent_id = NULL WHERE parent_id NOT IN (SELECT id FROM groups);
+         PRAGMA foreign_keys = ON;",
+    );
+
+    tables
+}
+
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!("PRAGMA table_info({table})"))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn copy_table(
+    source: &Connection,
+    target: &mut Connection,
+    table: &str,
+    result: &mut SalvagedTable,
+) -> Result<(), String> {
+    // Only columns both schemas share, so older databases salvage too.
+    let target_columns = table_columns(target, table)?;
+    let columns: Vec<String> = table_columns(source, table)?
+        .into_iter()
+        .filter(|c| target_columns.contains(c))
+        .collect();
+    if columns.is_empty() {
+        return Err("Table is missing or unreadable".to_string());
+    }
+
+    let column_list = columns.join(", ");
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let mut copied = 0;
+    let tx = target.transaction().map_err(|e| e.to_string())?;
+    {
+        let mut insert = tx
+            .prepare(&format!(
+                "INSERT OR IGNORE INTO {table} ({column_list}) VALUES ({placeholders})"
+            ))
+            .map_err(|e| e.to_string())?;
+        let mut select = source
+            .prepare(&format!("SELECT {column_list} FROM {table}"))
+            .map_err(|e| e.to_string())?;
+        let mut rows = select.query([]).map_err(|e| e.to_string())?;
+
+        let outcome = loop {
+            match rows.next() {
+                Ok(Some(row)) => {
+                    let values = (0..columns.len())
+                        .map(|i| row.get::<_, Value>(i))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|e| e.to_string())?;
+                    insert
+                        .execute(rusqlite::params_from_iter(values))
+                        .map_err(|e| e.to_string())?;
+                    copied += 1;
+                }
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e.to_string()),
+            }
+        };
+        if let Err(e) = outcome {
+            result.error = Some(e);
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    result.rows = copied;
+    result.complete = result.error.is_none();
+    Ok(())
+}
+
+fn salvage_table(source: &Connection, target: &mut Connection, table: &str) -> SalvagedTable {
+    let mut result = SalvagedTable {
+        table: table.to_string(),
+        rows: 0,
+        complete: false,
+        error: None,
+    };
+
+    if let Err(e) = copy_table(source, target, table, &mut result) {
+        result.error = Some(e);
+        result.complete = false;
+    }
+    result
+}
+
+/// Carry the `kb_meta` vector quantization mode over, recreating the still empty vec0
+/// table in that mode so re-embedding fills a quantized index again. Returns whether a
+/// non-float mode was restored.
+fn copy_quantization(source: &Connection, target: &Connection) -> Result<bool, String> {
+    // Databases from before `kb_meta` existed were always float.
+    if table_columns(source, "kb_meta")?.is_empty() {
+        return Ok(false);
+    }
+    let quantization = vector_index::read_quantization(source)?;
+    if quantization == VectorQuantization::Float {
+        return Ok(false);
+    }
+    let dims = vector_index::table_dimensions(target)?.ok_or("Embeddings table is missing")?;
+    vector_index::requantize(target, dims, quantization)?;
+    Ok(true)
+}
+
+/// Reported as the `kb_meta` table, with one row when a quantized mode was restored,
+/// so the UI can tell when the index fell back to float.
+fn salvage_quantization(source: &Connection, target: &Connection) -> SalvagedTable {
+    let mut result = SalvagedTable {
+        table: "kb_meta".to_string(),
+        rows: 0,
+        complete: false,
+        error: None,
+    };
+
+    match copy_quantization(source, target) {
+        Ok(restored) => {
+            result.rows = usize::from(restored);
+            result.complete = true;
+        }
+        Err(e) => result.error = Some(format!("Vector quantization was reset to float: {e}")),
+    }
+    result
+}
+
+/// Quarantine a corrupt database, create a fresh one with `reopen`, salvage what
+/// can be read into it and write a recovery report for the UI.
+pub fn recover_corrupt_database<F>(db_path: &Path, reason: &str, reopen: F) -> Result<Connection, String>
+where
+    F: Fn() -> Result<Connection, String>,
+{
+    let quarantined = quarantine(db_path, "corrupt")?;
+    eprintln!("Database corrupted ({reason}). Moved to {} and re-creating.", quarantined.display());
+
+    let mut conn = reopen()?;
+    // `.recover` gets past damaged pages; the rows it finds are then copied from its
+    // healthy rebuild exactly like rows from a readable file.
+    let (tables, method) = match recover_with_sqlite_shell(&quarantined) {
+        Ok(recovered) => {
+            let tables = salvage(&recovered, &mut conn);
+            let _ = std::fs::remove_file(&recovered);
+            (tables, SalvageMethod::Recover)
+        }
+        Err(e) => {
+            eprintln!("{e}; copying the readable rows instead.");
+            (salvage(&quarantined, &mut conn), SalvageMethod::Copy)
+        }
+    };
+    let salvaged_documents = tables
+        .iter()
+        .any(|t| t.table == "documents" && t.rows > 0);
+
+    let report = RecoveryReport {
+        recovered_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        reason: reason.to_string(),
+        quarantined_path: quarantined.to_string_lossy().to_string(),
+        tables,
+        method,
+        reembed_pending: salvaged_documents,
+    };
+    if let Err(e) = write_report(db_path, &report) {
+        eprintln!("{e}");
+    }
+
+    Ok(conn)
+}
+
+pub fn write_report(db_path: &Path, report: &RecoveryReport) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    std::fs::write(report_path(db_path), json)
+        .map_err(|e| format!("Failed to write recovery report: {e}"))
+}
+
+/// The last recovery report, if one has not been dismissed yet.
+pub fn read_report(db_path: &Path) -> Result<Option<RecoveryReport>, String> {
+    let path = report_path(db_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read recovery report: {e}"))?;
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| format!("Invalid recovery report: {e}"))
+}
+
+pub fn dismiss_report(db_path: &Path) -> Result<(), String> {
+    let path = report_path(db_path);
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to remove recovery report: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Re-embed salvaged documents if the last recovery left them without chunks.
+pub async fn finish_recovery(kb: &KbManager, db_path: &Path) -> Result<(), String> {
+    let Some(mut report) = read_report(db_path)? else {
+        return Ok(());
+    };
+    if !report.reembed_pending {
+        return Ok(());
+    }
+    kb.reembed_all_documents().await?;
+    report.reembed_pending = false;
+    write_report(db_path, &report)
+}
+
+/// Snapshot the database into `backups/` with `VACUUM INTO`, which only needs a read
+/// connection and produces a compact, consistent copy. Old backups are pruned.
+pub async fn create_backup(kb: &KbManager, db_path: &Path) -> Result<BackupRecord, String> {
+    let dir = backups_dir(db_path);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup directory: {e}"))?;
+    let stem = db_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("knowledge_base");
+    let path = dir.join(format!("{stem}-{}.db", file_timestamp()));
+
+    {
+        let conn = kb.read_connection().await;
+        conn.execute("VACUUM INTO ?1", [path.to_string_lossy()])
+            .map_err(|e| format!("Backup failed: {e}"))?;
+    }
+
+    let backups = list_backups(db_path)?;
+    for old in backups.iter().skip(MAX_BACKUPS) {
+        let _ = std::fs::remove_file(&old.path);
+    }
+
+    backups
+        .into_iter()
+        .find(|b| Path::new(&b.path) == path)
+        .ok_or_else(|| "Backup was not written".to_string())
+}
+
+/// Backups for `db_path`, newest first.
+pub fn list_backups(db_path: &Path) -> Result<Vec<BackupRecord>, String> {
+    let dir = backups_dir(db_path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(&dir).map_err(|e| format!("Failed to read backups: {e}"))? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "db") {
+            continue;
+        }
+        let metadata = entry.metadata().map_err(|e| e.to_string())?;
+        let created_at: chrono::DateTime<chrono::Utc> = metadata
+            .modified()
+            .map_err(|e| e.to_string())?
+            .into();
+        backups.push(BackupRecord {
+            name: entry.file_name().to_string_lossy().to_string(),
+            path: path.to_string_lossy().to_string(),
+            created_at: created_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            size: metadata.len(),
+        });
+    }
+
+    // Names embed a sortable UTC timestamp.
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(backups)
+}
+
+/// Mark `backup_name` to replace the database on the next start, when no
+/// connections are open. The current database is quarantined, not deleted.
+pub fn schedule_restore(db_path: &Path, backup_name: &str) -> Result<(), String> {
+    let exists = list_backups(db_path)?.iter().any(|b| b.name == backup_name);
+    if !exists {
+        return Err(format!("Backup not found: {backup_name}"));
+    }
+    std::fs::write(pending_restore_path(db_path), backup_name)
+        .map_err(|e| format!("Failed to schedule restore: {e}"))
+}
+
+/// Apply a restore scheduled by `schedule_restore`. Must run before the database is opened.
+pub fn apply_pending_restore(db_path: &Path) -> Result<Option<PathBuf>, String> {
+    let marker = pending_restore_path(db_path);
+    if !marker.exists() {
+        return Ok(None);
+    }
+    let backup_name = std::fs::read_to_string(&marker)
+        .map_err(|e| format!("Failed to read pending restore: {e}"))?;
+    let backup = backups_dir(db_path).join(backup_name.trim());
+    if !backup.exists() {
+        let _ = std::fs::remove_file(&marker);
+        return Err(format!("Backup to restore is missing: {}", backup.display()));
+    }
+
+    let replaced = if db_path.exists() {
+        Some(quarantine(db_path, "replaced")?)
+    } else {
+        None
+    };
+    std::fs::copy(&backup, db_path).map_err(|e| format!("Failed to restore backup: {e}"))?;
+    std::fs::remove_file(&marker).map_err(|e| format!("Failed to clear pending restore: {e}"))?;
+
+    Ok(replaced)
+}
+
+/// Back up now if the newest backup is older than `BACKUP_INTERVAL`, then keep
+/// backing up on that interval for the lifetime of the app.
+pub fn spawn_backup_task(kb: KbManager, db_path: PathBuf) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let newest_age = list_backups(&db_path)
+                .ok()
+                .and_then(|backups| backups.into_iter().next())
+                .and_then(|b| std::fs::metadata(&b.path).ok())
+                .and_then(|m| m.modified().ok())
+                .and_then(|modified| modified.elapsed().ok());
+
+            let wait = match newest_age {
+                Some(age) if age < BACKUP_INTERVAL => BACKUP_INTERVAL - age,
+                _ => {
+                    if let Err(e) = create_backup(&kb, &db_path).await {
+                        eprintln!("[knowledge_base] {e}");
+                    }
+                    BACKUP_INTERVAL
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    });
+}
@@ -0,0 +1,167 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Maximum number of revisions kept per document; older ones are pruned on write.
+pub const MAX_REVISIONS_PER_DOCUMENT: usize = 50;
+
+/// Upper bound on the LCS table size before `diff_lines` falls back to a
+/// whole-block replace, so huge rewrites don't allocate unbounded memory.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RevisionRecord {
+    pub id: i64,
+    #[serde(rename = "documentId")]
+    pub document_id: String,
+    pub title: String,
+    #[serde(rename = "contentHash")]
+    pub content_hash: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    pub size: i64,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffLineKind {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub text: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RevisionDiff {
+    pub from: RevisionRecord,
+    pub to: RevisionRecord,
+    pub added: usize,
+    pub removed: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// SHA-256 of the document content, hex encoded.
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// Line-based diff between two texts using a longest-common-subsequence table
+/// over the region left after trimming the common prefix and suffix.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let prefix = old_lines
+        .iter()
+        .zip(new_lines.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old_lines[prefix..old_lines.len() - suffix];
+    let new_mid = &new_lines[prefix..new_lines.len() - suffix];
+
+    let line = |kind: DiffLineKind, text: &str| DiffLine {
+        kind,
+        text: text.to_string(),
+    };
+
+    let mut result: Vec<DiffLine> = old_lines[..prefix]
+        .iter()
+        .map(|l| line(DiffLineKind::Unchanged, l))
+        .collect();
+
+    if (old_mid.len() + 1) * (new_mid.len() + 1) > MAX_DIFF_CELLS {
+        result.extend(old_mid.iter().map(|l| line(DiffLineKind::Removed, l)));
+        result.extend(new_mid.iter().map(|l| line(DiffLineKind::Added, l)));
+    } else {
+        // lcs[i][j] = LCS length of old_mid[i..] and new_mid[j..]
+        let (n, m) = (old_mid.len(), new_mid.len());
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old_mid[i] == new_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if old_mid[i] == new_mid[j] {
+                result.push(line(DiffLineKind::Unchanged, old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                result.push(line(DiffLineKind::Removed, old_mid[i]));
+                i += 1;
+            } else {
+                result.push(line(DiffLineKind::Added, new_mid[j]));
+                j += 1;
+            }
+        }
+        result.extend(old_mid[i..].iter().map(|l| line(DiffLineKind::Removed, l)));
+        result.extend(new_mid[j..].iter().map(|l| line(DiffLineKind::Added, l)));
+    }
+
+    result.extend(
+        old_lines[old_lines.len() - suffix..]
+            .iter()
+            .map(|l| line(DiffLineKind::Unchanged, l)),
+    );
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(lines: &[DiffLine]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|l| {
+                let sign = match l.kind {
+                    DiffLineKind::Unchanged => ' ',
+                    DiffLineKind::Added => '+',
+                    DiffLineKind::Removed => '-',
+                };
+                format!("{sign}{}", l.text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical_text_has_no_changes() {
+        let diff = diff_lines("a\nb\nc", "a\nb\nc");
+        assert!(diff.iter().all(|l| l.kind == DiffLineKind::Unchanged));
+        assert_eq!(diff.len(), 3);
+    }
+
+    #[test]
+    fn reports_replaced_and_inserted_lines() {
+        let diff = diff_lines("# Auth\nuse sessions\nexpiry 1h", "# Auth\nuse JWT\nexpiry 1h\nrefresh 7d");
+        assert_eq!(
+            render(&diff),
+            vec![" # Auth", "-use sessions", "+use JWT", " expiry 1h", "+refresh 7d"]
+        );
+    }
+
+    #[test]
+    fn content_hash_is_stable() {
+        assert_eq!(content_hash("abc"), content_hash("abc"));
+        assert_ne!(content_hash("abc"), content_hash("abd"));
+        assert_eq!(content_hash("").len(), 64);
+    }
+}
@@ -0,0 +1,223 @@
+//! Optional LLM-written summary and keyword list per document, so agents can triage
+//! search results and graph nodes without reading whole documents. Each summary stores
+//! the hash of the content it was written for; a summary whose hash no longer matches
+//! the document is stale, is never returned, and is regenerated by the next pass.
+//! A failed attempt is stored as an empty summary for the same hash, so the document
+//! is not prompted again until it changes or `FAILED_SUMMARY_RETRY` has passed.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use super::revisions::content_hash;
+
+/// Documents shorter than this are their own summary.
+const MIN_SUMMARY_CHARS: usize = 200;
+
+/// Content beyond this is cut before prompting; the opening of a doc carries its gist.
+const MAX_PROMPT_CHARS: usize = 12_000;
+
+const MAX_KEYWORDS: usize = 8;
+
+/// How long a document whose summary failed waits before it is prompted again.
+const FAILED_SUMMARY_RETRY: chrono::Duration = chrono::Duration::hours(24);
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DocumentSummary {
+    pub summary: String,
+    pub keywords: Vec<String>,
+}
+
+/// A document whose summary is missing or stale.
+pub struct PendingDocument {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+}
+
+pub fn build_prompt(title: &str, content: &str) -> String {
+    let mut end = content.len().min(MAX_PROMPT_CHARS);
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!(
+        "Summarize the document below for someone deciding whether to read it. Reply in \
+         exactly this format and nothing else:\n\
+         Summary: <one to three sentences>\n\
+         Keywords: <up to {MAX_KEYWORDS} comma-separated key terms>\n\n\
+         Title: {title}\n\n{}",
+        &content[..end]
+    )
+}
+
+/// Read the `Summary:` and `Keywords:` lines, ignoring reasoning blocks and stray text.
+/// A summary may continue on the lines after its label. A reply with neither label is
+/// taken as a bare summary, since smaller models often drop the format.
+pub fn parse_response(response: &str) -> Option<DocumentSummary> {
+    let text = match response.rfind("</think>") {
+        Some(end) => &response[end + "</think>".len()..],
+        None => response,
+    };
+
+    let mut summary_lines: Vec<&str> = Vec::new();
+    let mut keywords: Vec<String> = Vec::new();
+    let labelled = text.lines().any(|line| {
+        let lower = line.trim().to_lowercase();
+        lower.starts_with("summary:") || lower.starts_with("keywords:")
+    });
+    let mut in_summary = false;
+    for line in text.lines().map(str::trim) {
+        let lower = line.to_lowercase();
+        if lower.starts_with("summary:") {
+            summary_lines.push(line["summary:".len()..].trim());
+            in_summary = true;
+        } else if lower.starts_with("keywords:") {
+            in_summary = false;
+            for keyword in line["keywords:".len()..].split(',') {
+                let keyword = keyword.trim().trim_matches(|c| c == '.' || c == '"' || c == '*').trim();
+                if !keyword.is_empty() && !keywords.iter().any(|k| k.eq_ignore_ascii_case(keyword)) {
+                    keywords.push(keyword.to_string());
+                }
+            }
+        } else if (in_summary || !labelled) && !line.is_empty() {
+            summary_lines.push(line);
+        }
+    }
+    keywords.truncate(MAX_KEYWORDS);
+
+    let summary = summary_lines.join(" ").trim().to_string();
+    if summary.is_empty() {
+        return None;
+    }
+    Some(DocumentSummary { summary, keywords })
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Documents without an up-to-date summary, oldest first. Failed attempts count as
+/// up to date until `FAILED_SUMMARY_RETRY` has passed.
+pub fn pending_documents(conn: &Connection, limit: usize) -> Result<Vec<PendingDocument>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT d.id, d.title, d.content, s.content_hash, s.summary = '' AND s.generated_at < ?2
+             FROM documents d
+             LEFT JOIN document_summaries s ON s.document_id = d.id
+             WHERE length(d.content) >= ?1
+             ORDER BY d.updated_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let retry_before = (chrono::Utc::now() - FAILED_SUMMARY_RETRY).to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let rows = stmt
+        .query_map(params![MIN_SUMMARY_CHARS as i64, retry_before], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<bool>>(4)?.unwrap_or(false),
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut pending = Vec::new();
+    for row in rows {
+        let (id, title, content, summarized_hash, retry_failure) = row.map_err(|e| e.to_string())?;
+        if summarized_hash.as_deref() == Some(content_hash(&content).as_str()) && !retry_failure {
+            continue;
+        }
+        pending.push(PendingDocument { id, title, content });
+        if pending.len() == limit {
+            break;
+        }
+    }
+    Ok(pending)
+}
+
+pub fn store_summary(
+    conn: &Connection,
+    document_id: &str,
+    content: &str,
+    summary: &DocumentSummary,
+    model: &str,
+) -> Result<(), String> {
+    let keywords = serde_json::to_string(&summary.keywords).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO document_summaries (document_id, content_hash, summary, keywords, model, generated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(document_id) DO UPDATE SET
+             content_hash = excluded.content_hash,
+             summary = excluded.summary,
+             keywords = excluded.keywords,
+             model = excluded.model,
+             generated_at = excluded.generated_at",
+        params![
+            document_id,
+            content_hash(content),
+            summary.summary,
+            keywords,
+            model,
+            now()
+        ],
+    )
+    .map_err(|e| format!("Failed to store summary for {document_id}: {e}"))?;
+    Ok(())
+}
+
+/// Record that summarizing this version of the document failed.
+pub fn store_failure(conn: &Connection, document_id: &str, content: &str, model: &str) -> Result<(), String> {
+    store_summary(conn, document_id, content, &DocumentSummary { summary: String::new(), keywords: Vec::new() }, model)
+}
+
+/// Up-to-date summaries for the given `(id, current content)` pairs, keyed by id.
+pub fn load_fresh<'a>(
+    conn: &Connection,
+    documents: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Result<HashMap<String, DocumentSummary>, String> {
+    let mut stmt = conn
+        .prepare_cached("SELECT content_hash, summary, keywords FROM document_summaries WHERE document_id = ?1")
+        .map_err(|e| e.to_string())?;
+
+    let mut fresh = HashMap::new();
+    for (id, content) in documents {
+        let row = stmt.query_row(params![id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        });
+        let (hash, summary, keywords) = match row {
+            Ok(row) => row,
+            Err(rusqlite::Error::QueryReturnedNoRows) => continue,
+            Err(e) => return Err(format!("Failed to read summary for {id}: {e}")),
+        };
+        if hash != content_hash(content) || summary.is_empty() {
+            continue;
+        }
+        let keywords = serde_json::from_str(&keywords).unwrap_or_default();
+        fresh.insert(id.to_string(), DocumentSummary { summary, keywords });
+    }
+    Ok(fresh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_summary_and_keywords() {
+        let response = "<think>short doc about deploys</think>\nHere you go:\nSummary: Explains the release pipeline.\n\
+                        It runs on every merge.\nKeywords: release, CI, \"pipeline\", ci, rollback.";
+        assert_eq!(
+            parse_response(response),
+            Some(DocumentSummary {
+                summary: "Explains the release pipeline. It runs on every merge.".to_string(),
+                keywords: vec!["release".to_string(), "CI".to_string(), "pipeline".to_string(), "rollback".to_string()],
+            })
+        );
+        assert_eq!(parse_response("Keywords: a, b"), None);
+        assert_eq!(
+            parse_response("Describes the release\npipeline.").map(|s| s.summary),
+            Some("Describes the release pipeline.".to_string())
+        );
+    }
+}
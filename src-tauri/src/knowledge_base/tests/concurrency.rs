@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::knowledge_base::{db, embedding::Embedder, manager::KbManager};
+
+struct HashEmbedder;
+
+#[async_trait::async_trait]
+impl Embedder for HashEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        let seed = text.bytes().fold(0u32, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u32));
+        Ok((0..8).map(|i| ((seed as f32 + i as f32) * 0.37).sin()).collect())
+    }
+
+    fn dimensions(&self) -> usize {
+        8
+    }
+
+    fn name(&self) -> &'static str {
+        "mock/hash"
+    }
+}
+
+/// Removes the temporary database and its WAL side files when the test ends.
+struct TempDb(PathBuf);
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", self.0.display()));
+        }
+    }
+}
+
+fn build_pooled_manager() -> Result<(KbManager, TempDb), String> {
+    let path = std::env::temp_dir().join(format!("depdok-kb-{}.db", uuid::Uuid::new_v4()));
+    let conn = db::init_database_at_path(&path, 8)?;
+    let readers = db::open_read_connections(&path, db::READ_POOL_SIZE)?;
+    Ok((KbManager::new(conn, Box::new(HashEmbedder)).with_readers(readers), TempDb(path)))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn searches_do_not_wait_for_open_write_transaction() -> Result<(), String> {
+    let (kb, _db) = build_pooled_manager()?;
+    kb.upsert_document(Some("doc-1".to_string()), "Auth".to_string(), "token refresh flow".to_string(), vec![])
+        .await?;
+
+    // Hold the writer with an uncommitted transaction, as a long reindex would.
+    let writer = kb.db_lock();
+    let mut writer = writer.lock().await;
+    let tx = writer.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM documents", []).map_err(|e| e.to_string())?;
+
+    let results = tokio::time::timeout(
+        Duration::from_secs(5),
+        kb.search_hybrid("token".to_string(), 5),
+    )
+    .await
+    .map_err(|_| "search blocked behind the writer".to_string())??;
+
+    // Readers see the last committed snapshot, not the pending delete.
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].document_id, "doc-1");
+
+    drop(tx);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn searches_stay_responsive_during_large_reindex() -> Result<(), String> {
+    let (kb, _db) = build_pooled_manager()?;
+
+    for i in 0..300 {
+        let content = format!("Document {i} about topic{}.\n\n{}", i % 7, "filler text ".repeat(200));
+        kb.upsert_document(Some(format!("doc-{i}")), format!("Doc {i}"), content, vec!["bench".to_string()])
+            .await?;
+    }
+
+    let reindexing = Arc::new(AtomicBool::new(true));
+    let reindex = {
+        let kb = kb.clone();
+        let reindexing = reindexing.clone();
+        tokio::spawn(async move {
+            let result = kb.reembed_all_documents().await;
+            reindexing.store(false, Ordering::SeqCst);
+            result
+        })
+    };
+
+    let mut searches_during_reindex = 0;
+    let mut slowest = Duration::ZERO;
+    while reindexing.load(Ordering::SeqCst) {
+        let started = Instant::now();
+        let results = kb
+            .search_hybrid_in_groups("topic3".to_string(), 5, Some(vec!["bench".to_string()]))
+            .await?;
+        slowest = slowest.max(started.elapsed());
+        assert!(!results.is_empty());
+        searches_during_reindex += 1;
+    }
+
+    assert_eq!(reindex.await.map_err(|e| e.to_string())??, 300);
+    assert!(searches_during_reindex > 0, "reindex finished before any search ran");
+    assert!(slowest < Duration::from_secs(2), "slowest search took {slowest:?}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn project_graph_is_served_from_read_only_pool() -> Result<(), String> {
+    let (kb, _db) = build_pooled_manager()?;
+    kb.upsert_document(Some("doc-1".to_string()), "Auth".to_string(), "token refresh flow".to_string(), vec!["project-a".to_string()])
+        .await?;
+
+    let graph = kb.get_project_graph("project-a".to_string()).await?;
+    assert_eq!(graph.documents.len(), 1);
+
+    let unknown = kb.get_project_graph("/ws/unknown".to_string()).await?;
+    assert_eq!(unknown.group_title, "unknown");
+    assert!(unknown.documents.is_empty());
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use super::{build_test_manager, TempDir};
+use crate::knowledge_base::{
+    image_extractor::ImageTextExtractor,
+    parser::{extract_image_refs, ImageRef},
+};
+
+/// Returns fixed text per file name and counts how often it actually ran.
+struct MockExtractor {
+    calls: AtomicUsize,
+}
+
+impl ImageTextExtractor for MockExtractor {
+    fn extract(&self, path: &Path) -> Result<String, String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        match path.file_name().and_then(|n| n.to_str()) {
+            Some("checkout.png") => Ok("Payment gateway sequence: client -> gateway -> ledger".to_string()),
+            Some("broken.png") => Err("not an image".to_string()),
+            _ => Ok(String::new()),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "mock/ocr"
+    }
+}
+
+#[test]
+fn extracts_local_image_references() {
+    let refs = extract_image_refs(
+        "![Checkout *flow*](diagrams/check%20out.png)\n![](https://example.com/a.png)\n\
+         ![inline](data:image/png;base64,AAAA)\n![again](diagrams/check%20out.png)",
+    );
+    assert_eq!(
+        refs,
+        vec![ImageRef { path: "diagrams/check out.png".to_string(), alt: "Checkout flow".to_string() }]
+    );
+}
+
+#[tokio::test]
+async fn referenced_images_are_indexed_as_linked_documents() -> Result<(), String> {
+    let dir = TempDir::new("images")?;
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("img")).map_err(|e| e.to_string())?;
+    for name in ["checkout.png", "broken.png"] {
+        std::fs::write(root.join("img").join(name), name.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let workspace = root.to_string_lossy().to_string();
+    let md_path = root.join("payments.md").to_string_lossy().to_string();
+    let image_doc_id = format!("file:{}", root.join("img").join("checkout.png").to_string_lossy());
+
+    let kb = build_test_manager()?;
+    let extractor = Arc::new(MockExtractor { calls: AtomicUsize::new(0) });
+    kb.set_image_extractor(Some(extractor.clone()));
+
+    let content = "# Checkout\n\n![Checkout diagram](img/checkout.png)\n\n![](img/broken.png)\n\n\
+                   ![Again](./img/../img/checkout.png)\n";
+    kb.index_markdown_document_sections(md_path.clone(), "payments.md".to_string(), content.to_string(), vec![workspace.clone()])
+        .await?;
+
+    // The OCR text is searchable and resolves to the image document.
+    let results = kb.search_hybrid("gateway".to_string(), 5).await?;
+    let hit = results.iter().find(|r| r.document_id == image_doc_id).ok_or("image text not found")?;
+    assert_eq!(hit.title, "Checkout diagram (checkout.png)");
+
+    let section_id = format!("file:{md_path}#section:checkout");
+    let graph = kb.get_project_graph(workspace.clone()).await?;
+    let image_edges = graph
+        .edges
+        .iter()
+        .filter(|e| e.source_id == section_id && e.target_id == image_doc_id && e.edge_type.as_deref() == Some("image"))
+        .count();
+    assert_eq!(image_edges, 1, "every spelling of one image links once");
+
+    // Unchanged images are served from the cache on re-index.
+    let calls = extractor.calls.load(Ordering::SeqCst);
+    kb.index_markdown_document_sections(md_path.clone(), "payments.md".to_string(), content.to_string(), vec![workspace.clone()])
+        .await?;
+    assert_eq!(extractor.calls.load(Ordering::SeqCst), calls + 1, "only the failing image is retried");
+
+    // Removing the reference removes the image document.
+    kb.index_markdown_document_sections(md_path.clone(), "payments.md".to_string(), "# Checkout\n\nNo diagram.".to_string(), vec![workspace.clone()])
+        .await?;
+    assert!(kb.get_document(image_doc_id.clone()).await?.is_none());
+
+    // Deleting the markdown file deletes its images with it.
+    kb.index_markdown_document_sections(md_path.clone(), "payments.md".to_string(), content.to_string(), vec![workspace.clone()])
+        .await?;
+    assert!(kb.get_document(image_doc_id.clone()).await?.is_some());
+    kb.delete_document(format!("file:{md_path}")).await?;
+    assert!(kb.get_document(image_doc_id.clone()).await?.is_none());
+
+    // An image shared by files of two workspaces stays in both.
+    std::fs::create_dir_all(root.join("other")).map_err(|e| e.to_string())?;
+    let other_path = root.join("other").join("notes.md").to_string_lossy().to_string();
+    kb.index_markdown_document_sections(md_path, "payments.md".to_string(), content.to_string(), vec![workspace.clone()])
+        .await?;
+    kb.index_markdown_document_sections(
+        other_path,
+        "notes.md".to_string(),
+        "# Notes\n\n![Checkout diagram](../img/checkout.png)\n".to_string(),
+        vec!["other-workspace".to_string()],
+    )
+    .await?;
+    for group in [workspace, "other-workspace".to_string()] {
+        let recent = kb.list_recent_documents(Some(group.clone()), None, 20).await?;
+        assert!(recent.iter().any(|doc| doc.id == image_doc_id), "image missing from {group}");
+    }
+
+    Ok(())
+}
@@ -1,117 +1,8 @@
-use rusqlite::Connection;
-
-use crate::knowledge_base::{
-    embedding::Embedder,
-    manager::KbManager,
-};
-
-struct MockEmbedder;
-
-#[async_trait::async_trait]
-impl Embedder for MockEmbedder {
-    async fn embed(&self, _text: &str) -> Result<Vec<f32>, String> {
-        Ok(vec![0.1, 0.2, 0.3, 0.4])
-    }
-
-    fn dimensions(&self) -> usize {
-        4
-    }
-
-    fn name(&self) -> &'static str {
-        "mock/embedder"
-    }
-}
-
-async fn build_test_manager() -> Result<KbManager, String> {
-    unsafe {
-        rusqlite::ffi::sqlite3_auto_extension(Some(std::mem::transmute(
-            sqlite_vec::sqlite3_vec_init as *const (),
-        )));
-    }
-    let conn = Connection::open_in_memory().map_err(|e| e.to_string())?;
-
-    conn.execute_batch(
-        "
-        PRAGMA foreign_keys = ON;
-
-        CREATE TABLE documents (
-            id      TEXT PRIMARY KEY,
-            title   TEXT NOT NULL,
-            content TEXT NOT NULL
-        );
-
-        CREATE TABLE groups (
-            id    TEXT PRIMARY KEY,
-            title TEXT NOT NULL
-        );
-
-        CREATE TABLE document_groups (
-            document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
-            group_id    TEXT NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
-            PRIMARY KEY (document_id, group_id)
-        );
-
-        CREATE TABLE edges (
-            id        TEXT PRIMARY KEY,
-            source_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
-            target_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
-            type      TEXT
-        );
-
-        CREATE TABLE document_chunks (
-            chunk_id    TEXT PRIMARY KEY,
-            document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
-            chunk_index INTEGER NOT NULL,
-            content     TEXT NOT NULL
-        );
-
-        CREATE TABLE document_tags (
-            document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
-            tag         TEXT NOT NULL,
-            PRIMARY KEY (document_id, tag)
-        );
-
-        -- In tests, we size the embedding dimension to 4 to match MockEmbedder
-        CREATE VIRTUAL TABLE documents_embeddings USING vec0(
-            chunk_id    TEXT PRIMARY KEY,
-            document_id TEXT,
-            embedding   FLOAT[4]
-        );
-        
-        -- Create documents_fts table and triggers
-        CREATE VIRTUAL TABLE documents_fts USING fts5(
-            title,
-            content,
-            content=documents,
-            content_rowid=rowid
-        );
-
-        CREATE TRIGGER documents_fts_insert AFTER INSERT ON documents BEGIN
-            INSERT INTO documents_fts (rowid, title, content)
-            VALUES (new.rowid, new.title, new.content);
-        END;
-
-        CREATE TRIGGER documents_fts_update AFTER UPDATE ON documents BEGIN
-            INSERT INTO documents_fts (documents_fts, rowid, title, content)
-            VALUES ('delete', old.rowid, old.title, old.content);
-            INSERT INTO documents_fts (rowid, title, content)
-            VALUES (new.rowid, new.title, new.content);
-        END;
-
-        CREATE TRIGGER documents_fts_delete AFTER DELETE ON documents BEGIN
-            INSERT INTO documents_fts (documents_fts, rowid, title, content)
-            VALUES ('delete', old.rowid, old.title, old.content);
-        END;
-        ",
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(KbManager::new(conn, Box::new(MockEmbedder)))
-}
+use super::build_test_manager;
 
 #[tokio::test]
 async fn upsert_and_get_document_works() -> Result<(), String> {
-    let kb = build_test_manager().await?;
+    let kb = build_test_manager()?;
 
     let doc_id = kb.upsert_document(
         Some("doc-1".to_string()),
@@ -155,7 +46,7 @@ async fn upsert_and_get_document_works() -> Result<(), String> {
 
 #[tokio::test]
 async fn get_chunk_context_works() -> Result<(), String> {
-    let kb = build_test_manager().await?;
+    let kb = build_test_manager()?;
 
     // Create a document with long content that will split into multiple chunks.
     // Chunk size defaults to max_chars: 512, overlap: 64.
@@ -193,7 +84,7 @@ async fn get_chunk_context_works() -> Result<(), String> {
 
 #[tokio::test]
 async fn hybrid_search_rrf_works() -> Result<(), String> {
-    let kb = build_test_manager().await?;
+    let kb = build_test_manager()?;
 
     kb.upsert_document(
         Some("doc-a".to_string()),
@@ -220,7 +111,7 @@ async fn hybrid_search_rrf_works() -> Result<(), String> {
 
 #[tokio::test]
 async fn connect_and_delete_document_works() -> Result<(), String> {
-    let kb = build_test_manager().await?;
+    let kb = build_test_manager()?;
 
     kb.upsert_document(
         Some("doc-a".to_string()),
@@ -278,7 +169,7 @@ async fn connect_and_delete_document_works() -> Result<(), String> {
 
 #[tokio::test]
 async fn get_project_graph_returns_group_data() -> Result<(), String> {
-    let kb = build_test_manager().await?;
+    let kb = build_test_manager()?;
 
     kb.upsert_document(
         Some("doc-g1".to_string()),
@@ -310,3 +201,586 @@ async fn get_project_graph_returns_group_data() -> Result<(), String> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn indexing_creates_nested_folder_groups() -> Result<(), String> {
+    let kb = build_test_manager()?;
+
+    kb.index_markdown_document_sections(
+        "/ws/docs/api/auth.md".to_string(),
+        "auth.md".to_string(),
+        "Token refresh rules.".to_string(),
+        vec!["/ws".to_string()],
+    ).await?;
+
+    let groups = kb.list_groups().await?;
+    let parent_of = |id: &str| {
+        groups
+            .iter()
+            .find(|g| g.id == id)
+            .map(|g| g.parent_id.clone())
+    };
+    assert_eq!(parent_of("/ws"), Some(None));
+    assert_eq!(parent_of("/ws/docs"), Some(Some("/ws".to_string())));
+    assert_eq!(parent_of("/ws/docs/api"), Some(Some("/ws/docs".to_string())));
+
+    // The document stays attached to the workspace and is also attached to its folder.
+    let workspace_graph = kb.get_project_graph("/ws".to_string()).await?;
+    assert_eq!(workspace_graph.documents.len(), 1);
+    let folder_graph = kb.get_project_graph("/ws/docs/api".to_string()).await?;
+    assert_eq!(folder_graph.documents.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_hybrid_in_groups_spans_selected_workspaces() -> Result<(), String> {
+    let kb = build_test_manager()?;
+
+    for (id, group) in [("doc-a", "/repo-a"), ("doc-b", "/repo-b"), ("doc-c", "/repo-c")] {
+        kb.upsert_document(
+            Some(id.to_string()),
+            format!("Retry policy {id}"),
+            "Retry policy with exponential backoff.".to_string(),
+            vec![group.to_string()],
+        ).await?;
+    }
+
+    let results = kb
+        .search_hybrid_in_groups(
+            "retry".to_string(),
+            10,
+            Some(vec!["/repo-a".to_string(), "/repo-b".to_string()]),
+        )
+        .await?;
+
+    let mut ids: Vec<_> = results.iter().map(|r| r.document_id.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["doc-a", "doc-b"]);
+
+    let all = kb.search_hybrid("retry".to_string(), 10).await?;
+    assert_eq!(all.len(), 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_similar_in_groups_filters_by_workspace() -> Result<(), String> {
+    let kb = build_test_manager()?;
+
+    for (id, group) in [("doc-a", "/repo-a"), ("doc-b", "/repo-b")] {
+        kb.upsert_document(
+            Some(id.to_string()),
+            format!("Caching {id}"),
+            "Cache invalidation notes.".to_string(),
+            vec![group.to_string()],
+        ).await?;
+    }
+
+    let scoped = kb
+        .search_similar_in_groups("cache".to_string(), 5, Some(vec!["/repo-b".to_string()]))
+        .await?;
+    let ids: Vec<_> = scoped.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, vec!["doc-b"]);
+    assert_eq!(scoped[0].title, "Caching doc-b");
+
+    let all = kb.search_similar("cache".to_string(), 5).await?;
+    assert_eq!(all.len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn scoped_search_keeps_looking_past_other_workspaces() -> Result<(), String> {
+    let kb = build_test_manager()?;
+
+    // Every document embeds identically, so the first KNN candidates are all /repo-a.
+    for i in 0..40 {
+        kb.upsert_document(None, format!("A {i}"), "Cache notes.".to_string(), vec!["/repo-a".to_string()]).await?;
+    }
+    for i in 0..3 {
+        kb.upsert_document(None, format!("B {i}"), "Cache notes.".to_string(), vec!["/repo-b".to_string()]).await?;
+    }
+
+    let scope = Some(vec!["/repo-b".to_string()]);
+    let similar = kb.search_similar_in_groups("cache".to_string(), 3, scope.clone()).await?;
+    assert_eq!(similar.len(), 3);
+    let hybrid = kb.search_hybrid_in_groups("zzz".to_string(), 3, scope).await?;
+    assert_eq!(hybrid.len(), 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn merge_and_delete_groups_work() -> Result<(), String> {
+    let kb = build_test_manager()?;
+
+    kb.upsert_document(
+        Some("shared".to_string()),
+        "Shared".to_string(),
+        "Shared doc".to_string(),
+        vec!["group-a".to_string(), "group-b".to_string()],
+    ).await?;
+    kb.upsert_document(
+        Some("only-a".to_string()),
+        "Only A".to_string(),
+        "Exclusive doc".to_string(),
+        vec!["group-a".to_string()],
+    ).await?;
+
+    kb.rename_group("group-a".to_string(), "Alpha".to_string()).await?;
+    kb.merge_groups("group-a".to_string(), "group-c".to_string())
+        .await
+        .expect_err("merging into a missing group should fail");
+
+    kb.merge_groups("group-b".to_string(), "group-a".to_string()).await?;
+    let groups = kb.list_groups().await?;
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].title, "Alpha");
+    assert_eq!(groups[0].document_count, 2);
+
+    kb.upsert_document(
+        Some("shared".to_string()),
+        "Shared".to_string(),
+        "Shared doc".to_string(),
+        vec!["group-d".to_string()],
+    ).await?;
+
+    let result = kb.delete_group("group-a".to_string(), true).await?;
+    assert_eq!(result.deleted_groups, 1);
+    assert_eq!(result.deleted_documents, 1);
+    assert!(kb.get_document("only-a".to_string()).await?.is_none());
+    assert!(kb.get_document("shared".to_string()).await?.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn revisions_are_recorded_diffed_and_restored() -> Result<(), String> {
+    let kb = build_test_manager()?;
+
+    for content in ["Auth uses sessions.", "Auth uses sessions.", "Auth uses JWT."] {
+        kb.upsert_document(
+            Some("auth".to_string()),
+            "Auth design".to_string(),
+            content.to_string(),
+            vec!["project-a".to_string()],
+        ).await?;
+    }
+
+    // Identical content is not stored twice.
+    let revisions = kb.list_revisions("auth".to_string()).await?;
+    assert_eq!(revisions.len(), 2);
+    let (newest, oldest) = (&revisions[0], &revisions[1]);
+
+    let diff = kb.diff_revisions(oldest.id, newest.id).await?;
+    assert_eq!(diff.added, 1);
+    assert_eq!(diff.removed, 1);
+
+    kb.restore_revision(oldest.id).await?;
+    let doc = kb.get_document("auth".to_string()).await?.ok_or("missing document")?;
+    assert_eq!(doc.content, "Auth uses sessions.");
+    assert_eq!(kb.list_revisions("auth".to_string()).await?.len(), 3);
+
+    // Restoring keeps the document in its group.
+    let graph = kb.get_project_graph("project-a".to_string()).await?;
+    assert_eq!(graph.documents.len(), 1);
+
+    // Revisions of different documents cannot be diffed against each other.
+    kb.upsert_document(Some("other".to_string()), "Other".to_string(), "Other.".to_string(), vec![]).await?;
+    let other = kb.list_revisions("other".to_string()).await?;
+    assert!(kb.diff_revisions(oldest.id, other[0].id).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn file_documents_are_not_restored_and_derived_documents_keep_no_history() -> Result<(), String> {
+    let kb = build_test_manager()?;
+
+    kb.upsert_document(Some("file:/repo/notes.md".to_string()), "notes.md".to_string(), "Notes.".to_string(), vec![])
+        .await?;
+    let revisions = kb.list_revisions("file:/repo/notes.md".to_string()).await?;
+    assert_eq!(revisions.len(), 1);
+    assert!(kb.restore_revision(revisions[0].id).await.is_err());
+
+    kb.index_markdown_document_sections(
+        "/repo/setup.md".to_string(),
+        "setup.md".to_string(),
+        "# Setup\n\nInstall it.".to_string(),
+        vec!["/repo".to_string()],
+    )
+    .await?;
+    assert!(kb.list_revisions("file:/repo/setup.md#section:setup".to_string()).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn indexed_files_keep_one_revision_per_change() -> Result<(), String> {
+    let kb = build_test_manager()?;
+
+    for content in [
+        "# Auth\n\nAuth uses sessions.\n\n# Tokens\n\nTokens expire daily.",
+        "# Auth\n\nAuth uses JWT.\n\n# Tokens\n\nTokens expire daily.",
+    ] {
+        kb.index_markdown_document_sections(
+            "/repo/auth.md".to_string(),
+            "auth.md".to_string(),
+            content.to_string(),
+            vec!["/repo".to_string()],
+        )
+        .await?;
+    }
+
+    let revisions = kb.list_revisions("file:/repo/auth.md".to_string()).await?;
+    assert_eq!(revisions.len(), 2);
+    let diff = kb.diff_revisions(revisions[1].id, revisions[0].id).await?;
+    assert_eq!((diff.added, diff.removed), (1, 1));
+    assert!(diff.lines.iter().any(|line| line.text == "Auth uses JWT."));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn change_feed_tracks_upserts_and_deletes() -> Result<(), String> {
+    let kb = build_test_manager()?;
+
+    kb.upsert_document(Some("doc-a".to_string()), "A".to_string(), "Alpha".to_string(), vec!["g1".to_string()]).await?;
+    kb.upsert_document(Some("doc-b".to_string()), "B".to_string(), "Beta".to_string(), vec!["g2".to_string()]).await?;
+
+    let page = kb.get_changes_since(0, 10, None).await?;
+    assert_eq!(page.changes.len(), 2);
+    assert!(!page.has_more);
+    let cursor = page.next_cursor;
+
+    // Re-upserting identical content is not a change and keeps updated_at.
+    let before = kb.get_document("doc-a".to_string()).await?.ok_or("missing doc-a")?;
+    kb.upsert_document(Some("doc-a".to_string()), "A".to_string(), "Alpha".to_string(), vec!["g1".to_string()]).await?;
+    let after = kb.get_document("doc-a".to_string()).await?.ok_or("missing doc-a")?;
+    assert_eq!(before.updated_at, after.updated_at);
+    assert!(after.created_at.is_some());
+    assert!(kb.get_changes_since(cursor, 10, None).await?.changes.is_empty());
+
+    kb.upsert_document(Some("doc-a".to_string()), "A".to_string(), "Alpha v2".to_string(), vec!["g1".to_string()]).await?;
+    kb.delete_document("doc-b".to_string()).await?;
+
+    let page = kb.get_changes_since(cursor, 1, None).await?;
+    assert_eq!(page.changes.len(), 1);
+    assert!(page.has_more);
+    assert_eq!(page.changes[0].document_id, "doc-a");
+    assert_eq!(page.changes[0].change_type, "upsert");
+
+    let page = kb.get_changes_since(page.next_cursor, 10, None).await?;
+    assert_eq!(page.changes.len(), 1);
+    assert_eq!(page.changes[0].document_id, "doc-b");
+    assert_eq!(page.changes[0].change_type, "delete");
+    assert!(page.changes[0].title.is_none());
+
+    let recent = kb.list_recent_documents(Some("g1".to_string()), None, 10).await?;
+    assert_eq!(recent.len(), 1);
+    assert_eq!(recent[0].id, "doc-a");
+
+    // A deletion only shows up in the feeds of the groups the document was in.
+    let g1 = kb.get_changes_since(cursor, 10, Some("g1".to_string())).await?;
+    assert!(g1.changes.iter().all(|c| c.document_id == "doc-a"));
+    let g2 = kb.get_changes_since(cursor, 10, Some("g2".to_string())).await?;
+    let ids: Vec<_> = g2.changes.iter().map(|c| c.document_id.as_str()).collect();
+    assert_eq!(ids, vec!["doc-b"]);
+
+    // Upserting adds groups, and joining a group alone is a change.
+    let cursor = kb.get_changes_since(0, 10, None).await?.next_cursor;
+    kb.upsert_document(Some("doc-a".to_string()), "A".to_string(), "Alpha v2".to_string(), vec!["g3".to_string()]).await?;
+    assert_eq!(kb.list_recent_documents(Some("g1".to_string()), None, 10).await?.len(), 1);
+    assert_eq!(kb.list_recent_documents(Some("g3".to_string()), None, 10).await?.len(), 1);
+    let page = kb.get_changes_since(cursor, 10, None).await?;
+    assert_eq!(page.changes.len(), 1);
+    kb.upsert_document(Some("doc-a".to_string()), "A".to_string(), "Alpha v2".to_string(), vec![]).await?;
+    assert!(kb.get_changes_since(page.next_cursor, 10, None).await?.changes.is_empty());
+    assert_eq!(kb.list_recent_documents(Some("g1".to_string()), None, 10).await?.len(), 1);
+
+    // Replacing the groups moves the document.
+    assert!(kb.set_document_groups("doc-a".to_string(), vec!["g3".to_string()]).await?);
+    assert!(!kb.set_document_groups("doc-a".to_string(), vec!["g3".to_string()]).await?);
+    assert!(kb.list_recent_documents(Some("g1".to_string()), None, 10).await?.is_empty());
+    assert_eq!(kb.list_recent_documents(Some("g3".to_string()), None, 10).await?.len(), 1);
+    assert_eq!(kb.get_changes_since(page.next_cursor, 10, None).await?.changes.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn backlinks_and_graph_neighbors_follow_edges() -> Result<(), String> {
+    let kb = build_test_manager()?;
+
+    for (id, title) in [("file:/ws/a.md", "A"), ("file:/ws/a.md#section:intro", "A Intro"), ("doc-b", "B"), ("doc-c", "C"), ("doc-d", "D")] {
+        kb.upsert_document(Some(id.to_string()), title.to_string(), title.to_string(), vec!["g".to_string()]).await?;
+    }
+    {
+        let conn = kb.db_lock();
+        let conn = conn.lock().await;
+        conn.execute_batch(
+            "INSERT INTO edges (id, source_id, target_id, type) VALUES
+                ('e1', 'doc-b', 'file:/ws/a.md#section:intro', 'link'),
+                ('e2', 'file:/ws/a.md#section:intro', 'file:/ws/a.md', 'section'),
+                ('e3', 'doc-c', 'doc-b', 'related'),
+                ('e4', 'doc-d', 'doc-c', 'related');",
+        ).map_err(|e| e.to_string())?;
+    }
+
+    // Links into a section count as backlinks of the file; the file's own sections do not.
+    let backlinks = kb.get_backlinks("file:/ws/a.md".to_string()).await?;
+    assert_eq!(backlinks.len(), 1);
+    assert_eq!(backlinks[0].id, "doc-b");
+    assert_eq!(backlinks[0].edge_type.as_deref(), Some("link"));
+
+    let one_hop = kb.get_graph_neighbors("doc-b".to_string(), 1).await?;
+    let ids: Vec<_> = one_hop.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["file:/ws/a.md#section:intro", "doc-c"]);
+    assert_eq!(one_hop.edges.len(), 2);
+
+    let two_hops = kb.get_graph_neighbors("doc-b".to_string(), 2).await?;
+    let d = two_hops.documents.iter().find(|d| d.id == "doc-d").ok_or("doc-d not reached")?;
+    assert_eq!(d.hops, 2);
+    assert_eq!(two_hops.edges.len(), 4);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn chat_tools_only_read_documents_of_the_workspace() -> Result<(), String> {
+    use crate::llm::tools::kb::execute_kb_tool;
+    use serde_json::{json, Value};
+
+    let kb = build_test_manager()?;
+    for (id, group) in [("doc-a1", "/ws-a"), ("doc-a2", "/ws-a"), ("doc-b", "/ws-b")] {
+        kb.upsert_document(Some(id.to_string()), id.to_string(), format!("Notes of {id}"), vec![group.to_string()]).await?;
+    }
+    {
+        let conn = kb.db_lock();
+        let conn = conn.lock().await;
+        conn.execute_batch(
+            "INSERT INTO edges (id, source_id, target_id, type) VALUES
+                ('e1', 'doc-b', 'doc-a1', 'link'),
+                ('e2', 'doc-a2', 'doc-a1', 'link');",
+        ).map_err(|e| e.to_string())?;
+    }
+    let run = |name: &'static str, args: Value| {
+        let kb = &kb;
+        async move {
+            execute_kb_tool(name, &args, kb, "/ws-a")
+                .await
+                .and_then(|out| serde_json::from_str::<Value>(&out).map_err(|e| e.to_string()))
+        }
+    };
+
+    assert!(run("kb_get_document", json!({ "id": "doc-a1" })).await.is_ok());
+    for (name, args) in [
+        ("kb_get_document", json!({ "id": "doc-b" })),
+        ("kb_get_chunk_context", json!({ "document_id": "doc-b" })),
+        ("kb_backlinks", json!({ "id": "doc-b" })),
+        ("kb_graph_neighbors", json!({ "id": "doc-b" })),
+    ] {
+        let err = run(name, args).await.expect_err(name);
+        assert_eq!(err, "Document not found: doc-b");
+    }
+
+    // Links from other workspaces are left out.
+    let backlinks = run("kb_backlinks", json!({ "id": "doc-a1" })).await?;
+    assert_eq!(backlinks["backlinks"].as_array().map(Vec::len), Some(1));
+    assert_eq!(backlinks["backlinks"][0]["id"], "doc-a2");
+    let neighborhood = run("kb_graph_neighbors", json!({ "id": "doc-a1" })).await?;
+    assert_eq!(neighborhood["documents"].as_array().map(Vec::len), Some(1));
+    assert_eq!(neighborhood["edges"].as_array().map(Vec::len), Some(1));
+    assert_eq!(neighborhood["edges"][0]["id"], "e2");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn find_duplicates_reads_stored_chunks_of_the_group() -> Result<(), String> {
+    let kb = build_test_manager()?;
+    let shared = "Deployments run through the release pipeline on every merge to the main branch.";
+
+    kb.upsert_document(Some("doc-a".to_string()), "A".to_string(), shared.to_string(), vec!["g-dup".to_string()]).await?;
+    kb.upsert_document(Some("doc-b".to_string()), "B".to_string(), format!("  {shared}\n"), vec!["g-dup".to_string()]).await?;
+    kb.upsert_document(Some("doc-other".to_string()), "Other".to_string(), shared.to_string(), vec!["g-other".to_string()]).await?;
+
+    let report = kb.find_duplicates("g-dup".to_string(), 0.92, 0.5).await?;
+    assert_eq!(report.exact_duplicates.len(), 1);
+    let mut ids: Vec<_> = report.exact_duplicates[0].occurrences.iter().map(|o| o.document_id.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["doc-a", "doc-b"], "documents outside the group are not compared");
+
+    // MockEmbedder gives every chunk the same vector, so the pair matches completely.
+    assert_eq!(report.near_duplicates.len(), 1);
+    assert_eq!(report.near_duplicates[0].overlap_a, 100.0);
+    assert!((report.near_duplicates[0].similarity - 1.0).abs() < 1e-4);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn duplicate_scans_find_in_group_copies_behind_many_outside_ones() -> Result<(), String> {
+    let kb = build_test_manager()?;
+    let shared = "Deployments run through the release pipeline on every merge to the main branch.";
+
+    // MockEmbedder gives every chunk the same vector, so copies elsewhere tie with the in-group one.
+    for i in 0..30 {
+        kb.upsert_document(Some(format!("fork-{i}")), "Fork".to_string(), shared.to_string(), vec!["g-forks".to_string()])
+            .await?;
+    }
+    kb.upsert_document(Some("doc-a".to_string()), "A".to_string(), shared.to_string(), vec!["g-dup".to_string()]).await?;
+    kb.upsert_document(Some("doc-b".to_string()), "B".to_string(), shared.to_string(), vec!["g-dup".to_string()]).await?;
+
+    let report = kb.find_duplicates("g-dup".to_string(), 0.92, 0.5).await?;
+    assert_eq!(report.near_duplicates.len(), 1);
+    assert_eq!(report.near_duplicates[0].overlap_a, 100.0);
+    assert_eq!(report.near_duplicates[0].overlap_b, 100.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_filters_and_boosts_by_chunk_language() -> Result<(), String> {
+    use crate::knowledge_base::language::{LanguageMode, LanguagePreference};
+
+    let kb = build_test_manager()?;
+    kb.upsert_document(
+        Some("guide-en".to_string()),
+        "Deploy guide".to_string(),
+        "The deploy pipeline runs on every merge to the main branch and it is fast.".to_string(),
+        vec!["project-a".to_string()],
+    )
+    .await?;
+    kb.upsert_document(
+        Some("guide-vi".to_string()),
+        "Hướng dẫn triển khai".to_string(),
+        "Quy trình pipeline triển khai chạy mỗi khi hợp nhất vào nhánh chính và rất nhanh.".to_string(),
+        vec!["project-a".to_string()],
+    )
+    .await?;
+
+    let all = kb.search_hybrid("pipeline".to_string(), 5).await?;
+    assert_eq!(all.len(), 2);
+
+    let vietnamese = LanguagePreference::new("vi-VN", LanguageMode::Filter);
+    let filtered = kb
+        .search_hybrid_with_language("pipeline".to_string(), 5, None, vietnamese)
+        .await?;
+    let ids: Vec<_> = filtered.iter().map(|r| r.document_id.as_str()).collect();
+    assert_eq!(ids, vec!["guide-vi"]);
+    assert_eq!(filtered[0].language.as_deref(), Some("vi"));
+
+    for preferred in ["vi", "en"] {
+        let boosted = kb
+            .search_hybrid_with_language(
+                "pipeline".to_string(),
+                5,
+                None,
+                LanguagePreference::new(preferred, LanguageMode::Boost),
+            )
+            .await?;
+        assert_eq!(boosted.len(), 2);
+        assert_eq!(boosted[0].language.as_deref(), Some(preferred));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn connect_and_disconnect_documents() -> Result<(), String> {
+    let kb = build_test_manager()?;
+    for id in ["decision", "design"] {
+        kb.upsert_document(Some(id.to_string()), id.to_string(), "Notes".to_string(), vec!["project-a".to_string()])
+            .await?;
+    }
+
+    let missing = kb
+        .connect_documents("decision".to_string(), "nowhere".to_string(), None)
+        .await;
+    assert!(missing.is_err());
+
+    kb.connect_documents("decision".to_string(), "design".to_string(), Some("implements".to_string()))
+        .await?;
+    kb.connect_documents("design".to_string(), "decision".to_string(), None)
+        .await?;
+    let backlinks = kb.get_backlinks("design".to_string()).await?;
+    assert_eq!(backlinks.len(), 1);
+
+    let removed = kb
+        .disconnect_documents("decision".to_string(), "design".to_string())
+        .await?;
+    assert_eq!(removed, 2, "edges in both directions are removed");
+    assert!(kb.get_backlinks("design".to_string()).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_tags_and_document_sections() -> Result<(), String> {
+    let kb = build_test_manager()?;
+    kb.upsert_document(Some("adr-1".to_string()), "ADR 1".to_string(), "Use SQLite #adr #storage".to_string(), vec!["/ws".to_string()])
+        .await?;
+    kb.upsert_document(Some("adr-2".to_string()), "ADR 2".to_string(), "Use WAL #adr".to_string(), vec!["/other".to_string()])
+        .await?;
+    kb.index_markdown_document_sections(
+        "/ws/docs/guide.md".to_string(),
+        "guide.md".to_string(),
+        "# Install\n\nRun the installer.\n\n# Usage\n\nStart the app.".to_string(),
+        vec!["/ws".to_string()],
+    ).await?;
+
+    let all: Vec<(String, i64)> = kb.list_tags(None).await?.into_iter().map(|t| (t.tag, t.document_count)).collect();
+    assert_eq!(all, vec![("adr".to_string(), 2), ("storage".to_string(), 1)]);
+    let scoped = kb.list_tags(Some("/ws".to_string())).await?;
+    assert_eq!(scoped.iter().find(|t| t.tag == "adr").map(|t| t.document_count), Some(1));
+
+    let sections = kb.get_document_sections("file:/ws/docs/guide.md".to_string()).await?;
+    let anchors: Vec<&str> = sections.iter().map(|s| s.anchor.as_str()).collect();
+    assert_eq!(anchors, vec!["install", "usage"]);
+    assert!(kb.get_document_sections("adr-1".to_string()).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn sections_keep_file_order_after_a_heading_is_inserted() -> Result<(), String> {
+    let kb = build_test_manager()?;
+    let index = |content: &str| {
+        kb.index_markdown_document_sections(
+            "/ws/docs/guide.md".to_string(),
+            "guide.md".to_string(),
+            content.to_string(),
+            vec!["/ws".to_string()],
+        )
+    };
+    index("# Install\n\nRun the installer.\n\n# Usage\n\nStart the app.").await?;
+    index("# Install\n\nRun the installer.\n\n# Configure\n\nSet the port.\n\n# Usage\n\nStart the app.").await?;
+
+    let sections = kb.get_document_sections("file:/ws/docs/guide.md".to_string()).await?;
+    let anchors: Vec<&str> = sections.iter().map(|s| s.anchor.as_str()).collect();
+    assert_eq!(anchors, vec!["install", "configure", "usage"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn embeddings_table_is_resized_only_while_empty() -> Result<(), String> {
+    let kb = build_test_manager()?;
+
+    kb.fit_embeddings_table(8).await?;
+    {
+        let db = kb.db_lock();
+        let conn = db.lock().await;
+        assert_eq!(crate::knowledge_base::vector_index::table_dimensions(&conn)?, Some(8));
+    }
+
+    // Back to the mock embedder's size, then store vectors of that size.
+    kb.fit_embeddings_table(4).await?;
+    kb.upsert_document(Some("doc".to_string()), "Doc".to_string(), "Body".to_string(), vec![]).await?;
+    kb.fit_embeddings_table(4).await?;
+    assert!(kb.fit_embeddings_table(8).await.is_err());
+
+    Ok(())
+}
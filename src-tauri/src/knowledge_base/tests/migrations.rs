@@ -0,0 +1,49 @@
+use rusqlite::Connection;
+
+use super::TempDir;
+use crate::knowledge_base::db;
+
+#[test]
+fn failed_migration_leaves_the_old_version_untouched() -> Result<(), String> {
+    let dir = TempDir::new("migrations")?;
+    let path = dir.path().join("knowledge_base.db");
+
+    // A v2 database whose documents already carry `created_at`, so the v5 step fails
+    // after the v3 step has altered `groups`.
+    {
+        let conn = Connection::open(&path).map_err(|e| e.to_string())?;
+        conn.execute_batch(
+            "CREATE TABLE documents (id TEXT PRIMARY KEY, title TEXT NOT NULL, content TEXT NOT NULL, created_at TEXT);
+             CREATE TABLE groups (id TEXT PRIMARY KEY, title TEXT NOT NULL);
+             INSERT INTO documents (id, title, content) VALUES ('doc-1', 'Title', 'Body');
+             PRAGMA user_version = 2;",
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let err = match db::init_database_at_path(&path, 4) {
+        Ok(_) => return Err("migration unexpectedly succeeded".to_string()),
+        Err(err) => err,
+    };
+    assert!(err.contains("v5"), "{err}");
+
+    let conn = Connection::open(&path).map_err(|e| e.to_string())?;
+    let version: i32 = conn
+        .query_row("PRAGMA user_version;", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    assert_eq!(version, 2);
+    let has_parent: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM pragma_table_info('groups') WHERE name = 'parent_id')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    assert!(!has_parent, "the v3 step was rolled back");
+    let docs: i64 = conn
+        .query_row("SELECT COUNT(*) FROM documents", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    assert_eq!(docs, 1);
+
+    Ok(())
+}
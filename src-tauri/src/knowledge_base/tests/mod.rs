@@ -1 +1,68 @@
+mod concurrency;
+mod image_extraction;
 mod internal_commands;
+mod migrations;
+mod query_expansion;
+mod recovery;
+mod summaries;
+mod vector_quantization;
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+
+use crate::knowledge_base::{db, embedding::Embedder, manager::KbManager};
+
+/// Embeds every text to the same vector, so ranking is decided by the keyword side.
+pub(crate) struct MockEmbedder;
+
+#[async_trait::async_trait]
+impl Embedder for MockEmbedder {
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, String> {
+        Ok(vec![0.1, 0.2, 0.3, 0.4])
+    }
+
+    fn dimensions(&self) -> usize {
+        4
+    }
+
+    fn name(&self) -> &'static str {
+        "mock/embedder"
+    }
+}
+
+/// An in-memory database with the full schema, sized for `dims`-dimensional embeddings.
+pub(crate) fn open_test_connection(dims: usize) -> Result<Connection, String> {
+    db::register_sqlite_vec();
+    let conn = Connection::open_in_memory().map_err(|e| e.to_string())?;
+    conn.execute_batch("PRAGMA foreign_keys = ON;")
+        .map_err(|e| e.to_string())?;
+    db::create_schema(&conn, dims)?;
+    Ok(conn)
+}
+
+/// An in-memory knowledge base embedding with `MockEmbedder`.
+pub(crate) fn build_test_manager() -> Result<KbManager, String> {
+    Ok(KbManager::new(open_test_connection(4)?, Box::new(MockEmbedder)))
+}
+
+/// A scratch directory, removed with everything in it when the test ends.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(prefix: &str) -> Result<Self, String> {
+        let dir = std::env::temp_dir().join(format!("depdok-{prefix}-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(Self(dir))
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
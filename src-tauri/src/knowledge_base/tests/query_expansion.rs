@@ -0,0 +1,108 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::build_test_manager;
+use crate::knowledge_base::{
+    manager::KbManager,
+    query_expansion::{QueryExpansion, TextGenerator},
+};
+
+/// Replies with a fixed response (or an error) and counts how often it was asked.
+struct MockGenerator {
+    response: Result<String, String>,
+    calls: AtomicUsize,
+}
+
+impl MockGenerator {
+    fn new(response: Result<&str, &str>) -> Self {
+        Self {
+            response: response.map(str::to_string).map_err(str::to_string),
+            calls: AtomicUsize::new(0),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TextGenerator for MockGenerator {
+    async fn generate(&self, _prompt: &str) -> Result<String, String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.response.clone()
+    }
+
+    fn model_id(&self) -> String {
+        "mock:model".to_string()
+    }
+}
+
+async fn seed(kb: &KbManager) -> Result<(), String> {
+    kb.upsert_document(
+        Some("overview".to_string()),
+        "Retry policy".to_string(),
+        "The retry policy is described in the networking notes.".to_string(),
+        vec!["project-a".to_string()],
+    )
+    .await?;
+    kb.upsert_document(
+        Some("networking".to_string()),
+        "Networking".to_string(),
+        "Failed HTTP calls use exponential backoff with jitter.".to_string(),
+        vec!["project-a".to_string()],
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn expansions_are_fused_and_cached() -> Result<(), String> {
+    let kb = build_test_manager()?;
+    seed(&kb).await?;
+
+    let plain = kb.search_hybrid("retry policy".to_string(), 5).await?;
+    assert_eq!(plain.first().map(|r| r.document_id.as_str()), Some("overview"));
+
+    let generator = MockGenerator::new(Ok("1. exponential backoff\n2. backoff jitter\n3. retry policy"));
+    let expanded = kb
+        .search_expanded("retry policy".to_string(), 5, None, None, QueryExpansion::Expand, &generator)
+        .await?;
+    assert_eq!(expanded.first().map(|r| r.document_id.as_str()), Some("networking"));
+    assert_eq!(generator.calls.load(Ordering::SeqCst), 1);
+
+    // Same query with different spacing and case is answered from the cache.
+    let again = kb
+        .search_expanded("Retry  Policy".to_string(), 5, None, None, QueryExpansion::Expand, &generator)
+        .await?;
+    assert_eq!(generator.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(again.first().map(|r| r.document_id.as_str()), Some("networking"));
+
+    // HyDE is cached separately.
+    kb.search_expanded("retry policy".to_string(), 5, None, None, QueryExpansion::Hyde, &generator)
+        .await?;
+    assert_eq!(generator.calls.load(Ordering::SeqCst), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn llm_failure_falls_back_to_plain_search() -> Result<(), String> {
+    let kb = build_test_manager()?;
+    seed(&kb).await?;
+
+    let generator = MockGenerator::new(Err("model not loaded"));
+    let results = kb
+        .search_expanded("retry policy".to_string(), 5, None, None, QueryExpansion::Expand, &generator)
+        .await?;
+    assert_eq!(results.first().map(|r| r.document_id.as_str()), Some("overview"));
+
+    // Failures are not cached, so the next call tries the LLM again.
+    kb.search_expanded("retry policy".to_string(), 5, None, None, QueryExpansion::Expand, &generator)
+        .await?;
+    assert_eq!(generator.calls.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn hyphenated_queries_do_not_break_keyword_search() -> Result<(), String> {
+    let kb = build_test_manager()?;
+    seed(&kb).await?;
+    kb.search_hybrid("back-off retry-policy".to_string(), 5).await?;
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+
+use super::TempDir;
+use crate::knowledge_base::vector_index::{self, VectorQuantization};
+use crate::knowledge_base::{db, embedding::Embedder, manager::KbManager, recovery};
+
+struct MockEmbedder;
+
+#[async_trait::async_trait]
+impl Embedder for MockEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        let seed = text.len() as f32;
+        Ok((0..8).map(|i| ((seed + i as f32) * 0.3).cos()).collect())
+    }
+
+    fn dimensions(&self) -> usize {
+        8
+    }
+
+    fn name(&self) -> &'static str {
+        "mock/embedder"
+    }
+}
+
+/// The database inside a scratch directory that also receives its quarantined copies
+/// and backups.
+fn db_path(dir: &TempDir) -> PathBuf {
+    dir.path().join("knowledge_base.db")
+}
+
+fn open_manager(db_path: &Path) -> Result<KbManager, String> {
+    let conn = db::init_database_at_path(db_path, 8)?;
+    let readers = db::open_read_connections(db_path, 2)?;
+    Ok(KbManager::new(conn, Box::new(MockEmbedder)).with_readers(readers))
+}
+
+fn count(conn: &Connection, table: &str) -> Result<i64, String> {
+    conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+async fn seed(kb: &KbManager) -> Result<(), String> {
+    for i in 0..20 {
+        kb.upsert_document(
+            Some(format!("note-{i}")),
+            format!("Note {i}"),
+            format!("Manual note {i} #kept"),
+            vec!["project-a".to_string()],
+        )
+        .await?;
+    }
+    kb.upsert_document(Some("note-linked".to_string()), "Linked".to_string(), "See [note one](note-1)".to_string(), vec![])
+        .await?;
+    Ok(())
+}
+
+/// Overwrite every page of the file that contains `marker` with garbage.
+fn corrupt_pages_containing(db_path: &Path, marker: &str) -> Result<usize, String> {
+    // Fold the WAL into the main file so every row lives in a database page.
+    Connection::open(db_path)
+        .and_then(|conn| conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);"))
+        .map_err(|e| e.to_string())?;
+
+    let mut bytes = std::fs::read(db_path).map_err(|e| e.to_string())?;
+    let page_size = match u16::from_be_bytes([bytes[16], bytes[17]]) {
+        1 => 65_536,
+        size => size as usize,
+    };
+    let mut corrupted = 0;
+    // Page 1 holds the schema; leave it readable so the damage is confined to rows.
+    for page in bytes.chunks_mut(page_size).skip(1) {
+        if page.windows(marker.len()).any(|w| w == marker.as_bytes()) {
+            page.iter_mut().enumerate().for_each(|(i, b)| *b = (i * 7 + 13) as u8);
+            corrupted += 1;
+        }
+    }
+    std::fs::write(db_path, bytes).map_err(|e| e.to_string())?;
+    Ok(corrupted)
+}
+
+#[tokio::test]
+async fn corrupt_database_is_quarantined_and_salvaged() -> Result<(), String> {
+    const NOTES: usize = 200;
+    let dir = TempDir::new("recovery")?;
+    let db_path = db_path(&dir);
+    {
+        let kb = open_manager(&db_path)?;
+        for i in 0..NOTES {
+            kb.upsert_document(
+                Some(format!("note-{i:03}")),
+                format!("Note {i}"),
+                format!("Manual note {i:03} #kept. {}", "Padding to spread notes over pages. ".repeat(8)),
+                vec!["project-a".to_string()],
+            )
+            .await?;
+        }
+    }
+    assert!(corrupt_pages_containing(&db_path, "Manual note 100 ")? > 0);
+
+    let conn = recovery::recover_corrupt_database(&db_path, "database disk image is malformed", || {
+        db::init_database_at_path(&db_path, 8)
+    })?;
+
+    // The original file is kept aside, never deleted.
+    let quarantined: Vec<_> = std::fs::read_dir(dir.path())
+        .map_err(|e| e.to_string())?
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with("knowledge_base.corrupt-") && name.ends_with(".db"))
+        .collect();
+    assert_eq!(quarantined.len(), 1);
+
+    // The rows on the damaged page are lost, the rest of the table is not.
+    let documents = count(&conn, "documents")? as usize;
+    assert!(documents > 0 && documents < NOTES, "{documents} documents salvaged");
+    assert_eq!(count(&conn, "document_chunks")?, 0);
+    let has = |id: &str| -> Result<bool, String> {
+        conn.query_row("SELECT EXISTS(SELECT 1 FROM documents WHERE id = ?1)", [id], |row| row.get(0))
+            .map_err(|e| e.to_string())
+    };
+    assert!(!has("note-100")?);
+
+    let report = recovery::read_report(&db_path)?.ok_or("expected a recovery report")?;
+    assert!(report.reembed_pending);
+    assert!(report.quarantined_path.ends_with(&quarantined[0]));
+    let salvaged = report.tables.iter().find(|t| t.table == "documents").ok_or("missing documents")?;
+    assert_eq!(salvaged.rows, documents);
+    if report.method == recovery::SalvageMethod::Recover {
+        // `.recover` reads past the damaged page instead of stopping there.
+        assert!(has("note-199")?);
+        assert!(documents >= NOTES - 20, "only {documents} documents recovered");
+    }
+    drop(conn);
+
+    // Re-embedding restores semantic search for salvaged documents.
+    let kb = open_manager(&db_path)?;
+    recovery::finish_recovery(&kb, &db_path).await?;
+    assert!(!recovery::read_report(&db_path)?.ok_or("report should remain until dismissed")?.reembed_pending);
+    let results = kb.search_hybrid("Manual".to_string(), 5).await?;
+    assert!(!results.is_empty());
+
+    recovery::dismiss_report(&db_path)?;
+    assert!(recovery::read_report(&db_path)?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn salvage_keeps_the_vector_quantization_mode() -> Result<(), String> {
+    let dir = TempDir::new("recovery")?;
+    let db_path = db_path(&dir);
+    {
+        let kb = open_manager(&db_path)?;
+        for i in 0..50 {
+            kb.upsert_document(
+                Some(format!("note-{i:02}")),
+                format!("Note {i}"),
+                format!("Manual note {i:02}. {}", "Padding to spread notes over pages. ".repeat(8)),
+                vec!["project-a".to_string()],
+            )
+            .await?;
+        }
+        kb.set_vector_quantization(VectorQuantization::Int8).await?;
+    }
+    assert!(corrupt_pages_containing(&db_path, "Manual note 25.")? > 0);
+
+    let conn = recovery::recover_corrupt_database(&db_path, "database disk image is malformed", || {
+        db::init_database_at_path(&db_path, 8)
+    })?;
+    assert_eq!(vector_index::read_quantization(&conn)?, VectorQuantization::Int8);
+    let report = recovery::read_report(&db_path)?.ok_or("expected a recovery report")?;
+    let meta = report.tables.iter().find(|t| t.table == "kb_meta").ok_or("missing kb_meta")?;
+    assert!(meta.complete && meta.rows == 1, "{meta:?}");
+    drop(conn);
+
+    // Re-embedding fills the quantized index again.
+    let kb = open_manager(&db_path)?;
+    recovery::finish_recovery(&kb, &db_path).await?;
+    assert_eq!(kb.get_vector_quantization().await?, VectorQuantization::Int8);
+    assert!(!kb.search_hybrid("Manual".to_string(), 5).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn backups_can_be_listed_and_restored_on_next_open() -> Result<(), String> {
+    let dir = TempDir::new("recovery")?;
+    let db_path = db_path(&dir);
+
+    let backup = {
+        let kb = open_manager(&db_path)?;
+        seed(&kb).await?;
+        let backup = recovery::create_backup(&kb, &db_path).await?;
+        kb.upsert_document(Some("after-backup".to_string()), "Later".to_string(), "added later".to_string(), vec![])
+            .await?;
+        backup
+    };
+
+    let backups = recovery::list_backups(&db_path)?;
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].name, backup.name);
+    assert!(backups[0].size > 0);
+
+    assert!(recovery::schedule_restore(&db_path, "missing.db").is_err());
+    recovery::schedule_restore(&db_path, &backup.name)?;
+
+    // The restore happens when the app next opens the database.
+    let conn = db::open_app_database(&db_path, 8)?;
+    assert_eq!(count(&conn, "documents")?, 21);
+    let replaced = std::fs::read_dir(dir.path())
+        .map_err(|e| e.to_string())?
+        .filter_map(|e| e.ok())
+        .any(|e| e.file_name().to_string_lossy().starts_with("knowledge_base.replaced-"));
+    assert!(replaced, "the pre-restore database should be kept");
+
+    Ok(())
+}
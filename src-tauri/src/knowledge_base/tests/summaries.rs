@@ -0,0 +1,105 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::build_test_manager;
+use crate::knowledge_base::{manager::KbManager, query_expansion::TextGenerator};
+
+/// Summarizes every document the same way and counts the requests.
+struct MockSummarizer {
+    calls: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl TextGenerator for MockSummarizer {
+    async fn generate(&self, _prompt: &str) -> Result<String, String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok("Summary: How deploys are rolled out.\nKeywords: deploy, rollout, canary".to_string())
+    }
+
+    fn model_id(&self) -> String {
+        "mock:model".to_string()
+    }
+}
+
+async fn upsert(kb: &KbManager, id: &str, content: String) -> Result<(), String> {
+    kb.upsert_document(Some(id.to_string()), id.to_string(), content, vec!["project-a".to_string()])
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn summaries_are_attached_until_the_content_changes() -> Result<(), String> {
+    let kb = build_test_manager()?;
+    let deploys = "Deploys go out as a canary to five percent of hosts first. ".repeat(5);
+    upsert(&kb, "deploys", deploys.clone()).await?;
+    upsert(&kb, "stub", "Too short to summarize.".to_string()).await?;
+
+    let summarizer = MockSummarizer { calls: AtomicUsize::new(0) };
+    assert_eq!(kb.summarize_pending(&summarizer, 10).await?, 1, "short documents are skipped");
+    assert_eq!(kb.summarize_pending(&summarizer, 10).await?, 0);
+    assert_eq!(summarizer.calls.load(Ordering::SeqCst), 1);
+
+    let document = kb.get_document("deploys".to_string()).await?.ok_or("missing document")?;
+    assert_eq!(document.summary.as_deref(), Some("How deploys are rolled out."));
+    assert_eq!(document.keywords, vec!["deploy", "rollout", "canary"]);
+
+    let results = kb.search_hybrid("canary hosts".to_string(), 5).await?;
+    let hit = results.iter().find(|r| r.document_id == "deploys").ok_or("missing search hit")?;
+    assert_eq!(hit.summary.as_deref(), Some("How deploys are rolled out."));
+
+    let graph = kb.get_project_graph("project-a".to_string()).await?;
+    let node = graph.documents.iter().find(|d| d.id == "deploys").ok_or("missing graph node")?;
+    assert_eq!(node.keywords.len(), 3);
+
+    // An edit makes the summary stale: it disappears and the next pass regenerates it.
+    upsert(&kb, "deploys", format!("{deploys}Rollbacks are manual.")).await?;
+    let document = kb.get_document("deploys".to_string()).await?.ok_or("missing document")?;
+    assert_eq!(document.summary, None);
+    assert!(document.keywords.is_empty());
+    assert_eq!(kb.summarize_pending(&summarizer, 10).await?, 1);
+    assert_eq!(summarizer.calls.load(Ordering::SeqCst), 2);
+
+    Ok(())
+}
+
+/// Fails on documents whose prompt mentions "broken" and summarizes the rest.
+struct FlakySummarizer {
+    calls: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl TextGenerator for FlakySummarizer {
+    async fn generate(&self, prompt: &str) -> Result<String, String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if prompt.contains("broken") {
+            return Err("context window exceeded".to_string());
+        }
+        Ok("Summary: A working document.".to_string())
+    }
+
+    fn model_id(&self) -> String {
+        "mock:flaky".to_string()
+    }
+}
+
+#[tokio::test]
+async fn failed_summaries_are_not_retried_until_the_content_changes() -> Result<(), String> {
+    let kb = build_test_manager()?;
+    let broken = "This broken document trips up the model every time. ".repeat(5);
+    upsert(&kb, "a-broken", broken.clone()).await?;
+    upsert(&kb, "b-working", "This one summarizes without trouble at all. ".repeat(5)).await?;
+
+    let summarizer = FlakySummarizer { calls: AtomicUsize::new(0) };
+    assert!(kb.summarize_pending(&summarizer, 10).await.is_err());
+    // The failed document no longer blocks the queue, and is not prompted again.
+    assert_eq!(kb.summarize_pending(&summarizer, 10).await?, 1);
+    assert_eq!(kb.summarize_pending(&summarizer, 10).await?, 0);
+    assert_eq!(summarizer.calls.load(Ordering::SeqCst), 2);
+    let document = kb.get_document("a-broken".to_string()).await?.ok_or("missing document")?;
+    assert_eq!(document.summary, None);
+
+    upsert(&kb, "a-broken", format!("{broken}Now edited.")).await?;
+    assert!(kb.summarize_pending(&summarizer, 10).await.is_err());
+    assert_eq!(summarizer.calls.load(Ordering::SeqCst), 3);
+
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rusqlite::{params, Connection};
+
+use super::open_test_connection;
+use crate::knowledge_base::{
+    embedding::Embedder,
+    manager::KbManager,
+    vector_index::{self, VectorQuantization},
+};
+
+const DIMS: usize = 384;
+const CORPUS_SIZE: usize = 4000;
+const CLUSTERS: usize = 40;
+const QUERIES: usize = 50;
+const K: usize = 10;
+
+/// Deterministic xorshift64 generator so the benchmark corpus is identical on every run.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0
+    }
+
+    fn vector(&mut self) -> Vec<f32> {
+        (0..DIMS).map(|_| self.next_f32()).collect()
+    }
+}
+
+fn normalize(mut v: Vec<f32>) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    v.iter_mut().for_each(|x| *x /= norm);
+    v
+}
+
+/// Clustered unit vectors, which resemble real embeddings more than uniform noise does.
+fn synthetic_corpus(rng: &mut XorShift, centroids: &[Vec<f32>], count: usize) -> Vec<Vec<f32>> {
+    (0..count)
+        .map(|i| {
+            let centroid = &centroids[i % centroids.len()];
+            let noise = normalize(rng.vector());
+            normalize(centroid.iter().zip(noise).map(|(c, n)| c + 0.8 * n).collect())
+        })
+        .collect()
+}
+
+fn open_index(quantization: VectorQuantization, corpus: &[Vec<f32>]) -> Result<Connection, String> {
+    let conn = open_test_connection(DIMS)?;
+
+    conn.execute_batch("DROP TABLE documents_embeddings;")
+        .map_err(|e| e.to_string())?;
+    vector_index::write_quantization(&conn, quantization)?;
+    vector_index::create_embeddings_table(&conn, DIMS, quantization)?;
+
+    for (i, embedding) in corpus.iter().enumerate() {
+        let doc_id = format!("doc-{i}");
+        let chunk_id = format!("{doc_id}#0");
+        conn.execute(
+            "INSERT INTO documents (id, title, content) VALUES (?1, ?1, '')",
+            params![doc_id],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO document_chunks (chunk_id, document_id, chunk_index, content)
+             VALUES (?1, ?2, 0, '')",
+            params![chunk_id, doc_id],
+        )
+        .map_err(|e| e.to_string())?;
+        vector_index::insert_embedding(&conn, quantization, &chunk_id, &doc_id, embedding)?;
+    }
+
+    Ok(conn)
+}
+
+fn run_queries(
+    conn: &Connection,
+    quantization: VectorQuantization,
+    queries: &[Vec<f32>],
+) -> Result<(Vec<Vec<String>>, Duration), String> {
+    let started = Instant::now();
+    let mut results = Vec::new();
+    for query in queries {
+        let matches = vector_index::knn(conn, quantization, query, K)?;
+        results.push(matches.into_iter().map(|m| m.chunk_id).collect());
+    }
+    Ok((results, started.elapsed() / queries.len() as u32))
+}
+
+fn recall(expected: &[Vec<String>], actual: &[Vec<String>]) -> f32 {
+    let mut hits = 0;
+    for (expected, actual) in expected.iter().zip(actual) {
+        let expected: HashSet<&String> = expected.iter().collect();
+        hits += actual.iter().filter(|id| expected.contains(id)).count();
+    }
+    hits as f32 / (expected.len() * K) as f32
+}
+
+/// Recall@10 of the quantized modes against exact float KNN, with average query latency.
+/// A benchmark rather than a check, so it only runs on request:
+/// `cargo test quantization_recall_and_latency -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn quantization_recall_and_latency() -> Result<(), String> {
+    let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+    let centroids: Vec<Vec<f32>> = (0..CLUSTERS).map(|_| normalize(rng.vector())).collect();
+    let corpus = synthetic_corpus(&mut rng, &centroids, CORPUS_SIZE);
+    let queries = synthetic_corpus(&mut rng, &centroids, QUERIES);
+
+    let float_conn = open_index(VectorQuantization::Float, &corpus)?;
+    let (exact, float_latency) = run_queries(&float_conn, VectorQuantization::Float, &queries)?;
+    println!("float : recall@{K} 1.000, {float_latency:?}/query");
+
+    for (quantization, min_recall) in [
+        (VectorQuantization::Int8, 0.95),
+        (VectorQuantization::Binary, 0.85),
+    ] {
+        let conn = open_index(quantization, &corpus)?;
+        let (results, latency) = run_queries(&conn, quantization, &queries)?;
+        let recall = recall(&exact, &results);
+        println!("{:<6}: recall@{K} {recall:.3}, {latency:?}/query", quantization.as_str());
+        assert!(
+            recall >= min_recall,
+            "{} recall@{K} {recall:.3} below {min_recall}",
+            quantization.as_str()
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn quantized_matches_are_rescored_with_float_vectors() -> Result<(), String> {
+    let mut rng = XorShift(0xF10A7);
+    let corpus: Vec<Vec<f32>> = (0..50).map(|_| normalize(rng.vector())).collect();
+    let query = normalize(rng.vector());
+
+    let float_conn = open_index(VectorQuantization::Float, &corpus)?;
+    let exact = vector_index::knn(&float_conn, VectorQuantization::Float, &query, 5)?;
+    for quantization in [VectorQuantization::Int8, VectorQuantization::Binary] {
+        let conn = open_index(quantization, &corpus)?;
+        for m in vector_index::knn(&conn, quantization, &query, 5)? {
+            let index: usize = m.document_id.trim_start_matches("doc-").parse().map_err(|_| "bad id")?;
+            let expected: f32 = corpus[index].iter().zip(&query).map(|(c, q)| (c - q).powi(2)).sum::<f32>().sqrt();
+            assert!((m.distance - expected).abs() < 1e-4, "{quantization:?}: {} vs {expected}", m.distance);
+        }
+        let ids = |matches: &[vector_index::ChunkMatch]| matches.iter().map(|m| m.chunk_id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&vector_index::knn(&conn, quantization, &query, 1)?), ids(&exact[..1]));
+    }
+    Ok(())
+}
+
+#[test]
+fn int8_quantization_preserves_sign_and_scale() {
+    let quantized = vector_index::quantize_int8(&[0.5, -0.25, 0.0, -0.5]);
+    let values: Vec<i8> = quantized.into_iter().map(|b| b as i8).collect();
+    assert_eq!(values, vec![127, -64, 0, -127]);
+}
+
+/// Deterministic 8-dimension embedder that counts how often it ran.
+struct ConstantEmbedder(Arc<AtomicUsize>);
+
+#[async_trait::async_trait]
+impl Embedder for ConstantEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        let seed = text.len() as f32;
+        Ok((0..8).map(|i| ((seed + i as f32) * 0.7).sin()).collect())
+    }
+
+    fn dimensions(&self) -> usize {
+        8
+    }
+
+    fn name(&self) -> &'static str {
+        "mock/constant"
+    }
+}
+
+#[test]
+fn stored_vectors_read_back_in_every_mode() -> Result<(), String> {
+    let mut rng = XorShift(0x5eed);
+    let corpus: Vec<Vec<f32>> = (0..2).map(|_| normalize(rng.vector())).collect();
+    for quantization in [VectorQuantization::Float, VectorQuantization::Int8, VectorQuantization::Binary] {
+        let conn = open_index(quantization, &corpus)?;
+        let stored = vector_index::chunk_vector(&conn, quantization, "doc-0#0")?.ok_or("vector missing")?;
+        let norm = stored.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let cosine = stored.iter().zip(&corpus[0]).map(|(s, c)| s * c).sum::<f32>() / norm;
+        assert!(cosine > 0.99, "{quantization:?} read back with cosine {cosine}");
+        assert!(vector_index::chunk_vector(&conn, quantization, "missing#0")?.is_none());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn switching_quantization_rebuilds_the_index_from_stored_vectors() -> Result<(), String> {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let kb = KbManager::new(open_test_connection(8)?, Box::new(ConstantEmbedder(calls.clone())));
+
+    kb.upsert_document(Some("a".to_string()), "A".to_string(), "alpha".to_string(), vec![]).await?;
+    kb.upsert_document(Some("b".to_string()), "B".to_string(), "beta beta".to_string(), vec![]).await?;
+
+    for quantization in [VectorQuantization::Int8, VectorQuantization::Binary, VectorQuantization::Float] {
+        let before = calls.load(Ordering::SeqCst);
+        assert_eq!(kb.set_vector_quantization(quantization).await?, 2);
+        assert_eq!(calls.load(Ordering::SeqCst), before, "switching must not run the model");
+        assert_eq!(kb.get_vector_quantization().await?, quantization);
+
+        let results = kb.search_similar("alpha".to_string(), 1).await?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "a");
+        assert!(results[0].distance < 1e-2, "{quantization:?}: {}", results[0].distance);
+
+        // New writes use the active mode too.
+        kb.upsert_document(Some("c".to_string()), "C".to_string(), "gamma gamma gamma".to_string(), vec![]).await?;
+        kb.delete_document("c".to_string()).await?;
+    }
+
+    let conn = kb.db_lock();
+    let conn = conn.lock().await;
+    let side_vectors: i64 = conn
+        .query_row("SELECT COUNT(*) FROM document_chunk_vectors", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    assert_eq!(side_vectors, 0, "float mode should not keep re-scoring vectors");
+
+    Ok(())
+}
@@ -0,0 +1,370 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// How chunk embeddings are stored in the `documents_embeddings` vec0 table.
+///
+/// Quantized modes shrink the vec0 index, then re-score an oversampled candidate set
+/// against the float32 copy of each vector kept in `document_chunk_vectors`, which
+/// recovers most of the recall lost to quantization. Re-scored distances are L2
+/// distances between unit-normalised vectors, so they stay comparable with the float
+/// mode for normalised embedding models.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VectorQuantization {
+    /// Full float32 vectors with exact L2 KNN.
+    #[default]
+    Float,
+    /// One signed byte per dimension (4x smaller index), cosine KNN over the int8 vectors.
+    /// Shrinks the scanned index rather than the time: sqlite-vec scans int8 slower than float.
+    Int8,
+    /// One bit per dimension (32x smaller index), hamming KNN; the fastest scan.
+    Binary,
+}
+
+const QUANTIZATION_META_KEY: &str = "vector_quantization";
+
+impl VectorQuantization {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Float => "float",
+            Self::Int8 => "int8",
+            Self::Binary => "binary",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "float" => Ok(Self::Float),
+            "int8" => Ok(Self::Int8),
+            "binary" => Ok(Self::Binary),
+            other => Err(format!("Unknown vector quantization: {other}")),
+        }
+    }
+
+    /// How many KNN candidates to fetch per requested result before re-scoring.
+    fn candidate_multiplier(&self) -> usize {
+        match self {
+            Self::Float => 1,
+            Self::Int8 => 4,
+            Self::Binary => 10,
+        }
+    }
+}
+
+/// A chunk returned by `knn`, ordered by ascending distance.
+#[derive(Debug, Clone)]
+pub struct ChunkMatch {
+    pub chunk_id: String,
+    pub document_id: String,
+    pub distance: f32,
+}
+
+fn f32_slice_to_bytes(v: &[f32]) -> Vec<u8> {
+    v.iter().flat_map(|f| f.to_le_bytes()).collect()
+}
+
+fn bytes_to_f32_vec(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+/// Quantize to int8 with a per-vector scale. Cosine distance is scale invariant,
+/// so using the full [-127, 127] range for every vector keeps as much precision as possible.
+pub fn quantize_int8(v: &[f32]) -> Vec<u8> {
+    let max_abs = v.iter().fold(0.0f32, |acc, x| acc.max(x.abs()));
+    let scale = if max_abs > 0.0 { 127.0 / max_abs } else { 0.0 };
+    v.iter()
+        .map(|x| (x * scale).round().clamp(-127.0, 127.0) as i8 as u8)
+        .collect()
+}
+
+/// L2 distance between the unit-normalised query and a unit-normalised stored vector.
+fn rescore(query: &[f32], query_norm: f32, stored: &[f32]) -> f32 {
+    let mut dot = 0.0f32;
+    let mut norm = 0.0f32;
+    for (q, s) in query.iter().zip(stored.iter()) {
+        dot += q * s;
+        norm += s * s;
+    }
+    if query_norm == 0.0 || norm == 0.0 {
+        return f32::MAX;
+    }
+    let cosine = dot / (query_norm * norm.sqrt());
+    (2.0 - 2.0 * cosine).max(0.0).sqrt()
+}
+
+/// Read the quantization mode the database was created with (float when unset).
+pub fn read_quantization(conn: &Connection) -> Result<VectorQuantization, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM kb_meta WHERE key = ?1",
+            params![QUANTIZATION_META_KEY],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read vector quantization: {e}"))?;
+
+    match value {
+        Some(value) => VectorQuantization::parse(&value),
+        None => Ok(VectorQuantization::Float),
+    }
+}
+
+pub fn write_quantization(conn: &Connection, quantization: VectorQuantization) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO kb_meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![QUANTIZATION_META_KEY, quantization.as_str()],
+    )
+    .map_err(|e| format!("Failed to save vector quantization: {e}"))?;
+    Ok(())
+}
+
+/// Create the `documents_embeddings` vec0 table with the column type for `quantization`.
+///
+/// Dimension count is interpolated at runtime so it matches the active provider.
+/// NOTE: Changing providers or quantization requires re-creating this table because
+///       vec0 does not support ALTER TABLE.
+pub fn create_embeddings_table(
+    conn: &Connection,
+    dims: usize,
+    quantization: VectorQuantization,
+) -> Result<(), String> {
+    let column = match quantization {
+        VectorQuantization::Float => format!("FLOAT[{dims}]"),
+        VectorQuantization::Int8 => format!("INT8[{dims}] distance_metric=cosine"),
+        VectorQuantization::Binary => {
+            if !dims.is_multiple_of(8) {
+                return Err(format!(
+                    "Binary quantization needs a dimension count divisible by 8 (got {dims})"
+                ));
+            }
+            format!("BIT[{dims}]")
+        }
+    };
+
+    conn.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS documents_embeddings USING vec0(
+            chunk_id    TEXT PRIMARY KEY,
+            document_id TEXT,
+            embedding   {column}
+        );"
+    ))
+    .map_err(|e| format!("vec0 table creation failed: {e}"))
+}
+
+/// Dimension count the `documents_embeddings` table was created with, if it exists.
+pub fn table_dimensions(conn: &Connection) -> Result<Option<usize>, String> {
+    let sql: Option<String> = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE name = 'documents_embeddings'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read embeddings table: {e}"))?;
+    Ok(sql.and_then(|sql| {
+        let start = sql.find('[')? + 1;
+        let end = start + sql[start..].find(']')?;
+        sql[start..end].trim().parse().ok()
+    }))
+}
+
+/// Store one chunk embedding in the layout required by `quantization`.
+/// The chunk row must already exist in `document_chunks`.
+pub fn insert_embedding(
+    conn: &Connection,
+    quantization: VectorQuantization,
+    chunk_id: &str,
+    document_id: &str,
+    embedding: &[f32],
+) -> Result<(), String> {
+    insert_index_vector(conn, quantization, chunk_id, document_id, embedding)?;
+
+    if quantization != VectorQuantization::Float {
+        conn.execute(
+            "INSERT OR REPLACE INTO document_chunk_vectors (chunk_id, embedding) VALUES (?1, ?2)",
+            params![chunk_id, f32_slice_to_bytes(embedding)],
+        )
+        .map_err(|e| format!("Failed to insert re-scoring vector for {chunk_id}: {e}"))?;
+    }
+
+    Ok(())
+}
+
+/// Insert one vector into the vec0 table only, quantized for `quantization`.
+fn insert_index_vector(
+    conn: &Connection,
+    quantization: VectorQuantization,
+    chunk_id: &str,
+    document_id: &str,
+    embedding: &[f32],
+) -> Result<(), String> {
+    let sql = match quantization {
+        VectorQuantization::Float => {
+            "INSERT INTO documents_embeddings (chunk_id, document_id, embedding) VALUES (?1, ?2, ?3)"
+        }
+        VectorQuantization::Int8 => {
+            "INSERT INTO documents_embeddings (chunk_id, document_id, embedding) VALUES (?1, ?2, vec_int8(?3))"
+        }
+        VectorQuantization::Binary => {
+            "INSERT INTO documents_embeddings (chunk_id, document_id, embedding) VALUES (?1, ?2, vec_quantize_binary(?3))"
+        }
+    };
+    let bytes = match quantization {
+        VectorQuantization::Int8 => quantize_int8(embedding),
+        _ => f32_slice_to_bytes(embedding),
+    };
+
+    conn.prepare_cached(sql)
+        .and_then(|mut stmt| stmt.execute(params![chunk_id, document_id, bytes]))
+        .map_err(|e| format!("Failed to insert embedding for {chunk_id}: {e}"))?;
+    Ok(())
+}
+
+/// Switch the database to `quantization`, rebuilding the vec0 table from the float32
+/// vectors already stored: the vec0 table itself in float mode, the re-scoring copies
+/// otherwise. No model runs, so callers can do this in one transaction and searches
+/// never see a partial index. Returns the number of documents in the rebuilt index.
+pub fn requantize(conn: &Connection, dims: usize, quantization: VectorQuantization) -> Result<usize, String> {
+    // Gather every float32 vector in the re-scoring table, which outlives the vec0 table.
+    if read_quantization(conn)? == VectorQuantization::Float {
+        conn.execute(
+            "INSERT OR REPLACE INTO document_chunk_vectors (chunk_id, embedding)
+             SELECT chunk_id, embedding FROM documents_embeddings
+             WHERE chunk_id IN (SELECT chunk_id FROM document_chunks)",
+            [],
+        )
+        .map_err(|e| format!("Failed to copy float vectors: {e}"))?;
+    }
+
+    conn.execute_batch("DROP TABLE IF EXISTS documents_embeddings;")
+        .map_err(|e| format!("Failed to clear embeddings: {e}"))?;
+    write_quantization(conn, quantization)?;
+    create_embeddings_table(conn, dims, quantization)?;
+
+    let mut documents = std::collections::HashSet::new();
+    {
+        let mut stmt = conn
+            .prepare(
+                "SELECT cv.chunk_id, c.document_id, cv.embedding
+                 FROM document_chunk_vectors cv
+                 INNER JOIN document_chunks c ON c.chunk_id = cv.chunk_id",
+            )
+            .map_err(|e| e.to_string())?;
+        let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+        while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+            let chunk_id: String = row.get(0).map_err(|e| e.to_string())?;
+            let document_id: String = row.get(1).map_err(|e| e.to_string())?;
+            let embedding: Vec<u8> = row.get(2).map_err(|e| e.to_string())?;
+            insert_index_vector(conn, quantization, &chunk_id, &document_id, &bytes_to_f32_vec(&embedding))?;
+            documents.insert(document_id);
+        }
+    }
+
+    // Float mode searches the vec0 table directly and keeps no copies.
+    if quantization == VectorQuantization::Float {
+        conn.execute("DELETE FROM document_chunk_vectors", [])
+            .map_err(|e| format!("Failed to clear re-scoring vectors: {e}"))?;
+    }
+
+    Ok(documents.len())
+}
+
+/// K-nearest chunks to `query`. Quantized modes fetch an oversampled candidate set
+/// from vec0 and re-score it against the float query before truncating to `k`.
+pub fn knn(
+    conn: &Connection,
+    quantization: VectorQuantization,
+    query: &[f32],
+    k: usize,
+) -> Result<Vec<ChunkMatch>, String> {
+    let candidates = (k * quantization.candidate_multiplier()) as i64;
+
+    let sql = match quantization {
+        VectorQuantization::Float => {
+            "SELECT chunk_id, document_id, distance, NULL FROM documents_embeddings
+             WHERE embedding MATCH ?1 AND k = ?2 ORDER BY distance ASC"
+        }
+        VectorQuantization::Int8 => {
+            "SELECT de.chunk_id, de.document_id, de.distance, cv.embedding
+             FROM (
+                 SELECT chunk_id, document_id, distance FROM documents_embeddings
+                 WHERE embedding MATCH vec_int8(?1) AND k = ?2
+             ) de
+             LEFT JOIN document_chunk_vectors cv ON cv.chunk_id = de.chunk_id
+             ORDER BY de.distance ASC"
+        }
+        VectorQuantization::Binary => {
+            "SELECT de.chunk_id, de.document_id, de.distance, cv.embedding
+             FROM (
+                 SELECT chunk_id, document_id, distance FROM documents_embeddings
+                 WHERE embedding MATCH vec_quantize_binary(?1) AND k = ?2
+             ) de
+             LEFT JOIN document_chunk_vectors cv ON cv.chunk_id = de.chunk_id
+             ORDER BY de.distance ASC"
+        }
+    };
+    let query_bytes = match quantization {
+        VectorQuantization::Int8 => quantize_int8(query),
+        _ => f32_slice_to_bytes(query),
+    };
+
+    let mut stmt = conn
+        .prepare_cached(sql)
+        .map_err(|e| format!("Failed to prepare KNN query: {e}"))?;
+    let rows = stmt
+        .query_map(params![query_bytes, candidates], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, f32>(2)?,
+                row.get::<_, Option<Vec<u8>>>(3)?,
+            ))
+        })
+        .map_err(|e| format!("KNN query failed: {e}"))?;
+
+    let query_norm = query.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let mut matches = Vec::new();
+    for row in rows {
+        let (chunk_id, document_id, distance, stored) = row.map_err(|e| e.to_string())?;
+        let distance = match (quantization, stored) {
+            (VectorQuantization::Float, _) => distance,
+            (_, Some(stored)) => rescore(query, query_norm, &bytes_to_f32_vec(&stored)),
+            // No re-scoring vector available: keep the coarse order behind every re-scored match.
+            (_, None) => f32::MAX,
+        };
+        matches.push(ChunkMatch {
+            chunk_id,
+            document_id,
+            distance,
+        });
+    }
+
+    if quantization != VectorQuantization::Float {
+        matches.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap_or(std::cmp::Ordering::Equal));
+    }
+    matches.truncate(k);
+
+    Ok(matches)
+}
+
+/// Read back one chunk's stored float32 embedding. Quantized modes read the re-scoring copy.
+pub fn chunk_vector(
+    conn: &Connection,
+    quantization: VectorQuantization,
+    chunk_id: &str,
+) -> Result<Option<Vec<f32>>, String> {
+    let sql = match quantization {
+        VectorQuantization::Float => "SELECT embedding FROM documents_embeddings WHERE chunk_id = ?1",
+        _ => "SELECT embedding FROM document_chunk_vectors WHERE chunk_id = ?1",
+    };
+    let bytes: Option<Vec<u8>> = conn
+        .prepare_cached(sql)
+        .and_then(|mut stmt| stmt.query_row(params![chunk_id], |row| row.get(0)).optional())
+        .map_err(|e| format!("Failed to read embedding for {chunk_id}: {e}"))?;
+
+    Ok(bytes.map(|bytes| bytes_to_f32_vec(&bytes)))
+}
@@ -702,94 +702,6 @@ fn get_mcp_server_paths(app: tauri::AppHandle) -> Vec<String> {
         .collect()
 }
 
-#[tauri::command]
-fn check_mcp_config_status(app: tauri::AppHandle, agent_id: String, workspace_root: Option<String>) -> bool {
-    let home_dir = app.path().home_dir().ok();
-    
-    // Check helper
-    let file_contains_depdok = |path: &std::path::Path| -> bool {
-        if !path.exists() {
-            return false;
-        }
-        if let Ok(content) = std::fs::read_to_string(path) {
-            content.contains("depdok")
-        } else {
-            false
-        }
-    };
-
-    match agent_id.as_str() {
-        "claude" => {
-            if let Some(home) = home_dir {
-                #[cfg(target_os = "windows")]
-                let path = app.path().app_config_dir().ok()
-                    .map(|p| p.join("Claude/claude_desktop_config.json"))
-                    .unwrap_or_else(|| home.join("AppData/Roaming/Claude/claude_desktop_config.json"));
-                
-                #[cfg(not(target_os = "windows"))]
-                let path = home.join("Library/Application Support/Claude/claude_desktop_config.json");
-                
-                return file_contains_depdok(&path);
-            }
-            false
-        }
-        "claudecode" => {
-            let mut configured = false;
-            if let Some(ref home) = home_dir {
-                let path = home.join(".claude.json");
-                configured = configured || file_contains_depdok(&path);
-            }
-            if let Some(ref root) = workspace_root {
-                let path = std::path::PathBuf::from(root).join(".mcp.json");
-                configured = configured || file_contains_depdok(&path);
-            }
-            configured
-        }
-        "copilot" => {
-            let mut configured = false;
-            if let Some(ref home) = home_dir {
-                let path = home.join(".copilot/mcp-config.json");
-                configured = configured || file_contains_depdok(&path);
-            }
-            if let Some(ref root) = workspace_root {
-                let path = std::path::PathBuf::from(root).join(".vscode/mcp.json");
-                configured = configured || file_contains_depdok(&path);
-            }
-            configured
-        }
-        "gemini" => {
-            let mut configured = false;
-            if let Some(ref home) = home_dir {
-                let path = home.join(".gemini/settings.json");
-                configured = configured || file_contains_depdok(&path);
-            }
-            if let Some(ref root) = workspace_root {
-                let path = std::path::PathBuf::from(root).join(".vscode/mcp.json");
-                configured = configured || file_contains_depdok(&path);
-            }
-            configured
-        }
-        "codex" => {
-            let mut configured = false;
-            if let Some(ref root) = workspace_root {
-                let path1 = std::path::PathBuf::from(root).join(".vscode/mcp.json");
-                let path2 = std::path::PathBuf::from(root).join("codex-config.json");
-                configured = configured || file_contains_depdok(&path1) || file_contains_depdok(&path2);
-            }
-            configured
-        }
-        "opencode" => {
-            if let Some(ref root) = workspace_root {
-                let path = std::path::PathBuf::from(root).join("opencode.jsonc");
-                return file_contains_depdok(&path);
-            }
-            false
-        }
-        _ => false
-    }
-}
-
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // Load .env file (ignore errors if file doesn't exist)
@@ -1025,7 +937,9 @@ pub fn run() {
             is_licensed,
             get_grace_period_info,
             get_mcp_server_paths,
-            check_mcp_config_status,
+            commands::mcp_config::check_mcp_config_status,
+            commands::mcp_config::write_mcp_client_config,
+            commands::mcp_config::remove_mcp_client_config,
             activate_license,
             commands::terminal::start_pty_session,
             commands::terminal::write_to_pty,
@@ -1038,9 +952,31 @@ pub fn run() {
             knowledge_base::commands::connect_to,
             knowledge_base::commands::search_similar,
             knowledge_base::commands::search_hybrid,
+            knowledge_base::commands::find_duplicates,
             knowledge_base::commands::get_chunk_context,
             knowledge_base::commands::get_document,
             knowledge_base::commands::get_project_graph,
+            knowledge_base::commands::list_groups,
+            knowledge_base::commands::set_document_groups,
+            knowledge_base::commands::rename_group,
+            knowledge_base::commands::merge_groups,
+            knowledge_base::commands::delete_group,
+            knowledge_base::commands::get_vector_quantization,
+            knowledge_base::commands::set_vector_quantization,
+            knowledge_base::commands::get_image_extraction,
+            knowledge_base::commands::set_image_extraction,
+            knowledge_base::commands::get_auto_summaries,
+            knowledge_base::commands::set_auto_summaries,
+            knowledge_base::commands::get_recovery_report,
+            knowledge_base::commands::dismiss_recovery_report,
+            knowledge_base::commands::list_kb_backups,
+            knowledge_base::commands::create_kb_backup,
+            knowledge_base::commands::restore_kb_backup,
+            knowledge_base::commands::list_recent_documents,
+            knowledge_base::commands::get_document_changes,
+            knowledge_base::commands::list_document_revisions,
+            knowledge_base::commands::diff_document_revisions,
+            knowledge_base::commands::restore_document_revision,
             knowledge_base::commands::set_current_project_group,
             knowledge_base::commands::test_database_query,
             knowledge_base::commands::rebuild_all_edges,
@@ -1,22 +1,28 @@
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Emitter, State};
 use serde_json::Value;
 use chrono::Utc;
 
+use crate::knowledge_base::{rag, KbState};
 use crate::llm::provider::LlmState;
 use crate::llm::remote::ChatMessage;
 use crate::llm::session::{self, ChatSession, PersistedMessage};
 use crate::llm::providers::get_provider;
 use crate::llm::tools;
 
+/// Separator the chat panel puts between the typed question and attached file contents.
+const ATTACHED_CONTEXT_DELIMITER: &str = "\n\n---\n**Attached File Context:**";
+
 #[tauri::command]
 pub async fn send_chat_message(
     app: AppHandle,
     state: State<'_, LlmState>,
-    messages: Vec<ChatMessage>,
+    kb_state: State<'_, KbState>,
+    mut messages: Vec<ChatMessage>,
     workspace_root: String,
     session_id: String,
+    use_knowledge_base: Option<bool>,
 ) -> Result<(), String> {
     println!(
         "[llm][command] send_chat_message for session: {}, messages count: {}",
@@ -41,18 +47,49 @@ pub async fn send_chat_message(
     });
 
     let now = Utc::now().to_rfc3339();
-    let persisted_msgs: Vec<PersistedMessage> = messages
+    // The UI resends the whole conversation each turn without sources, so carry them
+    // over from the saved history for messages that are unchanged.
+    let previous = session::load_session(&workspace_root, &session_id).ok();
+    let mut persisted_msgs: Vec<PersistedMessage> = messages
         .iter()
-        .map(|m| PersistedMessage {
+        .enumerate()
+        .map(|(i, m)| PersistedMessage {
             role: m.role.clone(),
             content: m.content.clone(),
             timestamp: now.clone(),
             name: m.name.clone(),
             tool_input: None,
             tool_output: None,
+            sources: previous
+                .as_ref()
+                .and_then(|p| p.messages.get(i))
+                .filter(|p| p.role == m.role && p.content == m.content)
+                .map(|p| p.sources.clone())
+                .unwrap_or_default(),
         })
         .collect();
 
+    // Ground the latest user turn in the project's knowledge base. Retrieval happens once
+    // per turn; continuations after a tool call reuse the persisted sources.
+    if use_knowledge_base.unwrap_or(false) {
+        if let Some(turn) = messages.iter().rposition(|m| m.role == "user") {
+            if persisted_msgs[turn].sources.is_empty() {
+                let question = messages[turn].content.split(ATTACHED_CONTEXT_DELIMITER).next().unwrap_or_default();
+                let budget = rag::context_budget(config.ctx_size, config.max_tokens);
+                match rag::retrieve_sources(&kb_state.0, question, &workspace_root, budget).await {
+                    Ok(sources) => persisted_msgs[turn].sources = sources,
+                    Err(e) => println!("[llm][chat] Knowledge base retrieval failed, answering without it: {}", e),
+                }
+            }
+            let sources = &persisted_msgs[turn].sources;
+            println!("[llm][chat] Grounding turn with {} knowledge base sources", sources.len());
+            let _ = app.emit("llm-sources", sources);
+            if !sources.is_empty() {
+                messages[turn].content = rag::build_grounded_prompt(&messages[turn].content, sources);
+            }
+        }
+    }
+
     let session = ChatSession {
         id: session_id.clone(),
         created_at: now.clone(),
@@ -73,7 +110,8 @@ pub async fn send_chat_message(
 }
 
 #[tauri::command]
-pub fn execute_llm_tool(
+pub async fn execute_llm_tool(
+    kb_state: State<'_, KbState>,
     name: String,
     args_json: String,
     workspace_root: String,
@@ -81,7 +119,14 @@ pub fn execute_llm_tool(
     println!("[llm][command] execute_llm_tool: name={}, args={}", name, args_json);
     let args: Value =
         serde_json::from_str(&args_json).map_err(|e| format!("Invalid args JSON: {}", e))?;
-    let result = tools::execute_tool(&name, &args, &workspace_root);
+    let result = if tools::kb::is_kb_tool(&name) {
+        tools::kb::execute_kb_tool(&name, &args, &kb_state.0, &workspace_root).await
+    } else {
+        // File and shell tools block; keep them off the async runtime.
+        tokio::task::spawn_blocking(move || tools::execute_tool(&name, &args, &workspace_root))
+            .await
+            .map_err(|e| format!("Tool task panicked: {}", e))?
+    };
     match &result {
         Ok(out) => println!("[llm][tool] Tool execution succeeded: {} bytes returned", out.len()),
         Err(err) => println!("[llm][tool] Tool execution failed: {}", err),
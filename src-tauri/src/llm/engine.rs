@@ -7,6 +7,7 @@ use llama_cpp_2::{
 };
 use std::ffi::c_void;
 use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex, Weak};
 
 /// Suppress ALL internal llama.cpp / ggml / Metal logs — installed before backend init.
 extern "C" fn noop_log(
@@ -55,8 +56,21 @@ impl LlamaConfig {
     }
 }
 
+/// llama.cpp allows one backend per process. The chat engine and the GGUF embedder
+/// can be loaded at the same time, so they share it; it is freed with the last engine.
+fn shared_backend() -> Result<Arc<LlamaBackend>, Box<dyn std::error::Error + Send + Sync>> {
+    static BACKEND: Mutex<Weak<LlamaBackend>> = Mutex::new(Weak::new());
+    let mut slot = BACKEND.lock().unwrap();
+    if let Some(backend) = slot.upgrade() {
+        return Ok(backend);
+    }
+    let backend = Arc::new(LlamaBackend::init()?);
+    *slot = Arc::downgrade(&backend);
+    Ok(backend)
+}
+
 pub struct LlamaEngine {
-    backend: LlamaBackend,
+    backend: Arc<LlamaBackend>,
     model: LlamaModel,
     config: LlamaConfig,
 }
@@ -72,7 +86,7 @@ impl LlamaEngine {
                 llama_cpp_sys_2::llama_log_set(Some(noop_log), std::ptr::null_mut());
             }
         }
-        let backend = LlamaBackend::init()?;
+        let backend = shared_backend()?;
         let model_params = LlamaModelParams::default().with_n_gpu_layers(config.gpu_layers);
         let model = LlamaModel::load_from_file(&backend, path, &model_params)?;
         Ok(Self {
@@ -82,6 +96,57 @@ impl LlamaEngine {
         })
     }
 
+    /// Width of the vectors returned by [`LlamaEngine::embed`].
+    pub fn n_embd(&self) -> usize {
+        self.model.n_embd() as usize
+    }
+
+    /// Extract an L2-normalised sentence embedding.
+    ///
+    /// Pooling follows the GGUF metadata (mean for nomic-embed, CLS for bge), so this
+    /// only works for embedding models; chat models report no pooled output.
+    /// Input longer than `ctx_size` tokens is truncated.
+    pub fn embed(&self, text: &str) -> Result<Vec<f32>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut embeddings = self.embed_batch(&[text])?;
+        Ok(embeddings.pop().unwrap_or_default())
+    }
+
+    /// Embed several texts with one context, as [`LlamaEngine::embed`] does for one.
+    /// Creating a context allocates the KV cache, so a document's chunks share it.
+    pub fn embed_batch<S: AsRef<str>>(
+        &self,
+        texts: &[S],
+    ) -> Result<Vec<Vec<f32>>, Box<dyn std::error::Error + Send + Sync>> {
+        let ctx_size = self.config.ctx_size;
+        // Non-causal models must see the whole input in a single ubatch.
+        let ctx_params = LlamaContextParams::default()
+            .with_n_ctx(Some(NonZeroU32::new(ctx_size).unwrap()))
+            .with_n_batch(ctx_size)
+            .with_n_ubatch(ctx_size)
+            .with_embeddings(true);
+        let mut ctx = self.model.new_context(&self.backend, ctx_params)?;
+
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for text in texts {
+            let mut tokens = self.model.str_to_token(text.as_ref(), AddBos::Always)?;
+            tokens.truncate(ctx_size as usize);
+
+            // Each text is its own sequence 0; drop the previous one's cache first.
+            ctx.clear_kv_cache();
+            let mut batch = LlamaBatch::new(tokens.len().max(1), 1);
+            batch.add_sequence(&tokens, 0, false)?;
+            ctx.decode(&mut batch)?;
+
+            let mut embedding = ctx.embeddings_seq_ith(0)?.to_vec();
+            let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                embedding.iter_mut().for_each(|v| *v /= norm);
+            }
+            embeddings.push(embedding);
+        }
+        Ok(embeddings)
+    }
+
     /// Collect full response as a String (non-streaming).
     pub fn generate(
         &self,
@@ -110,6 +110,19 @@ pub fn scan_models(app: &AppHandle) -> Result<Vec<GgufModelInfo>, String> {
     Ok(models)
 }
 
+/// Resolve a model filename to its path in the models directory.
+/// Used by the knowledge base so GGUF embedding models share this download flow.
+pub fn resolve_model_path(app: &AppHandle, filename: &str) -> Result<PathBuf, String> {
+    if !filename.ends_with(".gguf") || filename.contains(['/', '\\']) {
+        return Err(format!("Invalid GGUF model filename: {}", filename));
+    }
+    let path = get_models_dir(app)?.join(filename);
+    if !path.exists() {
+        return Err(format!("GGUF model not found: {}", filename));
+    }
+    Ok(path)
+}
+
 /// Delete a GGUF model file.
 pub fn delete_model(app: &AppHandle, filename: &str) -> Result<(), String> {
     let models_dir = get_models_dir(app)?;
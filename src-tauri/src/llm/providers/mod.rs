@@ -73,3 +73,34 @@ pub async fn ensure_engine(state: &LlmState) -> Result<Arc<LlamaEngine>, String>
     *state.engine.lock().unwrap() = Some(Arc::clone(&engine_arc));
     Ok(engine_arc)
 }
+
+/// Adapts the active provider to the knowledge base's query expansion hook
+/// (`knowledge_base::query_expansion::TextGenerator`).
+pub struct ProviderTextGenerator {
+    provider: Box<dyn LlmProvider>,
+    config: LlmConfig,
+}
+
+impl ProviderTextGenerator {
+    pub async fn new(config: LlmConfig, state: &LlmState) -> Result<Self, String> {
+        let provider = get_provider(&config, state).await?;
+        // The chat system prompt describes editor tools, which only distracts a query rewrite.
+        let config = LlmConfig { system_prompt: None, ..config };
+        Ok(Self { provider, config })
+    }
+}
+
+#[async_trait]
+impl crate::knowledge_base::query_expansion::TextGenerator for ProviderTextGenerator {
+    async fn generate(&self, prompt: &str) -> Result<String, String> {
+        self.provider.generate(prompt, &self.config).await
+    }
+
+    fn model_id(&self) -> String {
+        let model = match self.config.provider_type {
+            ProviderType::Local => self.config.local_model_path.clone(),
+            _ => self.config.model_name.clone(),
+        };
+        format!("{:?}:{}", self.config.provider_type, model.unwrap_or_default())
+    }
+}
@@ -5,6 +5,8 @@ use std::fs;
 use std::path::PathBuf;
 use uuid::Uuid;
 
+use crate::knowledge_base::rag::ChatSource;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PersistedMessage {
     pub role: String,
@@ -16,6 +18,9 @@ pub struct PersistedMessage {
     pub tool_input: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_output: Option<Value>,
+    /// Knowledge-base passages retrieved for this user turn.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<ChatSource>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,213 @@
+use serde_json::{json, Value};
+
+use super::ToolDefinition;
+use crate::knowledge_base::language::{LanguageMode, LanguagePreference};
+use crate::knowledge_base::manager::KbManager;
+
+/// Deepest graph walk the model may request; the graph fans out quickly beyond that.
+const MAX_NEIGHBOR_DEPTH: u64 = 3;
+
+/// Knowledge-base tools, mirroring what the MCP server offers external clients.
+pub fn tool_definitions() -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "kb_search".to_string(),
+            description: "Search the project knowledge base (indexed docs) with hybrid keyword and semantic search. Returns matching documents with the passages that matched.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "What to look for, in natural language or keywords."
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of documents to return (default 5)."
+                    },
+                    "language": {
+                        "type": "string",
+                        "description": "Optional ISO 639-1 code (e.g. \"en\", \"vi\"); passages in this language are ranked first."
+                    }
+                },
+                "required": ["query"]
+            }),
+        },
+        ToolDefinition {
+            name: "kb_get_document".to_string(),
+            description: "Fetch a knowledge base document's full content by id (ids are returned by kb_search).".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Document id."
+                    }
+                },
+                "required": ["id"]
+            }),
+        },
+        ToolDefinition {
+            name: "kb_get_chunk_context".to_string(),
+            description: "Read a passage of a knowledge base document together with the passages around it.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "document_id": {
+                        "type": "string",
+                        "description": "Document id."
+                    },
+                    "chunk_index": {
+                        "type": "integer",
+                        "description": "Zero-based index of the passage within the document (default 0)."
+                    },
+                    "window": {
+                        "type": "integer",
+                        "description": "Number of passages to include before and after (default 1)."
+                    }
+                },
+                "required": ["document_id"]
+            }),
+        },
+        ToolDefinition {
+            name: "kb_backlinks".to_string(),
+            description: "List knowledge base documents that link to the given document or its sections.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Document id."
+                    }
+                },
+                "required": ["id"]
+            }),
+        },
+        ToolDefinition {
+            name: "kb_graph_neighbors".to_string(),
+            description: "Explore the knowledge graph around a document: linked documents in both directions and the edges between them.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Document id to start from."
+                    },
+                    "depth": {
+                        "type": "integer",
+                        "description": "How many links away to go, 1 to 3 (default 1)."
+                    }
+                },
+                "required": ["id"]
+            }),
+        },
+    ]
+}
+
+pub fn is_kb_tool(name: &str) -> bool {
+    name.starts_with("kb_")
+}
+
+/// Fail with "not found" unless document `id` belongs to the workspace's project group,
+/// so tools taking an id cannot read other workspaces' documents.
+async fn ensure_in_workspace(kb: &KbManager, workspace_root: &str, id: &str) -> Result<(), String> {
+    let found = kb
+        .documents_in_group(workspace_root.to_string(), vec![id.to_string()])
+        .await?;
+    if found.is_empty() {
+        return Err(format!("Document not found: {}", id));
+    }
+    Ok(())
+}
+
+/// Execute a knowledge base tool. Every tool is scoped to the workspace's project group:
+/// searches only match its documents, and tools taking an id only accept and return them.
+pub async fn execute_kb_tool(
+    name: &str,
+    args: &Value,
+    kb: &KbManager,
+    workspace_root: &str,
+) -> Result<String, String> {
+    match name {
+        "kb_search" => {
+            let query = args["query"]
+                .as_str()
+                .ok_or("Missing 'query' argument")?;
+            let limit = args["limit"].as_u64().unwrap_or(5) as usize;
+            let language = args["language"]
+                .as_str()
+                .and_then(|code| LanguagePreference::new(code, LanguageMode::Boost));
+            let results = kb
+                .search_hybrid_with_language(
+                    query.to_string(),
+                    limit,
+                    Some(vec![workspace_root.to_string()]),
+                    language,
+                )
+                .await?;
+            Ok(json!({ "results": results }).to_string())
+        }
+
+        "kb_get_document" => {
+            let id = args["id"]
+                .as_str()
+                .ok_or("Missing 'id' argument")?;
+            ensure_in_workspace(kb, workspace_root, id).await?;
+            match kb.get_document(id.to_string()).await? {
+                Some(document) => Ok(json!({ "document": document }).to_string()),
+                None => Err(format!("Document not found: {}", id)),
+            }
+        }
+
+        "kb_get_chunk_context" => {
+            let document_id = args["document_id"]
+                .as_str()
+                .ok_or("Missing 'document_id' argument")?;
+            let chunk_index = args["chunk_index"].as_u64().unwrap_or(0);
+            let window = args["window"].as_u64().unwrap_or(1) as usize;
+            ensure_in_workspace(kb, workspace_root, document_id).await?;
+            let chunks = kb
+                .get_chunk_context(format!("{}#{}", document_id, chunk_index), window)
+                .await?;
+            Ok(json!({ "chunks": chunks }).to_string())
+        }
+
+        "kb_backlinks" => {
+            let id = args["id"]
+                .as_str()
+                .ok_or("Missing 'id' argument")?;
+            ensure_in_workspace(kb, workspace_root, id).await?;
+            let mut backlinks = kb.get_backlinks(id.to_string()).await?;
+            let in_workspace = kb
+                .documents_in_group(
+                    workspace_root.to_string(),
+                    backlinks.iter().map(|link| link.id.clone()).collect(),
+                )
+                .await?;
+            backlinks.retain(|link| in_workspace.contains(&link.id));
+            Ok(json!({ "backlinks": backlinks }).to_string())
+        }
+
+        "kb_graph_neighbors" => {
+            let id = args["id"]
+                .as_str()
+                .ok_or("Missing 'id' argument")?;
+            let depth = args["depth"].as_u64().unwrap_or(1).clamp(1, MAX_NEIGHBOR_DEPTH) as usize;
+            ensure_in_workspace(kb, workspace_root, id).await?;
+            let mut neighborhood = kb.get_graph_neighbors(id.to_string(), depth).await?;
+            let mut in_workspace = kb
+                .documents_in_group(
+                    workspace_root.to_string(),
+                    neighborhood.documents.iter().map(|document| document.id.clone()).collect(),
+                )
+                .await?;
+            in_workspace.insert(neighborhood.root_id.clone());
+            neighborhood.documents.retain(|document| in_workspace.contains(&document.id));
+            neighborhood
+                .edges
+                .retain(|edge| in_workspace.contains(&edge.source_id) && in_workspace.contains(&edge.target_id));
+            serde_json::to_string(&neighborhood).map_err(|e| e.to_string())
+        }
+
+        _ => Err(format!("Unknown tool: {}", name)),
+    }
+}
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 pub mod fs;
+pub mod kb;
 pub mod shell;
 pub mod yahoo;
 
@@ -14,7 +15,7 @@ pub struct ToolDefinition {
 
 /// Returns the standard tool definitions sent to every LLM provider.
 pub fn get_tool_definitions() -> Vec<ToolDefinition> {
-    vec![
+    let mut definitions = vec![
         ToolDefinition {
             name: "read_file".to_string(),
             description: "Read the contents of a file at the given path.".to_string(),
@@ -89,11 +90,14 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                 "required": ["query"]
             }),
         },
-    ]
+    ];
+    definitions.extend(kb::tool_definitions());
+    definitions
 }
 
 /// Execute a tool call given its name and arguments.
 /// Returns a JSON string result or an error string.
+/// Knowledge base tools are async and dispatched through `kb::execute_kb_tool` instead.
 pub fn execute_tool(
     name: &str,
     args: &Value,
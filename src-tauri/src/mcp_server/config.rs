@@ -2,10 +2,12 @@ use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use rmcp::model::ProtocolVersion;
 use serde::Deserialize;
 
+use crate::llm::LlmConfig;
+
 #[derive(Debug, Clone, Default, Deserialize)]
-#[allow(dead_code)]
 pub struct FileConfig {
     #[serde(default)]
     pub database_path: Option<PathBuf>,
@@ -21,14 +23,100 @@ pub struct FileConfig {
     pub server_name: Option<String>,
     #[serde(default)]
     pub server_version: Option<String>,
+    #[serde(default)]
+    pub transport: Option<McpTransport>,
+    #[serde(default)]
+    pub http_port: Option<u16>,
+    #[serde(default)]
+    pub http_token: Option<String>,
+    #[serde(default)]
+    pub watch: Vec<PathBuf>,
+    #[serde(default)]
+    pub instructions: Option<String>,
+}
+
+/// How clients reach the server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpTransport {
+    /// One client per process, speaking JSON-RPC over stdin/stdout.
+    #[default]
+    Stdio,
+    /// Streamable HTTP (with SSE streams) on localhost, shared by any number of clients.
+    Http,
+}
+
+impl McpTransport {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "stdio" => Some(Self::Stdio),
+            "http" => Some(Self::Http),
+            _ => None,
+        }
+    }
+}
+
+/// Protocol versions this server can speak, newest first.
+const SUPPORTED_PROTOCOL_VERSIONS: &[ProtocolVersion] = &[
+    ProtocolVersion::V_2025_06_18,
+    ProtocolVersion::V_2025_03_26,
+    ProtocolVersion::V_2024_11_05,
+];
+
+/// Versions offered when none are configured. 2025-06-18 is opt-in until rmcp makes
+/// it its default.
+const DEFAULT_PROTOCOL_VERSIONS: &[ProtocolVersion] = &[
+    ProtocolVersion::V_2025_03_26,
+    ProtocolVersion::V_2024_11_05,
+];
+
+/// Parse a comma-separated list such as `2025-03-26,2024-11-05`, newest first.
+fn parse_protocol_versions(value: &str) -> Result<Vec<ProtocolVersion>, String> {
+    let mut versions = Vec::new();
+    for version in value.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+        let known = SUPPORTED_PROTOCOL_VERSIONS
+            .iter()
+            .find(|known| known.to_string() == version)
+            .ok_or_else(|| {
+                let supported: Vec<_> = SUPPORTED_PROTOCOL_VERSIONS.iter().map(|v| v.to_string()).collect();
+                format!("Unsupported MCP protocol version '{version}': expected one of {}", supported.join(", "))
+            })?;
+        if !versions.contains(known) {
+            versions.push(known.clone());
+        }
+    }
+    if versions.is_empty() {
+        return Err("No MCP protocol version given".to_string());
+    }
+    versions.sort_by_key(|v| std::cmp::Reverse(v.to_string()));
+    Ok(versions)
 }
 
+/// Port the HTTP transport listens on when none is configured.
+pub const DEFAULT_HTTP_PORT: u16 = 7337;
+
 #[derive(Debug, Clone)]
 pub struct McpServerConfig {
     pub database_path: PathBuf,
     pub cache_dir: PathBuf,
     pub server_name: String,
     pub server_version: String,
+    /// Registers the write tools (`kb_upsert_document`, `kb_delete_document`, ...).
+    pub write_enabled: bool,
+    /// Group that reads are scoped to, and that written documents join, when a request
+    /// names none.
+    pub default_group_id: Option<String>,
+    pub transport: McpTransport,
+    /// Localhost port for the HTTP transport.
+    pub http_port: u16,
+    /// Bearer token HTTP clients must send; the HTTP transport refuses to start without one.
+    pub http_token: Option<String>,
+    /// Workspace roots whose markdown files are kept indexed while the server runs.
+    pub watch: Vec<PathBuf>,
+    /// Protocol versions the server accepts, newest first.
+    pub protocol_versions: Vec<ProtocolVersion>,
+    /// Extra notes appended to the instructions sent to clients on initialize.
+    pub instructions: Option<String>,
 }
 
 impl McpServerConfig {
@@ -64,13 +152,100 @@ impl McpServerConfig {
             .or_else(|| env::var("DEPDOK_MCP_SERVER_VERSION").ok())
             .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
 
+        let write_enabled = cli
+            .write_enabled
+            .or_else(|| env::var("DEPDOK_MCP_WRITE_ENABLED").ok().map(|v| matches!(v.as_str(), "1" | "true")))
+            .or(file_config.write_enabled)
+            .unwrap_or(false);
+
+        let default_group_id = cli
+            .default_group_id
+            .or_else(|| env::var("DEPDOK_MCP_DEFAULT_GROUP_ID").ok())
+            .or(file_config.default_group_id)
+            .filter(|id| !id.trim().is_empty())
+            .map(resolve_group_id);
+
+        let transport = match cli.transport.or_else(|| env::var("DEPDOK_MCP_TRANSPORT").ok()) {
+            Some(value) => McpTransport::parse(&value)
+                .ok_or_else(|| format!("Unknown MCP transport '{value}': expected 'stdio' or 'http'"))?,
+            None => file_config.transport.unwrap_or_default(),
+        };
+
+        let http_port = match cli.http_port.or_else(|| env::var("DEPDOK_MCP_PORT").ok()) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("Invalid MCP HTTP port '{value}'"))?,
+            None => file_config.http_port.unwrap_or(DEFAULT_HTTP_PORT),
+        };
+
+        let http_token = cli
+            .http_token
+            .or_else(|| env::var("DEPDOK_MCP_TOKEN").ok())
+            .or(file_config.http_token)
+            .filter(|token| !token.trim().is_empty());
+
+        let watch = if !cli.watch.is_empty() {
+            cli.watch
+        } else if let Some(paths) = env::var_os("DEPDOK_MCP_WATCH") {
+            env::split_paths(&paths).filter(|p| !p.as_os_str().is_empty()).collect()
+        } else {
+            file_config.watch
+        };
+
+        let protocol_versions = match cli
+            .protocol_version
+            .or_else(|| env::var("DEPDOK_MCP_PROTOCOL_VERSION").ok())
+            .or(file_config.protocol_version)
+        {
+            Some(value) => parse_protocol_versions(&value)?,
+            None => DEFAULT_PROTOCOL_VERSIONS.to_vec(),
+        };
+
+        let instructions = cli
+            .instructions
+            .or_else(|| env::var("DEPDOK_MCP_INSTRUCTIONS").ok())
+            .or(file_config.instructions)
+            .filter(|notes| !notes.trim().is_empty());
+
         Ok(Self {
             database_path,
             cache_dir,
             server_name,
             server_version,
+            write_enabled,
+            default_group_id,
+            transport,
+            http_port,
+            http_token,
+            watch,
+            protocol_versions,
+            instructions,
         })
     }
+
+    /// The version to answer a client's `initialize` with: the newest accepted version
+    /// that the client also speaks. A client that only speaks older versions gets the
+    /// newest accepted one, as the MCP spec asks, and decides itself whether to disconnect.
+    pub fn negotiate_protocol_version(&self, requested: &ProtocolVersion) -> ProtocolVersion {
+        self.protocol_versions
+            .iter()
+            .find(|version| version.to_string() <= requested.to_string())
+            .unwrap_or(&self.protocol_versions[0])
+            .clone()
+    }
+
+    /// The desktop app's LLM settings, read from the `store.json` that sits next to the
+    /// database in the app data directory. Falls back to the defaults when the app has
+    /// never saved a configuration.
+    pub fn load_llm_config(&self) -> LlmConfig {
+        let store_path = self.database_path.with_file_name("store.json");
+        fs::read_to_string(&store_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            .and_then(|mut store| store.get_mut("llm_config").map(serde_json::Value::take))
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Default)]
@@ -81,6 +256,11 @@ struct CliArgs {
     write_enabled: Option<bool>,
     default_group_id: Option<String>,
     protocol_version: Option<String>,
+    transport: Option<String>,
+    http_port: Option<String>,
+    http_token: Option<String>,
+    watch: Vec<PathBuf>,
+    instructions: Option<String>,
 }
 
 impl CliArgs {
@@ -95,6 +275,11 @@ impl CliArgs {
                 "--cache-dir" => cli.cache_dir = args.next().map(PathBuf::from),
                 "--default-group-id" => cli.default_group_id = args.next(),
                 "--protocol-version" => cli.protocol_version = args.next(),
+                "--transport" => cli.transport = args.next(),
+                "--port" => cli.http_port = args.next(),
+                "--token" => cli.http_token = args.next(),
+                "--watch" => cli.watch.extend(args.next().map(PathBuf::from)),
+                "--instructions" => cli.instructions = args.next(),
                 "--write-enabled" => cli.write_enabled = Some(true),
                 "--read-only" => cli.write_enabled = Some(false),
                 _ => {}
@@ -105,6 +290,57 @@ impl CliArgs {
     }
 }
 
+/// Workspace group ids are absolute paths. A relative one such as `.` names the
+/// directory the client started the server in, so a project-local client config can
+/// scope the server to its own repository without spelling out the path.
+fn resolve_group_id(group_id: String) -> String {
+    let is_relative_path = group_id == "."
+        || group_id == ".."
+        || group_id.starts_with("./")
+        || group_id.starts_with("../");
+    if !is_relative_path {
+        return group_id;
+    }
+    absolute_path(Path::new(&group_id)).to_string_lossy().to_string()
+}
+
+/// `path` made absolute against the start directory, with `.` and `..` resolved without
+/// touching the disk. Workspace groups are keyed by the path a folder was opened under,
+/// so symlinks are kept rather than resolved as `canonicalize` would.
+pub(crate) fn absolute_path(path: &Path) -> PathBuf {
+    let base = match path.is_absolute() {
+        true => PathBuf::new(),
+        false => working_dir().unwrap_or_default(),
+    };
+    join_lexically(&base, path)
+}
+
+/// The start directory as the shell spelled it. `current_dir` resolves symlinks, but
+/// workspace groups keep the path the folder was opened under, so `$PWD` is preferred
+/// while it still names the same directory.
+fn working_dir() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    let logical = env::var_os("PWD")
+        .map(PathBuf::from)
+        .filter(|pwd| pwd.is_absolute() && pwd.canonicalize().ok() == cwd.canonicalize().ok());
+    Some(logical.unwrap_or(cwd))
+}
+
+/// `base` joined with `relative`, with `.` and `..` resolved without touching the disk.
+fn join_lexically(base: &Path, relative: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in base.join(relative).components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
 fn read_file_config(path: &Path) -> Result<FileConfig, String> {
     let content = fs::read_to_string(path)
         .map_err(|e| format!("Failed to read MCP config file {}: {e}", path.display()))?;
@@ -148,4 +384,50 @@ fn default_data_dir() -> PathBuf {
             .unwrap_or_else(|| PathBuf::from("."))
             .join(".local/share/com.depdok.app")
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocol_versions_are_validated_and_sorted_newest_first() {
+        assert_eq!(
+            parse_protocol_versions("2024-11-05, 2025-06-18,2024-11-05").unwrap(),
+            vec![ProtocolVersion::V_2025_06_18, ProtocolVersion::V_2024_11_05]
+        );
+        assert!(parse_protocol_versions("2023-01-01").is_err());
+        assert!(parse_protocol_versions(" , ").is_err());
+    }
+
+    #[test]
+    fn negotiation_falls_back_to_the_newest_accepted_version() {
+        let config = McpServerConfig {
+            database_path: PathBuf::new(),
+            cache_dir: PathBuf::new(),
+            server_name: "depdok".to_string(),
+            server_version: "0.0.0".to_string(),
+            write_enabled: false,
+            default_group_id: None,
+            transport: McpTransport::default(),
+            http_port: DEFAULT_HTTP_PORT,
+            http_token: None,
+            watch: Vec::new(),
+            protocol_versions: vec![ProtocolVersion::V_2025_03_26, ProtocolVersion::V_2024_11_05],
+            instructions: None,
+        };
+        assert_eq!(config.negotiate_protocol_version(&ProtocolVersion::V_2025_06_18), ProtocolVersion::V_2025_03_26);
+        assert_eq!(config.negotiate_protocol_version(&ProtocolVersion::V_2024_11_05), ProtocolVersion::V_2024_11_05);
+        let ancient: ProtocolVersion = serde_json::from_str("\"2023-01-01\"").unwrap();
+        assert_eq!(config.negotiate_protocol_version(&ancient), ProtocolVersion::V_2025_03_26);
+    }
+
+    #[test]
+    fn relative_group_ids_keep_the_spelled_path() {
+        let cwd = Path::new("/ws/linked-repo");
+        assert_eq!(join_lexically(cwd, Path::new(".")), PathBuf::from("/ws/linked-repo"));
+        assert_eq!(join_lexically(cwd, Path::new("./docs/../api")), PathBuf::from("/ws/linked-repo/api"));
+        assert_eq!(join_lexically(cwd, Path::new("..")), PathBuf::from("/ws"));
+        assert_eq!(absolute_path(Path::new("/ws/./repo/../other")), PathBuf::from("/ws/other"));
+        assert_eq!(resolve_group_id("/abs/group".to_string()), "/abs/group");
+    }
+}
@@ -0,0 +1,88 @@
+// Structured MCP errors for failures a client can act on. Each carries its own JSON-RPC
+// code (from the range reserved for implementations) and a `data.reason`, so an agent
+// can tell "download the model" from "retry shortly" without parsing the message.
+
+use rmcp::{model::ErrorCode, ErrorData as McpError};
+use serde_json::json;
+
+/// No embedding model is available: it was never downloaded, or failed to load.
+pub const MODEL_NOT_DOWNLOADED: ErrorCode = ErrorCode(-32010);
+
+/// The vector index does not match the loaded embedding model and must be rebuilt.
+pub const INDEX_STALE: ErrorCode = ErrorCode(-32011);
+
+/// Another process holds the database lock; the request can be retried.
+pub const DATABASE_LOCKED: ErrorCode = ErrorCode(-32012);
+
+pub fn model_not_downloaded(detail: impl Into<String>) -> McpError {
+    let detail = detail.into();
+    McpError::new(
+        MODEL_NOT_DOWNLOADED,
+        format!("The embedding model is not available: {detail}"),
+        Some(json!({
+            "reason": "model_not_downloaded",
+            "retryable": false,
+            "hint": "Open the Depdok app once with network access to download the model, or check cache_dir.",
+            "detail": detail,
+        })),
+    )
+}
+
+pub fn index_stale(detail: impl Into<String>) -> McpError {
+    let detail = detail.into();
+    McpError::new(
+        INDEX_STALE,
+        format!("The search index does not match the embedding model: {detail}"),
+        Some(json!({
+            "reason": "index_stale",
+            "retryable": false,
+            "hint": "Re-embed the knowledge base from the Depdok app with the same model as this server, or call kb_reindex.",
+            "detail": detail,
+        })),
+    )
+}
+
+pub fn database_locked(detail: impl Into<String>) -> McpError {
+    let detail = detail.into();
+    McpError::new(
+        DATABASE_LOCKED,
+        format!("The knowledge base is locked by another process: {detail}"),
+        Some(json!({
+            "reason": "database_locked",
+            "retryable": true,
+            "detail": detail,
+        })),
+    )
+}
+
+/// Map a knowledge base error onto a structured error when its cause is recognizable,
+/// otherwise onto an internal error carrying the message.
+pub fn kb_error(message: String) -> McpError {
+    let lower = message.to_lowercase();
+    if lower.contains("database is locked") || lower.contains("database is busy") || lower.contains("database table is locked") {
+        database_locked(message)
+    } else if lower.contains("no embedding model is downloaded") || lower.contains("failed to initialise fastembed model") {
+        model_not_downloaded(message)
+    } else if lower.contains("dimension mismatch") || lower.contains("no such table: documents_embeddings") {
+        index_stale(message)
+    } else {
+        McpError::internal_error(message, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_failures_get_their_own_codes() {
+        let code = |message: &str| kb_error(message.to_string()).code;
+        assert_eq!(code("Failed to upsert: database is locked"), DATABASE_LOCKED);
+        assert_eq!(code("No embedding model is downloaded. Please download/select one in Settings first."), MODEL_NOT_DOWNLOADED);
+        assert_eq!(
+            code("KNN query failed: Dimension mismatch for query vector for the \"embedding\" column. Expected 768 dimensions but received 384."),
+            INDEX_STALE
+        );
+        assert_eq!(code("Search query failed: disk I/O error"), ErrorCode::INTERNAL_ERROR);
+    }
+}
@@ -0,0 +1,81 @@
+// Streamable HTTP transport: one long-running server on localhost that any number of
+// agents and editors can connect to, sharing a single database handle and embedding
+// model instead of each spawning its own stdio process. Responses stream over SSE.
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use rmcp::transport::streamable_http_server::{
+    session::local::LocalSessionManager, StreamableHttpServerConfig, StreamableHttpService,
+};
+
+use super::KbMcpService;
+
+/// Path the MCP endpoint is mounted at, e.g. `http://127.0.0.1:7337/mcp`.
+const MCP_PATH: &str = "/mcp";
+
+pub async fn serve(service: KbMcpService) -> Result<(), String> {
+    let port = service.config.http_port;
+    // Anything on this machine (including web pages) can reach a localhost port, so the
+    // token is required rather than optional.
+    let token = service.config.http_token.clone().ok_or_else(|| {
+        "The HTTP transport requires a bearer token: set --token, DEPDOK_MCP_TOKEN or http_token".to_string()
+    })?;
+
+    let mcp = StreamableHttpService::new(
+        move || Ok(service.for_session()),
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig::default(),
+    );
+    let app = Router::new()
+        .nest_service(MCP_PATH, mcp)
+        .layer(middleware::from_fn_with_state(Arc::new(token), require_bearer));
+
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("Failed to bind MCP HTTP server to {addr}: {e}"))?;
+    eprintln!("depdok MCP server listening on http://{addr}{MCP_PATH}");
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .map_err(|e| format!("MCP HTTP server failed: {e}"))
+}
+
+async fn require_bearer(State(token): State<Arc<String>>, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|presented| tokens_match(presented.trim(), &token));
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "Missing or invalid bearer token",
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Compares in time independent of where the first mismatch is.
+fn tokens_match(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
@@ -0,0 +1,98 @@
+// Usage instructions sent to clients on `initialize`. They describe what is indexed
+// (workspaces, embedding model) and which tool fits which question, so the client
+// model picks the right tool without trial and error.
+
+use std::fmt::Write;
+
+use super::model::ModelStatus;
+use super::service::{KbMcpService, ALL_GROUPS};
+
+/// Workspaces listed by name; the rest are summarized as a count.
+const MAX_LISTED_WORKSPACES: usize = 20;
+
+impl KbMcpService {
+    pub(crate) async fn instructions(&self) -> String {
+        let mut text = String::from(
+            "Depdok knowledge base: the user's project documentation (markdown files, notes, decision records), \
+             indexed for keyword and semantic search.\n\n\
+             Choosing a tool:\n\
+             - kb_search answers questions about the docs (hybrid keyword + semantic search); start here.\n\
+             - kb_search_similar finds passages by meaning only, when wording varies.\n\
+             - kb_get_document reads a whole document or one section; kb_get_chunk_context widens a search hit.\n\
+             - kb_list_groups, kb_get_project_graph and kb_list_tags show how the docs are organized and linked.\n\
+             - kb_get_changes and kb_recent_documents report what changed.\n",
+        );
+
+        if self.config.write_enabled {
+            text.push_str(
+                "- kb_upsert_document, kb_delete_document, kb_connect and kb_disconnect record notes and links. \
+                 Files (ids starting with file:) are read-only; edit the file instead.\n\
+                 - kb_reindex re-indexes a workspace folder after its files changed on disk.\n",
+            );
+        } else {
+            text.push_str("\nThis server is read-only.\n");
+        }
+
+        match &self.config.default_group_id {
+            Some(group_id) => {
+                let _ = write!(
+                    text,
+                    "\nSearches are limited to the group {group_id} and its subgroups. Pass group_id to search \
+                     another group, or group_id \"{ALL_GROUPS}\" to search every group.\n"
+                );
+            }
+            None => text.push_str("\nSearches cover every group unless a group_id is given.\n"),
+        }
+
+        match self.kb_manager.list_groups().await {
+            Ok(groups) => {
+                let workspaces: Vec<_> = groups.iter().filter(|g| g.parent_id.is_none()).collect();
+                if workspaces.is_empty() {
+                    text.push_str("\nNothing has been indexed yet.\n");
+                } else {
+                    text.push_str("\nIndexed workspaces (group ids):\n");
+                    for workspace in workspaces.iter().take(MAX_LISTED_WORKSPACES) {
+                        let _ = writeln!(text, "- {} ({})", workspace.id, workspace.title);
+                    }
+                    if workspaces.len() > MAX_LISTED_WORKSPACES {
+                        let _ = writeln!(
+                            text,
+                            "- … and {} more; call kb_list_groups for the full list.",
+                            workspaces.len() - MAX_LISTED_WORKSPACES
+                        );
+                    }
+                }
+            }
+            Err(e) => eprintln!("failed to list groups for instructions: {e}"),
+        }
+
+        let model_status = self.model_status.borrow().clone();
+        match model_status {
+            ModelStatus::Ready => {
+                let embedder = self.kb_manager.embedder_lock();
+                let embedder = embedder.read().await;
+                let _ = writeln!(
+                    text,
+                    "\nEmbedding model: {} ({} dimensions). Semantic search works best with queries phrased \
+                     like the docs themselves.",
+                    embedder.name(),
+                    embedder.dimensions()
+                );
+            }
+            ModelStatus::Loading => text.push_str(
+                "\nThe embedding model is still loading. Searches wait for it and report progress; other \
+                 tools work right away.\n",
+            ),
+            ModelStatus::Unavailable(_) => text.push_str(
+                "\nNo embedding model is available, so search tools fail with model_not_downloaded. \
+                 Documents can still be read by id.\n",
+            ),
+        }
+
+        if let Some(notes) = &self.config.instructions {
+            let _ = write!(text, "\n{}\n", notes.trim());
+        }
+
+        text
+    }
+}
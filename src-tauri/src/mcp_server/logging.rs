@@ -0,0 +1,91 @@
+// Server-side events (model loading, `--watch` indexing) that are written
+// to stderr and also forwarded to every connected client as MCP logging messages,
+// filtered by the level each client set with `logging/setLevel`.
+
+use std::sync::{Arc, RwLock};
+
+use rmcp::{model::*, Peer, RoleServer};
+use serde_json::json;
+use tokio::sync::broadcast;
+
+/// Events buffered for a client that is slow to receive them; older ones are dropped.
+const LOG_BUFFER: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct LogEvent {
+    pub level: LoggingLevel,
+    pub logger: &'static str,
+    pub message: String,
+}
+
+#[derive(Clone)]
+pub struct ServerLog {
+    tx: broadcast::Sender<LogEvent>,
+}
+
+impl Default for ServerLog {
+    fn default() -> Self {
+        Self { tx: broadcast::channel(LOG_BUFFER).0 }
+    }
+}
+
+impl ServerLog {
+    pub fn log(&self, level: LoggingLevel, logger: &'static str, message: impl Into<String>) {
+        let message = message.into();
+        eprintln!("[{logger}] {message}");
+        // No receivers just means no client is connected yet.
+        let _ = self.tx.send(LogEvent { level, logger, message });
+    }
+
+    pub fn info(&self, logger: &'static str, message: impl Into<String>) {
+        self.log(LoggingLevel::Info, logger, message);
+    }
+
+    pub fn warning(&self, logger: &'static str, message: impl Into<String>) {
+        self.log(LoggingLevel::Warning, logger, message);
+    }
+
+    pub fn error(&self, logger: &'static str, message: impl Into<String>) {
+        self.log(LoggingLevel::Error, logger, message);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LogEvent> {
+        self.tx.subscribe()
+    }
+}
+
+/// Whether a message at `level` passes a client's minimum level.
+pub fn enabled(level: LoggingLevel, minimum: LoggingLevel) -> bool {
+    level as u8 >= minimum as u8
+}
+
+/// Send one message to a client if its level allows. Returns `false` once the client is gone.
+pub async fn send(peer: &Peer<RoleServer>, minimum: &RwLock<LoggingLevel>, event: LogEvent) -> bool {
+    let minimum = minimum.read().map(|level| *level).unwrap_or(LoggingLevel::Info);
+    if !enabled(event.level, minimum) {
+        return true;
+    }
+    peer.notify_logging_message(LoggingMessageNotificationParam {
+        level: event.level,
+        logger: Some(event.logger.to_string()),
+        data: json!(event.message),
+    })
+    .await
+    .is_ok()
+}
+
+/// Forward server events to one client until it disconnects.
+pub async fn forward(log: ServerLog, peer: Peer<RoleServer>, minimum: Arc<RwLock<LoggingLevel>>) {
+    let mut events = log.subscribe();
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                if !send(&peer, &minimum, event).await {
+                    return;
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
@@ -1,13 +1,26 @@
 mod config;
+mod errors;
+mod http;
+mod instructions;
+mod logging;
+mod model;
+mod progress;
+mod prompts;
+mod resources;
 mod service;
 mod tools;
+mod watch;
 
 use std::path::Path;
 
-use crate::knowledge_base::db::init_database_at_path;
+use crate::knowledge_base::db::{init_database_at_path, is_corruption_error, open_read_connections, READ_POOL_SIZE};
 use crate::knowledge_base::embedding;
+use crate::knowledge_base::lease;
 use crate::knowledge_base::manager::KbManager;
 
+use config::McpTransport;
+use logging::ServerLog;
+
 pub use config::McpServerConfig;
 pub use service::KbMcpService;
 
@@ -15,13 +28,38 @@ pub fn run() -> Result<(), String> {
     let rt = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
     rt.block_on(async {
         let config = McpServerConfig::load()?;
+        let log = ServerLog::default();
         let kb_manager = build_state(&config)?;
-        let service = KbMcpService::new(config, kb_manager);
-        
-        use rmcp::{transport::stdio, ServiceExt};
-        let running = service.serve(stdio()).await.map_err(|e| e.to_string())?;
-        running.waiting().await.map_err(|e| e.to_string())?;
-        Ok(())
+        let model_status = model::load_in_background(kb_manager.clone(), config.cache_dir.clone(), log.clone());
+        // The watcher and `kb_reindex` index under one holder, so they never wait on each other.
+        let indexer_holder = lease::holder_id("mcp");
+        let watcher = match config.watch.is_empty() {
+            true => None,
+            false => Some(watch::spawn(
+                kb_manager.clone(),
+                &config.watch,
+                indexer_holder.clone(),
+                model_status.clone(),
+                log.clone(),
+            )?),
+        };
+        let transport = config.transport;
+        let service = KbMcpService::new(config, kb_manager, indexer_holder, model_status, log);
+
+        let served = match transport {
+            McpTransport::Stdio => {
+                use rmcp::{transport::stdio, ServiceExt};
+                match service.serve(stdio()).await {
+                    Ok(running) => running.waiting().await.map(|_| ()).map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            McpTransport::Http => http::serve(service).await,
+        };
+        if let Some(watcher) = watcher {
+            watcher.release().await;
+        }
+        served
     })
 }
 
@@ -34,10 +72,21 @@ fn build_state(config: &McpServerConfig) -> Result<KbManager, String> {
     std::fs::create_dir_all(&config.cache_dir)
         .map_err(|e| format!("Failed to create MCP cache directory: {e}"))?;
 
-    let embedder = embedding::init_embedder(Some(config.cache_dir.clone()))?;
+    // The model itself loads in the background (see `model`). The placeholder has the
+    // default model's dimensions.
+    let embedder: Box<dyn embedding::Embedder> = Box::new(embedding::DummyEmbedder);
     let dims = embedder.dimensions();
-    let conn = init_database_at_path(Path::new(&config.database_path), dims)?;
-    let kb_manager = KbManager::new(conn, embedder);
+    let db_path = Path::new(&config.database_path);
+    // Recovering a corrupt file moves it, which only the desktop app may do.
+    let conn = init_database_at_path(db_path, dims).map_err(|e| {
+        if is_corruption_error(&e) {
+            format!("{e}. Open the desktop app to recover the knowledge base.")
+        } else {
+            e
+        }
+    })?;
+    let readers = open_read_connections(db_path, READ_POOL_SIZE)?;
+    let kb_manager = KbManager::new(conn, embedder).with_readers(readers);
 
     Ok(kb_manager)
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+// Loading the embedding model takes a while, and downloads it on first use. The server
+// starts with a placeholder embedder and swaps the model in once it is loaded; requests
+// that need embeddings wait for it and report progress in the meantime.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rmcp::ErrorData as McpError;
+use tokio::sync::watch;
+
+use super::errors;
+use super::logging::ServerLog;
+use super::progress::Progress;
+use crate::knowledge_base::embedding;
+use crate::knowledge_base::manager::KbManager;
+
+#[derive(Debug, Clone)]
+pub enum ModelStatus {
+    Loading,
+    Ready,
+    Unavailable(String),
+}
+
+/// Load the default embedding model off the async runtime and install it into `kb_manager`.
+pub fn load_in_background(kb_manager: KbManager, cache_dir: PathBuf, log: ServerLog) -> watch::Receiver<ModelStatus> {
+    let (tx, rx) = watch::channel(ModelStatus::Loading);
+    tokio::spawn(async move {
+        log.info("model", "loading the embedding model");
+        let loaded = tokio::task::spawn_blocking(move || embedding::init_embedder(Some(cache_dir)))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+        match loaded {
+            Ok(embedder) => {
+                let name = embedder.name();
+                *kb_manager.embedder_lock().write().await = embedder;
+                log.info("model", format!("embedding model {name} is ready"));
+                let _ = tx.send(ModelStatus::Ready);
+            }
+            Err(e) => {
+                log.error("model", format!("embedding model unavailable: {e}"));
+                let _ = tx.send(ModelStatus::Unavailable(e));
+            }
+        }
+    });
+    rx
+}
+
+/// Wait until the model is loaded, reporting progress about once a second while it loads.
+pub async fn wait_until_ready(status: &mut watch::Receiver<ModelStatus>, progress: &Progress) -> Result<(), McpError> {
+    loop {
+        match &*status.borrow_and_update() {
+            ModelStatus::Ready => return Ok(()),
+            ModelStatus::Unavailable(e) => return Err(errors::model_not_downloaded(e.clone())),
+            ModelStatus::Loading => {}
+        }
+        progress.step("Loading the embedding model").await;
+        let _ = tokio::time::timeout(Duration::from_secs(1), status.changed()).await;
+    }
+}
@@ -0,0 +1,73 @@
+// Progress notifications for long-running requests. A client opts in by sending a
+// `progressToken` in the request's `_meta`; without one, reports are dropped.
+
+use std::sync::Mutex;
+
+use rmcp::{model::*, Peer, RoleServer};
+
+pub struct Progress {
+    target: Option<(Peer<RoleServer>, ProgressToken)>,
+    sent: Mutex<Sent>,
+}
+
+/// MCP requires the progress value to grow with every notification, so counted work
+/// continues from the steps reported before it.
+#[derive(Default)]
+struct Sent {
+    last: f64,
+    counted_from: Option<f64>,
+}
+
+impl Progress {
+    pub fn new(peer: Peer<RoleServer>, meta: &Meta) -> Self {
+        Self {
+            target: meta.get_progress_token().map(|token| (peer, token)),
+            sent: Mutex::default(),
+        }
+    }
+
+    /// For work started outside a request, such as `--watch` indexing.
+    pub fn none() -> Self {
+        Self { target: None, sent: Mutex::default() }
+    }
+
+    /// Report a step of open-ended work, such as waiting for the model to load.
+    pub async fn step(&self, message: impl Into<String>) {
+        let value = match self.sent.lock() {
+            Ok(mut sent) => {
+                sent.last += 1.0;
+                sent.last
+            }
+            Err(_) => return,
+        };
+        self.send(value, None, message.into()).await;
+    }
+
+    /// Report that `done` (at least 1) of `total` units of counted work are finished.
+    pub async fn report(&self, done: usize, total: usize, message: impl Into<String>) {
+        let (value, total) = match self.sent.lock() {
+            Ok(mut sent) => {
+                let last = sent.last;
+                let start = *sent.counted_from.get_or_insert(last);
+                sent.last = start + done as f64;
+                (sent.last, start + total as f64)
+            }
+            Err(_) => return,
+        };
+        self.send(value, Some(total), message.into()).await;
+    }
+
+    async fn send(&self, progress: f64, total: Option<f64>, message: String) {
+        let Some((peer, token)) = &self.target else {
+            return;
+        };
+        let _ = peer
+            .notify_progress(ProgressNotificationParam {
+                progress_token: token.clone(),
+                progress,
+                total,
+                message: Some(message),
+            })
+            .await;
+    }
+}
@@ -0,0 +1,304 @@
+// MCP prompts for common documentation workflows. Each prompt runs a hybrid search
+// (scoped to `group_id`, or the server's default group) and pre-fills its message with
+// the retrieved passages, so the client model starts from the project's own docs.
+
+use rmcp::{
+    handler::server::{router::prompt::PromptRouter, wrapper::Parameters},
+    model::*,
+    prompt, prompt_router, ErrorData as McpError,
+};
+use serde::Deserialize;
+
+use super::errors::kb_error;
+use super::progress::Progress;
+use super::resources::doc_uri;
+use super::service::KbMcpService;
+use crate::knowledge_base::manager::HybridSearchResult;
+
+/// Documents retrieved into a prompt's context.
+const CONTEXT_DOCUMENTS: usize = 8;
+
+/// Characters of each document quoted into the context when no passage matched.
+const MAX_EXCERPT_CHARS: usize = 2_000;
+
+/// Linked documents quoted into the review prompt.
+const MAX_LINKED_SPECS: usize = 6;
+
+#[derive(Debug, Deserialize)]
+pub struct SummarizeDocsArgs {
+    pub group_id: Option<String>,
+    pub focus: Option<String>,
+}
+
+impl schemars::JsonSchema for SummarizeDocsArgs {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("SummarizeDocsArgs")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "group_id": {
+                    "type": "string",
+                    "description": "Group (usually a workspace path) to summarize, or 'all'; defaults to the server's default group"
+                },
+                "focus": {
+                    "type": "string",
+                    "description": "Optional area to concentrate on, e.g. 'deployment' or 'public API'"
+                }
+            },
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FindAdrsArgs {
+    pub topic: String,
+    pub group_id: Option<String>,
+}
+
+impl schemars::JsonSchema for FindAdrsArgs {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("FindAdrsArgs")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "topic": {
+                    "type": "string",
+                    "description": "Component, technology or question the decisions should relate to"
+                },
+                "group_id": {
+                    "type": "string",
+                    "description": "Group to search, or 'all'; defaults to the server's default group"
+                }
+            },
+            "required": ["topic"],
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DraftDocArgs {
+    pub topic: String,
+    pub doc_type: Option<String>,
+    pub group_id: Option<String>,
+}
+
+impl schemars::JsonSchema for DraftDocArgs {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("DraftDocArgs")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "topic": {
+                    "type": "string",
+                    "description": "What the new document is about"
+                },
+                "doc_type": {
+                    "type": "string",
+                    "description": "Kind of document, e.g. 'ADR', 'runbook', 'design doc' (default 'document')"
+                },
+                "group_id": {
+                    "type": "string",
+                    "description": "Group whose documents set the house style, or 'all'; defaults to the server's default group"
+                }
+            },
+            "required": ["topic"],
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewDocArgs {
+    pub document_id: String,
+}
+
+impl schemars::JsonSchema for ReviewDocArgs {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("ReviewDocArgs")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "document_id": {
+                    "type": "string",
+                    "description": "Id of the document to review"
+                }
+            },
+            "required": ["document_id"],
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+fn excerpt(content: &str) -> String {
+    match content.char_indices().nth(MAX_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", &content[..end]),
+        None => content.to_string(),
+    }
+}
+
+/// Search results as markdown sections, each citing its resource URI.
+fn format_context(results: &[HybridSearchResult]) -> String {
+    if results.is_empty() {
+        return "(No matching documents were found in the knowledge base.)".to_string();
+    }
+    results
+        .iter()
+        .map(|result| {
+            let mut section = format!("### {}\nSource: {}\n", result.title, doc_uri(&result.document_id));
+            if let Some(summary) = &result.summary {
+                section.push_str(&format!("Summary: {summary}\n"));
+            }
+            let body = match result.matched_chunks.is_empty() {
+                true => excerpt(&result.content),
+                false => result.matched_chunks.join("\n\n…\n\n"),
+            };
+            section.push('\n');
+            section.push_str(body.trim());
+            section
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n")
+}
+
+fn user_message(text: String) -> Vec<PromptMessage> {
+    vec![PromptMessage::new_text(PromptMessageRole::User, text)]
+}
+
+impl KbMcpService {
+    pub fn kb_prompt_router() -> PromptRouter<KbMcpService> {
+        Self::prompt_router()
+    }
+
+    async fn search_context(&self, query: String, group_id: Option<String>) -> Result<Vec<HybridSearchResult>, McpError> {
+        self.wait_for_model(&Progress::none()).await?;
+        let group_ids = self.group_scope(group_id).map(|id| vec![id]);
+        self.kb_manager
+            .search_hybrid_in_groups(query, CONTEXT_DOCUMENTS, group_ids)
+            .await
+            .map_err(kb_error)
+    }
+}
+
+#[prompt_router]
+impl KbMcpService {
+    #[prompt(description = "Summarize the project's documentation: purpose, architecture, key decisions and open questions.")]
+    async fn summarize_project_docs(&self, Parameters(args): Parameters<SummarizeDocsArgs>) -> Result<Vec<PromptMessage>, McpError> {
+        let focus = args.focus.filter(|focus| !focus.trim().is_empty());
+        let query = match &focus {
+            Some(focus) => focus.clone(),
+            None => "project overview architecture design getting started".to_string(),
+        };
+        let results = self.search_context(query, args.group_id).await?;
+
+        let focus = focus
+            .map(|focus| format!(" Concentrate on {focus}."))
+            .unwrap_or_default();
+        Ok(user_message(format!(
+            "Summarize this project's documentation for a new team member.{focus} Cover what the project does, \
+             how it is structured, the key decisions that shape it, and any open questions or gaps. \
+             Cite the source URI of each document you rely on.\n\n\
+             ## Knowledge base context\n\n{}",
+            format_context(&results)
+        )))
+    }
+
+    #[prompt(description = "Find architecture decision records (ADRs) related to a topic and explain how they bear on it.")]
+    async fn find_related_adrs(&self, Parameters(args): Parameters<FindAdrsArgs>) -> Result<Vec<PromptMessage>, McpError> {
+        let results = self
+            .search_context(format!("architecture decision record ADR {}", args.topic), args.group_id)
+            .await?;
+
+        Ok(user_message(format!(
+            "Which architecture decision records relate to \"{topic}\"? From the documents below, pick out \
+             the ADRs and other recorded decisions. For each one give its title and source URI, its status \
+             if stated, the decision in one sentence, and how it affects {topic}. Say so plainly if none \
+             of them apply.\n\n\
+             ## Knowledge base context\n\n{}",
+            format_context(&results),
+            topic = args.topic
+        )))
+    }
+
+    #[prompt(description = "Draft a new document in the house style of related documents in the knowledge base.")]
+    async fn draft_in_house_style(&self, Parameters(args): Parameters<DraftDocArgs>) -> Result<Vec<PromptMessage>, McpError> {
+        let doc_type = args
+            .doc_type
+            .filter(|doc_type| !doc_type.trim().is_empty())
+            .unwrap_or_else(|| "document".to_string());
+        let results = self
+            .search_context(format!("{doc_type} {}", args.topic), args.group_id)
+            .await?;
+
+        Ok(user_message(format!(
+            "Draft a new {doc_type} about \"{topic}\" in markdown. Match the house style of the existing \
+             documents below: their heading structure, section order, tone, terminology and level of detail. \
+             Reuse facts from them where relevant and link to them by source URI rather than repeating them; \
+             mark anything you had to assume with TODO.\n\n\
+             ## Related documents\n\n{}",
+            format_context(&results),
+            topic = args.topic
+        )))
+    }
+
+    #[prompt(description = "Review a document against the specifications it links to, plus other closely related documents.")]
+    async fn review_against_specs(&self, Parameters(args): Parameters<ReviewDocArgs>) -> Result<Vec<PromptMessage>, McpError> {
+        let document = self.kb_manager
+            .get_document(args.document_id.clone())
+            .await
+            .map_err(kb_error)?
+            .ok_or_else(|| McpError::invalid_params(format!("Document not found: {}", args.document_id), None))?;
+
+        let neighborhood = self.kb_manager
+            .get_graph_neighbors(document.id.clone(), 1)
+            .await
+            .map_err(kb_error)?;
+        let mut specs = Vec::new();
+        for linked in neighborhood.documents.iter().take(MAX_LINKED_SPECS) {
+            if let Some(spec) = self.kb_manager.get_document(linked.id.clone()).await.map_err(kb_error)? {
+                specs.push(format!(
+                    "### {}\nSource: {}\n\n{}",
+                    spec.title,
+                    doc_uri(&spec.id),
+                    excerpt(&spec.content).trim()
+                ));
+            }
+        }
+        let linked_specs = match specs.is_empty() {
+            true => "(This document has no links to other documents.)".to_string(),
+            false => specs.join("\n\n---\n\n"),
+        };
+
+        let mut related = self.search_context(document.title.clone(), None).await?;
+        related.retain(|result| {
+            result.document_id != document.id && !neighborhood.documents.iter().any(|d| d.id == result.document_id)
+        });
+
+        Ok(user_message(format!(
+            "Review the document \"{title}\" ({uri}) against the specifications it links to. List every \
+             contradiction, missing requirement and outdated statement, quoting both sides and citing the \
+             source URI. Then note gaps where the related documents suggest something the document should \
+             cover. Finish with a short verdict.\n\n\
+             ## Document under review\n\n{content}\n\n\
+             ## Linked specifications\n\n{linked_specs}\n\n\
+             ## Other related documents\n\n{}",
+            format_context(&related),
+            title = document.title,
+            uri = doc_uri(&document.id),
+            content = document.content.trim(),
+        )))
+    }
+}
@@ -0,0 +1,508 @@
+// MCP resources: knowledge base documents as `depdok://doc/{id}`, plus templates that
+// list the documents of a group or tag. Markdown files with several sections are stored
+// as one document per section, so a file's resource stitches its sections together and
+// each section stays addressable as `depdok://doc/{id}#{anchor}`.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rmcp::model::*;
+use rmcp::{Peer, RoleServer};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::json;
+
+use crate::knowledge_base::manager::{group_scope_cte, DocumentChangeRecord, KbManager};
+
+const DOC_PREFIX: &str = "depdok://doc/";
+const GROUP_PREFIX: &str = "depdok://group/";
+const TAG_PREFIX: &str = "depdok://tag/";
+const SECTION_MARKER: &str = "#section:";
+const MARKDOWN: &str = "text/markdown";
+
+/// Resources per `resources/list` page.
+const PAGE_SIZE: usize = 200;
+
+/// Upper bound on documents in a group or tag listing.
+const MAX_LISTING: usize = 500;
+
+/// How often the change feed is checked for subscribed resources.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ResourceUri {
+    Document { id: String, anchor: Option<String> },
+    Group(String),
+    Tag(String),
+}
+
+/// Resource URI of a document id; section documents map to their file's URI plus anchor.
+pub fn doc_uri(id: &str) -> String {
+    match id.split_once(SECTION_MARKER) {
+        Some((base, anchor)) => format!(
+            "{DOC_PREFIX}{}#{}",
+            urlencoding::encode(base),
+            urlencoding::encode(anchor)
+        ),
+        None => format!("{DOC_PREFIX}{}", urlencoding::encode(id)),
+    }
+}
+
+pub fn parse_uri(uri: &str) -> Option<ResourceUri> {
+    let decode = |s: &str| urlencoding::decode(s).ok().map(|s| s.into_owned());
+    if let Some(rest) = uri.strip_prefix(DOC_PREFIX) {
+        let (id, anchor) = match rest.split_once('#') {
+            Some((id, anchor)) => (id, Some(decode(anchor)?)),
+            None => (rest, None),
+        };
+        let id = decode(id)?;
+        return (!id.is_empty()).then_some(ResourceUri::Document { id, anchor });
+    }
+    if let Some(rest) = uri.strip_prefix(GROUP_PREFIX) {
+        return decode(rest).filter(|g| !g.is_empty()).map(ResourceUri::Group);
+    }
+    if let Some(rest) = uri.strip_prefix(TAG_PREFIX) {
+        return decode(rest)
+            .map(|t| t.trim_start_matches('#').to_string())
+            .filter(|t| !t.is_empty())
+            .map(ResourceUri::Tag);
+    }
+    None
+}
+
+fn base_id(id: &str) -> &str {
+    id.split_once(SECTION_MARKER).map_or(id, |(base, _)| base)
+}
+
+pub fn templates() -> Vec<ResourceTemplate> {
+    let template = |uri_template: &str, name: &str, description: &str, mime_type: &str| {
+        RawResourceTemplate {
+            uri_template: uri_template.to_string(),
+            name: name.to_string(),
+            title: None,
+            description: Some(description.to_string()),
+            mime_type: Some(mime_type.to_string()),
+        }
+        .no_annotation()
+    };
+    vec![
+        template(
+            "depdok://doc/{id}",
+            "document",
+            "A knowledge base document by URL-encoded id. Append #anchor to read one section of a markdown file.",
+            MARKDOWN,
+        ),
+        template(
+            "depdok://group/{group_id}",
+            "group",
+            "Documents in a group (usually a workspace path) and its subgroups, most recently updated first.",
+            "application/json",
+        ),
+        template(
+            "depdok://tag/{tag}",
+            "tag",
+            "Documents carrying a #tag.",
+            "application/json",
+        ),
+    ]
+}
+
+/// One page of document resources, one per file or standalone document, limited to
+/// `group_id` and its subgroups when given. `cursor` is the offset returned as
+/// `next_cursor` by the previous page.
+pub fn list_documents(
+    conn: &Connection,
+    cursor: Option<&str>,
+    group_id: Option<&str>,
+) -> Result<ListResourcesResult, String> {
+    let offset: usize = cursor.and_then(|c| c.parse().ok()).unwrap_or(0);
+    let mut stmt = conn
+        .prepare(&format!(
+            "WITH RECURSIVE {}
+             SELECT base, MIN(title), COUNT(*), SUM(length(CAST(content AS BLOB))) FROM (
+                 SELECT CASE WHEN instr(id, '#section:') > 0
+                             THEN substr(id, 1, instr(id, '#section:') - 1)
+                             ELSE id END AS base,
+                        title, content
+                 FROM documents
+                 WHERE ?3 IS NULL OR id IN (
+                     SELECT dg.document_id FROM document_groups dg
+                     INNER JOIN scope s ON s.id = dg.group_id
+                 )
+             )
+             GROUP BY base ORDER BY base
+             LIMIT ?1 OFFSET ?2",
+            group_scope_cte(3)
+        ))
+        .map_err(|e| e.to_string())?;
+    let scope_json = group_id.map(|id| json!([id]).to_string());
+    let rows: Vec<(String, String, i64, i64)> = stmt
+        .query_map(params![PAGE_SIZE as i64 + 1, offset as i64, scope_json], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut groups_stmt = conn
+        .prepare(
+            "SELECT DISTINCT g.title FROM document_groups dg
+             INNER JOIN groups g ON g.id = dg.group_id
+             WHERE dg.document_id = ?1 OR substr(dg.document_id, 1, length(?2)) = ?2
+             ORDER BY g.title",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let has_more = rows.len() > PAGE_SIZE;
+    let mut resources = Vec::new();
+    for (base, title, documents, bytes) in rows.into_iter().take(PAGE_SIZE) {
+        let groups: Vec<String> = groups_stmt
+            .query_map(params![base, format!("{base}{SECTION_MARKER}")], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+
+        let sectioned = documents > 1;
+        let title = if sectioned {
+            let path = base.strip_prefix("file:").unwrap_or(&base);
+            Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| base.clone())
+        } else {
+            title
+        };
+        let mut description = match groups.is_empty() {
+            true => "Not in any group".to_string(),
+            false => format!("In {}", groups.join(", ")),
+        };
+        if sectioned {
+            description.push_str(&format!("; {documents} sections"));
+        }
+
+        let mut resource = RawResource::new(doc_uri(&base), base.clone());
+        resource.title = Some(title);
+        resource.description = Some(description);
+        resource.mime_type = Some(MARKDOWN.to_string());
+        resource.size = (!sectioned).then(|| u32::try_from(bytes).unwrap_or(u32::MAX));
+        resources.push(resource.no_annotation());
+    }
+
+    Ok(ListResourcesResult {
+        resources,
+        next_cursor: has_more.then(|| (offset + PAGE_SIZE).to_string()),
+    })
+}
+
+/// Contents behind a resource URI, or `None` when nothing lives there.
+pub fn read(conn: &Connection, uri: &str) -> Result<Option<ResourceContents>, String> {
+    match parse_uri(uri) {
+        Some(ResourceUri::Document { id, anchor }) => read_document(conn, uri, &id, anchor.as_deref()),
+        Some(ResourceUri::Group(group_id)) => {
+            let documents = listing(
+                conn,
+                &format!(
+                    "WITH RECURSIVE {}
+                     SELECT DISTINCT d.id, d.title, d.updated_at FROM documents d
+                     INNER JOIN document_groups dg ON dg.document_id = d.id
+                     INNER JOIN scope s ON s.id = dg.group_id
+                     ORDER BY d.updated_at DESC LIMIT ?2",
+                    group_scope_cte(1)
+                ),
+                json!([group_id]).to_string(),
+            )?;
+            json_contents(uri, json!({ "groupId": group_id, "documents": documents })).map(Some)
+        }
+        Some(ResourceUri::Tag(tag)) => {
+            let documents = listing(
+                conn,
+                "SELECT d.id, d.title, d.updated_at FROM documents d
+                 INNER JOIN document_tags t ON t.document_id = d.id
+                 WHERE t.tag = ?1
+                 ORDER BY d.updated_at DESC LIMIT ?2",
+                tag.clone(),
+            )?;
+            json_contents(uri, json!({ "tag": tag, "documents": documents })).map(Some)
+        }
+        None => Ok(None),
+    }
+}
+
+fn read_document(
+    conn: &Connection,
+    uri: &str,
+    id: &str,
+    anchor: Option<&str>,
+) -> Result<Option<ResourceContents>, String> {
+    let target = match anchor {
+        Some(anchor) => format!("{id}{SECTION_MARKER}{anchor}"),
+        None => id.to_string(),
+    };
+    let content: Option<String> = conn
+        .query_row("SELECT content FROM documents WHERE id = ?1", params![target], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(text) = content {
+        return Ok(Some(ResourceContents::TextResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some(MARKDOWN.to_string()),
+            text,
+            meta: None,
+        }));
+    }
+    if anchor.is_some() {
+        return Ok(None);
+    }
+
+    // A file indexed section by section: stitch the sections back together in file order.
+    let prefix = format!("{id}{SECTION_MARKER}");
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, content FROM documents
+             WHERE substr(id, 1, length(?1)) = ?1
+             ORDER BY section_position, rowid",
+        )
+        .map_err(|e| e.to_string())?;
+    let sections: Vec<(String, String)> = stmt
+        .query_map(params![prefix], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    if sections.is_empty() {
+        return Ok(None);
+    }
+
+    let mut parts = Vec::with_capacity(sections.len());
+    let mut anchors = Vec::with_capacity(sections.len());
+    for (section_id, content) in &sections {
+        let anchor = &section_id[prefix.len()..];
+        parts.push(format!("<a id=\"{anchor}\"></a>\n\n{}", content.trim_end()));
+        anchors.push(json!({ "anchor": anchor, "uri": doc_uri(section_id) }));
+    }
+    let mut meta = Meta::new();
+    meta.insert("sections".to_string(), json!(anchors));
+
+    Ok(Some(ResourceContents::TextResourceContents {
+        uri: uri.to_string(),
+        mime_type: Some(MARKDOWN.to_string()),
+        text: parts.join("\n\n"),
+        meta: Some(meta),
+    }))
+}
+
+fn listing(conn: &Connection, sql: &str, key: String) -> Result<Vec<serde_json::Value>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![key, MAX_LISTING as i64], |row| {
+            let id: String = row.get(0)?;
+            Ok(json!({
+                "uri": doc_uri(&id),
+                "id": id,
+                "title": row.get::<_, String>(1)?,
+                "updatedAt": row.get::<_, Option<String>>(2)?,
+            }))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn json_contents(uri: &str, value: serde_json::Value) -> Result<ResourceContents, String> {
+    Ok(ResourceContents::TextResourceContents {
+        uri: uri.to_string(),
+        mime_type: Some("application/json".to_string()),
+        text: serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?,
+        meta: None,
+    })
+}
+
+/// URIs the client asked to be notified about via `resources/subscribe`.
+#[derive(Default)]
+pub struct Subscriptions(Mutex<HashSet<String>>);
+
+impl Subscriptions {
+    pub fn add(&self, uri: String) {
+        self.0.lock().unwrap().insert(uri);
+    }
+
+    pub fn remove(&self, uri: &str) {
+        self.0.lock().unwrap().remove(uri);
+    }
+
+    fn snapshot(&self) -> Vec<String> {
+        self.0.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Subscribed URIs whose contents may have changed with `changes`.
+fn affected_uris(
+    conn: &Connection,
+    changes: &[DocumentChangeRecord],
+    subscribed: &[String],
+) -> Result<Vec<String>, String> {
+    let any_delete = changes.iter().any(|c| c.change_type == "delete");
+    let mut affected = Vec::new();
+    for uri in subscribed {
+        let hit = match parse_uri(uri) {
+            Some(ResourceUri::Document { id, anchor: None }) => {
+                changes.iter().any(|c| base_id(&c.document_id) == id)
+            }
+            Some(ResourceUri::Document { id, anchor: Some(anchor) }) => {
+                let section_id = format!("{id}{SECTION_MARKER}{anchor}");
+                changes.iter().any(|c| c.document_id == section_id || c.document_id == id)
+            }
+            // Memberships of deleted documents are gone, so any delete may affect a listing.
+            Some(ResourceUri::Group(group_id)) => {
+                any_delete || {
+                    let sql = format!(
+                        "WITH RECURSIVE {}
+                         SELECT EXISTS(
+                             SELECT 1 FROM document_groups dg
+                             INNER JOIN scope s ON s.id = dg.group_id
+                             WHERE dg.document_id = ?2
+                         )",
+                        group_scope_cte(1)
+                    );
+                    let scope = json!([group_id]).to_string();
+                    let mut hit = false;
+                    for change in changes {
+                        hit |= conn
+                            .query_row(&sql, params![scope, change.document_id], |row| row.get::<_, bool>(0))
+                            .map_err(|e| e.to_string())?;
+                    }
+                    hit
+                }
+            }
+            Some(ResourceUri::Tag(tag)) => {
+                any_delete || {
+                    let mut hit = false;
+                    for change in changes {
+                        hit |= conn
+                            .query_row(
+                                "SELECT EXISTS(SELECT 1 FROM document_tags WHERE document_id = ?1 AND tag = ?2)",
+                                params![change.document_id, tag],
+                                |row| row.get::<_, bool>(0),
+                            )
+                            .map_err(|e| e.to_string())?;
+                    }
+                    hit
+                }
+            }
+            None => false,
+        };
+        if hit {
+            affected.push(uri.clone());
+        }
+    }
+    Ok(affected)
+}
+
+/// Follow the change feed for as long as the client is connected, sending
+/// `notifications/resources/list_changed` for every batch of changes and
+/// `notifications/resources/updated` for each affected subscription. Watching the
+/// shared feed catches writes made by the desktop app as well as by this server.
+pub async fn watch_changes(kb_manager: Arc<KbManager>, subscriptions: Arc<Subscriptions>, peer: Peer<RoleServer>) {
+    let mut cursor = {
+        let conn = kb_manager.read_connection().await;
+        match conn.query_row("SELECT COALESCE(MAX(seq), 0) FROM document_changes", [], |row| row.get(0)) {
+            Ok(seq) => seq,
+            Err(e) => {
+                eprintln!("resource notifications disabled: {e}");
+                return;
+            }
+        }
+    };
+
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let page = match kb_manager.get_changes_since(cursor, PAGE_SIZE, None).await {
+            Ok(page) => page,
+            Err(e) => {
+                eprintln!("failed to read change feed: {e}");
+                continue;
+            }
+        };
+        if page.changes.is_empty() {
+            continue;
+        }
+        cursor = page.next_cursor;
+
+        let affected = {
+            let conn = kb_manager.read_connection().await;
+            affected_uris(&conn, &page.changes, &subscriptions.snapshot())
+        };
+        if peer.notify_resource_list_changed().await.is_err() {
+            return;
+        }
+        match affected {
+            Ok(uris) => {
+                for uri in uris {
+                    if peer.notify_resource_updated(ResourceUpdatedNotificationParam { uri }).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(e) => eprintln!("failed to match resource subscriptions: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_uris_round_trip_with_section_anchors() {
+        let uri = doc_uri("file:/ws/docs/setup guide.md#section:install");
+        assert_eq!(uri, "depdok://doc/file%3A%2Fws%2Fdocs%2Fsetup%20guide.md#install");
+        assert_eq!(
+            parse_uri(&uri),
+            Some(ResourceUri::Document {
+                id: "file:/ws/docs/setup guide.md".to_string(),
+                anchor: Some("install".to_string()),
+            })
+        );
+        assert_eq!(parse_uri("depdok://tag/%23adr"), Some(ResourceUri::Tag("adr".to_string())));
+        assert_eq!(parse_uri("depdok://group/"), None);
+        assert_eq!(parse_uri("file:///etc/passwd"), None);
+    }
+
+    #[tokio::test]
+    async fn sectioned_files_are_stitched_in_file_order() -> Result<(), String> {
+        let kb = crate::knowledge_base::tests::build_test_manager()?;
+        let path = "/ws/guide.md".to_string();
+        for content in ["# Install\n\nRun it.\n\n# Usage\n\nUse it.", "# Install\n\nRun it.\n\n# Configure\n\nSet it.\n\n# Usage\n\nUse it."] {
+            kb.index_markdown_document_sections(path.clone(), "guide.md".to_string(), content.to_string(), vec!["/ws".to_string()])
+                .await?;
+        }
+
+        let db = kb.db_lock();
+        let conn = db.lock().await;
+        let Some(ResourceContents::TextResourceContents { text, .. }) =
+            read_document(&conn, "depdok://doc/guide", "file:/ws/guide.md", None)?
+        else {
+            return Err("expected the stitched file".to_string());
+        };
+        let position = |heading: &str| text.find(heading).ok_or(format!("{heading} missing"));
+        assert!(position("# Install")? < position("# Configure")?);
+        assert!(position("# Configure")? < position("# Usage")?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn listings_are_limited_to_the_given_group() -> Result<(), String> {
+        let kb = crate::knowledge_base::tests::build_test_manager()?;
+        for (path, group) in [("/repo-a/docs/a.md", "/repo-a"), ("/repo-b/b.md", "/repo-b")] {
+            kb.index_markdown_document_sections(path.to_string(), "doc.md".to_string(), "# A\n\nOne.\n\n# B\n\nTwo.".to_string(), vec![group.to_string()])
+                .await?;
+        }
+
+        let conn = kb.read_connection().await;
+        let names = |result: ListResourcesResult| -> Vec<String> {
+            result.resources.into_iter().map(|resource| resource.raw.name).collect()
+        };
+        assert_eq!(names(list_documents(&conn, None, Some("/repo-a"))?), vec!["file:/repo-a/docs/a.md"]);
+        assert_eq!(names(list_documents(&conn, None, None)?).len(), 2);
+        Ok(())
+    }
+}
@@ -1,32 +1,108 @@
-use rmcp::{handler::server::router::tool::ToolRouter, model::*, ServerHandler};
-use std::sync::Arc;
+use rmcp::{
+    handler::server::router::{prompt::PromptRouter, tool::ToolRouter},
+    model::*,
+    service::{NotificationContext, RequestContext},
+    ErrorData as McpError, RoleServer, ServerHandler,
+};
+use std::sync::{Arc, RwLock};
+use tokio::sync::watch;
 
 use crate::knowledge_base::manager::KbManager;
+use crate::llm::LlmState;
+use crate::mcp_server::logging::{self, ServerLog};
+use crate::mcp_server::model::{self, ModelStatus};
+use crate::mcp_server::progress::Progress;
+use crate::mcp_server::resources::{self, Subscriptions};
 use crate::mcp_server::McpServerConfig;
 
+/// `group_id` value that lifts the default group and searches every group.
+pub(crate) const ALL_GROUPS: &str = "all";
+
 #[derive(Clone)]
 pub struct KbMcpService {
     pub(crate) config: McpServerConfig,
     pub(crate) kb_manager: Arc<KbManager>,
+    /// Holds the local model between query expansion calls.
+    pub(crate) llm_state: Arc<LlmState>,
+    /// Resource URIs the client subscribed to for update notifications.
+    subscriptions: Arc<Subscriptions>,
+    /// Least severe log level the client asked to receive with `logging/setLevel`.
+    pub(crate) logging_level: Arc<RwLock<LoggingLevel>>,
+    /// Server events forwarded to the client as logging messages.
+    pub(crate) server_log: ServerLog,
+    pub(crate) model_status: watch::Receiver<ModelStatus>,
+    /// Indexer lease holder id shared by `--watch` and `kb_reindex`.
+    pub(crate) indexer_holder: String,
     tool_router: ToolRouter<KbMcpService>,
+    prompt_router: PromptRouter<KbMcpService>,
 }
 
 impl KbMcpService {
-    pub fn new(config: McpServerConfig, kb_manager: KbManager) -> Self {
+    pub fn new(
+        config: McpServerConfig,
+        kb_manager: KbManager,
+        indexer_holder: String,
+        model_status: watch::Receiver<ModelStatus>,
+        server_log: ServerLog,
+    ) -> Self {
+        let mut tool_router = Self::kb_tool_router();
+        if config.write_enabled {
+            tool_router += Self::kb_write_tool_router();
+        }
         Self {
             config,
             kb_manager: Arc::new(kb_manager),
-            tool_router: Self::kb_tool_router(),
+            llm_state: Arc::new(LlmState::new()),
+            subscriptions: Arc::new(Subscriptions::default()),
+            logging_level: Arc::new(RwLock::new(LoggingLevel::Info)),
+            server_log,
+            model_status,
+            indexer_holder,
+            tool_router,
+            prompt_router: Self::kb_prompt_router(),
+        }
+    }
+
+    /// Wait for the embedding model before work that embeds text.
+    pub(crate) async fn wait_for_model(&self, progress: &Progress) -> Result<(), McpError> {
+        model::wait_until_ready(&mut self.model_status.clone(), progress).await
+    }
+
+    /// The group a read request is limited to: the requested `group_id`, else the server's
+    /// default group. `None` (asked for with `"all"`, or with no default) means every group.
+    pub(crate) fn group_scope(&self, group_id: Option<String>) -> Option<String> {
+        match group_id.filter(|id| !id.trim().is_empty()) {
+            Some(id) if id == ALL_GROUPS => None,
+            Some(id) => Some(id),
+            None => self.config.default_group_id.clone(),
+        }
+    }
+
+    /// A handle for another client connection: shares the knowledge base and the loaded
+    /// models, but keeps its own resource subscriptions and log level.
+    pub fn for_session(&self) -> Self {
+        Self {
+            subscriptions: Arc::new(Subscriptions::default()),
+            logging_level: Arc::new(RwLock::new(LoggingLevel::Info)),
+            ..self.clone()
         }
     }
 }
 
 #[rmcp::tool_handler]
+#[rmcp::prompt_handler]
 impl ServerHandler for KbMcpService {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
-            protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            protocol_version: self.config.protocol_versions[0].clone(),
+            capabilities: ServerCapabilities::builder()
+                .enable_logging()
+                .enable_prompts()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_resources_list_changed()
+                .enable_tools()
+                .build(),
             server_info: Implementation {
                 name: self.config.server_name.clone(),
                 version: self.config.server_version.clone(),
@@ -35,4 +111,115 @@ impl ServerHandler for KbMcpService {
             instructions: None,
         }
     }
+
+    async fn initialize(
+        &self,
+        request: InitializeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<InitializeResult, McpError> {
+        let protocol_version = self.config.negotiate_protocol_version(&request.protocol_version);
+        if context.peer.peer_info().is_none() {
+            context.peer.set_peer_info(request);
+        }
+        Ok(ServerInfo {
+            protocol_version,
+            instructions: Some(self.instructions().await),
+            ..self.get_info()
+        })
+    }
+
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        if let Ok(mut level) = self.logging_level.write() {
+            *level = request.level;
+        }
+        Ok(())
+    }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        // Clients that connect mid-load missed the event, so tell them where loading stands.
+        let status = match &*self.model_status.borrow() {
+            ModelStatus::Loading => Some((LoggingLevel::Info, "the embedding model is still loading".to_string())),
+            ModelStatus::Unavailable(e) => Some((LoggingLevel::Error, format!("embedding model unavailable: {e}"))),
+            ModelStatus::Ready => None,
+        };
+        if let Some((level, message)) = status {
+            let event = logging::LogEvent { level, logger: "model", message };
+            logging::send(&context.peer, &self.logging_level, event).await;
+        }
+        tokio::spawn(logging::forward(
+            self.server_log.clone(),
+            context.peer.clone(),
+            self.logging_level.clone(),
+        ));
+        tokio::spawn(resources::watch_changes(
+            self.kb_manager.clone(),
+            self.subscriptions.clone(),
+            context.peer,
+        ));
+    }
+
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        // Like searches, the listing is limited to the default group when there is one.
+        let scope = self.group_scope(None);
+        let conn = self.kb_manager.read_connection().await;
+        let cursor = request.and_then(|r| r.cursor);
+        resources::list_documents(&conn, cursor.as_deref(), scope.as_deref())
+            .map_err(|e| McpError::internal_error(format!("Failed to list resources: {e}"), None))
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        Ok(ListResourceTemplatesResult {
+            resource_templates: resources::templates(),
+            next_cursor: None,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let conn = self.kb_manager.read_connection().await;
+        match resources::read(&conn, &request.uri) {
+            Ok(Some(contents)) => Ok(ReadResourceResult { contents: vec![contents] }),
+            Ok(None) => Err(McpError::resource_not_found(
+                format!("Resource not found: {}", request.uri),
+                None,
+            )),
+            Err(e) => Err(McpError::internal_error(format!("Failed to read {}: {e}", request.uri), None)),
+        }
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        if resources::parse_uri(&request.uri).is_none() {
+            return Err(McpError::invalid_params(format!("Unknown resource URI: {}", request.uri), None));
+        }
+        self.subscriptions.add(request.uri);
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.subscriptions.remove(&request.uri);
+        Ok(())
+    }
 }
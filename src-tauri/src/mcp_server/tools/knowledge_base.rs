@@ -2,15 +2,22 @@
 // Each tool is decorated with the `#[tool]` macro and routed via the `#[tool_router]` macro,
 // allowing connected LLM clients (like Codex or Claude Desktop) to invoke them over stdio.
 
-use rmcp::{handler::server::wrapper::Parameters, model::*, tool, tool_router, ErrorData as McpError};
+use rmcp::{handler::server::wrapper::Parameters, model::*, tool, tool_router, ErrorData as McpError, Peer, RoleServer};
 use serde::Deserialize;
 use serde_json::json;
+use super::super::errors::kb_error;
+use super::super::progress::Progress;
 use super::super::service::KbMcpService;
+use super::json_result;
+use crate::knowledge_base::language::{LanguageMode, LanguagePreference};
+use crate::knowledge_base::query_expansion::QueryExpansion;
+use crate::llm::providers::ProviderTextGenerator;
 
 #[derive(Debug, Deserialize)]
 pub struct SearchRequest {
     pub query: String,
     pub limit: Option<usize>,
+    pub group_id: Option<String>,
 }
 
 impl schemars::JsonSchema for SearchRequest {
@@ -28,6 +35,61 @@ impl schemars::JsonSchema for SearchRequest {
                 "limit": {
                     "type": "integer",
                     "minimum": 1
+                },
+                "group_id": {
+                    "type": "string",
+                    "description": "Only search documents in this group and its subgroups; defaults to the server's default group. Pass 'all' to search every group"
+                }
+            },
+            "required": ["query"],
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HybridSearchRequest {
+    pub query: String,
+    pub limit: Option<usize>,
+    pub expansion: Option<QueryExpansion>,
+    pub language: Option<String>,
+    pub language_mode: Option<LanguageMode>,
+    pub group_id: Option<String>,
+}
+
+impl schemars::JsonSchema for HybridSearchRequest {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("HybridSearchRequest")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string"
+                },
+                "limit": {
+                    "type": "integer",
+                    "minimum": 1
+                },
+                "expansion": {
+                    "type": "string",
+                    "enum": ["expand", "hyde"],
+                    "description": "Rewrite the query with the app's configured LLM before searching: 'expand' adds alternative queries, 'hyde' searches with a hypothetical answer passage. Results of all queries are fused."
+                },
+                "language": {
+                    "type": "string",
+                    "description": "ISO 639-1 code (e.g. 'en', 'vi') of the language to prefer; chunk languages are detected at indexing time"
+                },
+                "language_mode": {
+                    "type": "string",
+                    "enum": ["filter", "boost"],
+                    "description": "'filter' returns only passages in the language, 'boost' (default) ranks them first"
+                },
+                "group_id": {
+                    "type": "string",
+                    "description": "Only search documents in this group and its subgroups; defaults to the server's default group. Pass 'all' to search every group"
                 }
             },
             "required": ["query"],
@@ -36,6 +98,165 @@ impl schemars::JsonSchema for SearchRequest {
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ChangesRequest {
+    pub cursor: Option<i64>,
+    pub limit: Option<usize>,
+    pub group_id: Option<String>,
+}
+
+impl schemars::JsonSchema for ChangesRequest {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("ChangesRequest")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "cursor": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "nextCursor from the previous call; omit or 0 for the full feed"
+                },
+                "limit": {
+                    "type": "integer",
+                    "minimum": 1
+                },
+                "group_id": {
+                    "type": "string",
+                    "description": "Only report changes to documents in this group and its subgroups, deletions included; defaults to the server's default group. Pass 'all' for every group"
+                }
+            },
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecentDocumentsRequest {
+    pub group_id: Option<String>,
+    pub since: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl schemars::JsonSchema for RecentDocumentsRequest {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("RecentDocumentsRequest")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "group_id": {
+                    "type": "string",
+                    "description": "Group to list, with its subgroups; defaults to the server's default group. Pass 'all' for every group"
+                },
+                "since": {
+                    "type": "string",
+                    "description": "RFC 3339 timestamp; only documents updated at or after it"
+                },
+                "limit": {
+                    "type": "integer",
+                    "minimum": 1
+                }
+            },
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetDocumentRequest {
+    pub id: String,
+    pub section: Option<String>,
+}
+
+impl schemars::JsonSchema for GetDocumentRequest {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("GetDocumentRequest")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "id": {
+                    "type": "string",
+                    "description": "Document id as returned by search, e.g. 'file:/path/to/README.md'"
+                },
+                "section": {
+                    "type": "string",
+                    "description": "Anchor of one section of a markdown file, e.g. 'installation'; omit for the whole document"
+                }
+            },
+            "required": ["id"],
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChunkContextRequest {
+    pub document_id: String,
+    pub chunk_index: Option<usize>,
+    pub window: Option<usize>,
+}
+
+impl schemars::JsonSchema for ChunkContextRequest {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("ChunkContextRequest")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "document_id": {
+                    "type": "string"
+                },
+                "chunk_index": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Zero-based index of the passage within the document (default 0)"
+                },
+                "window": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Passages to include before and after it (default 1)"
+                }
+            },
+            "required": ["document_id"],
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GroupRequest {
+    pub group_id: Option<String>,
+}
+
+impl schemars::JsonSchema for GroupRequest {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("GroupRequest")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "group_id": {
+                    "type": "string",
+                    "description": "Group id from kb_list_groups (usually a workspace or folder path); defaults to the server's default group. kb_list_tags also accepts 'all'"
+                }
+            },
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
 impl KbMcpService {
     pub fn kb_tool_router() -> rmcp::handler::server::router::tool::ToolRouter<KbMcpService> {
         Self::tool_router()
@@ -46,8 +267,7 @@ impl KbMcpService {
 impl KbMcpService {
     #[tool(description = "Return SQLite version, database path, and record counts for the knowledge base.")]
     async fn kb_get_status(&self) -> Result<CallToolResult, McpError> {
-        let conn = self.kb_manager.db_lock();
-        let conn = conn.lock().await;
+        let conn = self.kb_manager.read_connection().await;
 
         let sqlite_version: String = conn
             .query_row("SELECT sqlite_version()", [], |row| row.get(0))
@@ -92,11 +312,26 @@ impl KbMcpService {
         })
     }
 
-    #[tool(description = "Search the knowledge base using hybrid keyword (FTS5) + semantic (vector) search.")]
-    async fn kb_search(&self, Parameters(req): Parameters<SearchRequest>) -> Result<CallToolResult, McpError> {
+    #[tool(description = "Search the knowledge base using hybrid keyword (FTS5) + semantic (vector) search, within the server's default group unless group_id says otherwise.")]
+    async fn kb_search(&self, Parameters(req): Parameters<HybridSearchRequest>, meta: Meta, peer: Peer<RoleServer>) -> Result<CallToolResult, McpError> {
+        let progress = Progress::new(peer, &meta);
+        self.wait_for_model(&progress).await?;
         let limit = req.limit.unwrap_or(10);
-        let results = self.kb_manager.search_hybrid(req.query, limit).await
-            .map_err(|e| McpError::internal_error(e, None))?;
+        let group_ids = self.group_scope(req.group_id).map(|id| vec![id]);
+        let language = req
+            .language
+            .and_then(|code| LanguagePreference::new(&code, req.language_mode.unwrap_or_default()));
+        let results = match req.expansion {
+            Some(mode) => {
+                let generator = ProviderTextGenerator::new(self.config.load_llm_config(), &self.llm_state)
+                    .await
+                    .map_err(|e| McpError::internal_error(e, None))?;
+                progress.report(1, 2, "Loaded the query expansion model; expanding and searching").await;
+                self.kb_manager.search_expanded(req.query, limit, group_ids, language, mode, &generator).await
+            }
+            None => self.kb_manager.search_hybrid_with_language(req.query, limit, group_ids, language).await,
+        }
+        .map_err(kb_error)?;
 
         let pretty_results = serde_json::to_string_pretty(&results)
             .map_err(|e| McpError::internal_error(e.to_string(), None))?;
@@ -111,11 +346,13 @@ impl KbMcpService {
         })
     }
 
-    #[tool(description = "Search the knowledge base for semantically similar documents.")]
-    async fn kb_search_similar(&self, Parameters(req): Parameters<SearchRequest>) -> Result<CallToolResult, McpError> {
+    #[tool(description = "Search the knowledge base for semantically similar documents, within the server's default group unless group_id says otherwise.")]
+    async fn kb_search_similar(&self, Parameters(req): Parameters<SearchRequest>, meta: Meta, peer: Peer<RoleServer>) -> Result<CallToolResult, McpError> {
+        self.wait_for_model(&Progress::new(peer, &meta)).await?;
         let limit = req.limit.unwrap_or(10);
-        let results = self.kb_manager.search_similar(req.query, limit).await
-            .map_err(|e| McpError::internal_error(e, None))?;
+        let group_ids = self.group_scope(req.group_id).map(|id| vec![id]);
+        let results = self.kb_manager.search_similar_in_groups(req.query, limit, group_ids).await
+            .map_err(kb_error)?;
 
         let pretty_results = serde_json::to_string_pretty(&results)
             .map_err(|e| McpError::internal_error(e.to_string(), None))?;
@@ -129,4 +366,141 @@ impl KbMcpService {
             structured_content: Some(structured),
         })
     }
+
+    #[tool(description = "Return documents changed after a cursor (upserts and deletions), oldest first. Pass the returned nextCursor on the next call to stay in sync incrementally.")]
+    async fn kb_get_changes(&self, Parameters(req): Parameters<ChangesRequest>) -> Result<CallToolResult, McpError> {
+        let page = self.kb_manager
+            .get_changes_since(req.cursor.unwrap_or(0), req.limit.unwrap_or(100), self.group_scope(req.group_id))
+            .await
+            .map_err(kb_error)?;
+
+        let structured = serde_json::to_value(&page)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let pretty_page = serde_json::to_string_pretty(&structured)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult {
+            content: vec![Content::text(pretty_page)],
+            is_error: Some(false),
+            meta: None,
+            structured_content: Some(structured),
+        })
+    }
+
+    #[tool(description = "List the most recently updated documents in a group, optionally only those updated since a timestamp.")]
+    async fn kb_recent_documents(&self, Parameters(req): Parameters<RecentDocumentsRequest>) -> Result<CallToolResult, McpError> {
+        let documents = self.kb_manager
+            .list_recent_documents(self.group_scope(req.group_id), req.since, req.limit.unwrap_or(20))
+            .await
+            .map_err(kb_error)?;
+
+        let pretty_documents = serde_json::to_string_pretty(&documents)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        let structured = json!({ "documents": documents });
+
+        Ok(CallToolResult {
+            content: vec![Content::text(pretty_documents)],
+            is_error: Some(false),
+            meta: None,
+            structured_content: Some(structured),
+        })
+    }
+
+    #[tool(description = "Fetch a knowledge base document by id. Pass section to read one section of a markdown file; a file split into sections returns all of them, in order.")]
+    async fn kb_get_document(&self, Parameters(req): Parameters<GetDocumentRequest>) -> Result<CallToolResult, McpError> {
+        let id = match req.section.as_deref().map(|s| s.trim_start_matches('#')) {
+            Some(section) if !section.is_empty() => format!("{}#section:{section}", req.id),
+            _ => req.id.clone(),
+        };
+        if let Some(document) = self.kb_manager
+            .get_document(id.clone())
+            .await
+            .map_err(kb_error)?
+        {
+            return json_result(json!({ "document": document }));
+        }
+
+        let sections = self.kb_manager
+            .get_document_sections(id.clone())
+            .await
+            .map_err(kb_error)?;
+        if sections.is_empty() {
+            return Err(McpError::invalid_params(format!("Document not found: {id}"), None));
+        }
+        let mut documents = Vec::with_capacity(sections.len());
+        for section in sections {
+            if let Some(document) = self.kb_manager
+                .get_document(section.id)
+                .await
+                .map_err(kb_error)?
+            {
+                documents.push(json!({ "anchor": section.anchor, "document": document }));
+            }
+        }
+
+        json_result(json!({ "id": id, "sections": documents }))
+    }
+
+    #[tool(description = "Read a passage of a document together with the passages around it, e.g. to widen a search hit.")]
+    async fn kb_get_chunk_context(&self, Parameters(req): Parameters<ChunkContextRequest>) -> Result<CallToolResult, McpError> {
+        let chunk_id = format!("{}#{}", req.document_id, req.chunk_index.unwrap_or(0));
+        let chunks = self.kb_manager
+            .get_chunk_context(chunk_id, req.window.unwrap_or(1))
+            .await
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        json_result(json!({ "chunks": chunks }))
+    }
+
+    #[tool(description = "List every group (workspaces and their folders) with its parent and document count.")]
+    async fn kb_list_groups(&self) -> Result<CallToolResult, McpError> {
+        let groups = self.kb_manager
+            .list_groups()
+            .await
+            .map_err(kb_error)?;
+
+        json_result(json!({ "groups": groups }))
+    }
+
+    #[tool(description = "Return the documents of a group (without their content) and the edges between them. Fetch content with kb_get_document.")]
+    async fn kb_get_project_graph(&self, Parameters(req): Parameters<GroupRequest>) -> Result<CallToolResult, McpError> {
+        let group_id = self
+            .group_scope(req.group_id)
+            .ok_or_else(|| McpError::invalid_params("group_id must name one group: this server has no default_group_id", None))?;
+        let graph = self.kb_manager
+            .get_project_graph(group_id)
+            .await
+            .map_err(kb_error)?;
+
+        // Whole groups can hold megabytes of text; the graph shape is what this tool is for.
+        let documents: Vec<_> = graph
+            .documents
+            .iter()
+            .map(|d| json!({
+                "id": d.id,
+                "title": d.title,
+                "updatedAt": d.updated_at,
+                "summary": d.summary,
+                "keywords": d.keywords,
+            }))
+            .collect();
+
+        json_result(json!({
+            "groupId": graph.group_id,
+            "groupTitle": graph.group_title,
+            "documents": documents,
+            "edges": graph.edges,
+        }))
+    }
+
+    #[tool(description = "List #tags with the number of documents carrying each, most used first, optionally within one group.")]
+    async fn kb_list_tags(&self, Parameters(req): Parameters<GroupRequest>) -> Result<CallToolResult, McpError> {
+        let tags = self.kb_manager
+            .list_tags(self.group_scope(req.group_id))
+            .await
+            .map_err(kb_error)?;
+
+        json_result(json!({ "tags": tags }))
+    }
 }
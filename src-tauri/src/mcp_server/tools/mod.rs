@@ -1 +1,17 @@
+use rmcp::{model::*, ErrorData as McpError};
+
 pub mod knowledge_base;
+pub mod write;
+
+/// A tool result carrying `structured` both as pretty-printed text and as structured content.
+fn json_result(structured: serde_json::Value) -> Result<CallToolResult, McpError> {
+    let pretty = serde_json::to_string_pretty(&structured)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(pretty)],
+        is_error: Some(false),
+        meta: None,
+        structured_content: Some(structured),
+    })
+}
@@ -0,0 +1,291 @@
+// Write tools for the Depdok MCP server. They are only registered when the server runs
+// with `--write-enabled` (or `write_enabled` in the config file), so coding agents can
+// record decisions and notes in the project knowledge base when explicitly allowed.
+
+use std::path::Path;
+
+use rmcp::{handler::server::wrapper::Parameters, model::*, tool, tool_router, ErrorData as McpError, Peer, RoleServer};
+use serde::Deserialize;
+use serde_json::json;
+use super::super::config::absolute_path;
+use super::super::errors::{database_locked, kb_error};
+use super::super::progress::Progress;
+use super::super::service::KbMcpService;
+use super::super::watch::sync_workspace_leased;
+use super::json_result;
+
+#[derive(Debug, Deserialize)]
+pub struct UpsertDocumentRequest {
+    pub id: Option<String>,
+    pub title: String,
+    pub content: String,
+    pub group_ids: Option<Vec<String>>,
+}
+
+impl schemars::JsonSchema for UpsertDocumentRequest {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("UpsertDocumentRequest")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "id": {
+                    "type": "string",
+                    "description": "Existing document id to update; omit to create a new document"
+                },
+                "title": {
+                    "type": "string"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Markdown; #tags and [[wikilinks]] are indexed"
+                },
+                "group_ids": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Groups to add the document to; defaults to the server's default group"
+                }
+            },
+            "required": ["title", "content"],
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteDocumentRequest {
+    pub id: String,
+}
+
+impl schemars::JsonSchema for DeleteDocumentRequest {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("DeleteDocumentRequest")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "id": {
+                    "type": "string"
+                }
+            },
+            "required": ["id"],
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConnectRequest {
+    pub source_id: String,
+    pub target_id: String,
+    pub edge_type: Option<String>,
+}
+
+impl schemars::JsonSchema for ConnectRequest {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("ConnectRequest")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "source_id": {
+                    "type": "string"
+                },
+                "target_id": {
+                    "type": "string"
+                },
+                "edge_type": {
+                    "type": "string",
+                    "description": "Relationship label, e.g. 'related' (default), 'supersedes', 'implements'"
+                }
+            },
+            "required": ["source_id", "target_id"],
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisconnectRequest {
+    pub source_id: String,
+    pub target_id: String,
+}
+
+impl schemars::JsonSchema for DisconnectRequest {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("DisconnectRequest")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "source_id": {
+                    "type": "string"
+                },
+                "target_id": {
+                    "type": "string"
+                }
+            },
+            "required": ["source_id", "target_id"],
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReindexRequest {
+    pub path: String,
+    pub force: Option<bool>,
+}
+
+impl schemars::JsonSchema for ReindexRequest {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("ReindexRequest")
+    }
+
+    fn json_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Absolute path of the workspace folder whose markdown files to index"
+                },
+                "force": {
+                    "type": "boolean",
+                    "description": "Re-index every file, not only those changed since they were last indexed (default false)"
+                }
+            },
+            "required": ["path"],
+            "additionalProperties": false
+        })).unwrap()
+    }
+}
+
+/// `file:` documents mirror files on disk and are rebuilt from them on every index, so
+/// edits through MCP would be silently lost.
+fn reject_file_document(id: &str) -> Result<(), McpError> {
+    if id.starts_with("file:") {
+        return Err(McpError::invalid_params(
+            format!("{id} is indexed from a file on disk; edit the file instead"),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+impl KbMcpService {
+    pub fn kb_write_tool_router() -> rmcp::handler::server::router::tool::ToolRouter<KbMcpService> {
+        Self::write_tool_router()
+    }
+}
+
+#[tool_router(router = write_tool_router)]
+impl KbMcpService {
+    #[tool(description = "Create or update a knowledge base document (e.g. a decision record or note). Omit id to create one. The document joins group_ids, or the server's default group.")]
+    async fn kb_upsert_document(&self, Parameters(req): Parameters<UpsertDocumentRequest>, meta: Meta, peer: Peer<RoleServer>) -> Result<CallToolResult, McpError> {
+        if let Some(id) = req.id.as_deref() {
+            reject_file_document(id)?;
+        }
+        self.wait_for_model(&Progress::new(peer, &meta)).await?;
+        let group_ids = req
+            .group_ids
+            .filter(|ids| !ids.is_empty())
+            .or_else(|| self.config.default_group_id.clone().map(|id| vec![id]))
+            .ok_or_else(|| {
+                McpError::invalid_params("group_ids is required: this server has no default_group_id", None)
+            })?;
+
+        let id = self.kb_manager
+            .upsert_document(req.id, req.title, req.content, group_ids.clone())
+            .await
+            .map_err(kb_error)?;
+
+        json_result(json!({ "id": id, "groupIds": group_ids }))
+    }
+
+    #[tool(description = "Delete a knowledge base document, its sections and its links. Documents indexed from files cannot be deleted.")]
+    async fn kb_delete_document(&self, Parameters(req): Parameters<DeleteDocumentRequest>) -> Result<CallToolResult, McpError> {
+        reject_file_document(&req.id)?;
+        let existing = self.kb_manager
+            .get_document(req.id.clone())
+            .await
+            .map_err(kb_error)?;
+        if existing.is_none() {
+            return Err(McpError::invalid_params(format!("Document not found: {}", req.id), None));
+        }
+
+        self.kb_manager
+            .delete_document(req.id.clone())
+            .await
+            .map_err(kb_error)?;
+
+        json_result(json!({ "deleted": req.id }))
+    }
+
+    #[tool(description = "Link two knowledge base documents with a typed edge, shown in the knowledge graph.")]
+    async fn kb_connect(&self, Parameters(req): Parameters<ConnectRequest>) -> Result<CallToolResult, McpError> {
+        let edge_type = req.edge_type.unwrap_or_else(|| "related".to_string());
+        let edge_id = self.kb_manager
+            .connect_documents(req.source_id, req.target_id, Some(edge_type))
+            .await
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        json_result(json!({ "edgeId": edge_id }))
+    }
+
+    #[tool(description = "Remove all links between two knowledge base documents (and their sections), in both directions.")]
+    async fn kb_disconnect(&self, Parameters(req): Parameters<DisconnectRequest>) -> Result<CallToolResult, McpError> {
+        let removed = self.kb_manager
+            .disconnect_documents(req.source_id, req.target_id)
+            .await
+            .map_err(kb_error)?;
+
+        json_result(json!({ "removed": removed }))
+    }
+
+    #[tool(description = "Index the markdown files of a workspace folder from disk: new and changed files are indexed, and documents of deleted files removed. The folder must be a workspace known to the knowledge base or watched by this server. Sends progress notifications when the request has a progress token.")]
+    async fn kb_reindex(&self, Parameters(req): Parameters<ReindexRequest>, meta: Meta, peer: Peer<RoleServer>) -> Result<CallToolResult, McpError> {
+        // Groups are keyed by the path the folder was opened under, so symlinks stay as given.
+        let root = absolute_path(Path::new(&req.path));
+        if !root.is_dir() {
+            return Err(McpError::invalid_params(format!("{} is not a folder", root.display()), None));
+        }
+        let group_id = root.to_string_lossy().to_string();
+        let watched = self.config.watch.iter().any(|path| absolute_path(path) == root);
+        let known = watched
+            || self.kb_manager.list_groups().await.map_err(kb_error)?.iter().any(|group| group.id == group_id);
+        if !known {
+            return Err(McpError::invalid_params(
+                format!("{group_id} is not a workspace of this knowledge base: open it in the Depdok app or pass it to --watch"),
+                None,
+            ));
+        }
+
+        let progress = Progress::new(peer, &meta);
+        self.wait_for_model(&progress).await?;
+        self.server_log.info("reindex", format!("indexing {group_id}"));
+        let release = self.config.watch.is_empty();
+        let summary = sync_workspace_leased(&self.kb_manager, &self.indexer_holder, release, &root, req.force.unwrap_or(false), &progress)
+            .await
+            .map_err(kb_error)?
+            .ok_or_else(|| database_locked("another process is indexing the knowledge base; retry once it has finished"))?;
+        self.server_log.info(
+            "reindex",
+            format!("indexed {group_id}: {} indexed, {} removed", summary.indexed, summary.removed),
+        );
+
+        json_result(json!({
+            "groupId": group_id,
+            "indexed": summary.indexed,
+            "removed": summary.removed,
+        }))
+    }
+}
@@ -0,0 +1,428 @@
+// `--watch` mode: keep the markdown files of workspace roots indexed in the shared
+// database, so agents do not search stale docs while the desktop app is closed.
+// Indexing is guarded by the indexer lease: the desktop app claims it while it runs,
+// and among several watching MCP servers only the lease holder writes. Followers retry
+// on every renewal tick and catch up with the disk when they take over.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+use serde::Serialize;
+use tokio::sync::{mpsc, watch};
+
+use crate::commands::file_watcher::{is_ignored_path, watch_workspace_changes, WorkspaceChangeEvent};
+use crate::knowledge_base::lease::{self, INDEXER_LEASE, LEASE_RENEW_INTERVAL, LEASE_TTL};
+use crate::knowledge_base::manager::KbManager;
+use crate::knowledge_base::revisions::content_hash;
+
+use super::config::absolute_path;
+use super::logging::ServerLog;
+use super::model::{self, ModelStatus};
+use super::progress::Progress;
+
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| MARKDOWN_EXTENSIONS.iter().any(|m| ext.eq_ignore_ascii_case(m)))
+}
+
+/// Releases the indexer lease when the server shuts down, so the next process need not
+/// wait for it to expire.
+pub struct WatchHandle {
+    kb_manager: KbManager,
+    holder: String,
+    log: ServerLog,
+}
+
+impl WatchHandle {
+    pub async fn release(self) {
+        let db = self.kb_manager.db_lock();
+        let conn = db.lock().await;
+        if let Err(e) = lease::release(&conn, INDEXER_LEASE, &self.holder) {
+            self.log.warning("watch", e);
+        }
+    }
+}
+
+/// Start watching `roots` and indexing their markdown whenever `holder` holds the
+/// indexer lease. Indexing starts once the embedding model has loaded.
+pub fn spawn(
+    kb_manager: KbManager,
+    roots: &[PathBuf],
+    holder: String,
+    mut model_status: watch::Receiver<ModelStatus>,
+    log: ServerLog,
+) -> Result<WatchHandle, String> {
+    let roots: Vec<PathBuf> = roots.iter().map(|root| absolute_path(root)).collect();
+    if let Some(missing) = roots.iter().find(|root| !root.is_dir()) {
+        return Err(format!("Cannot watch {}: not a folder", missing.display()));
+    }
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<(PathBuf, Vec<WorkspaceChangeEvent>)>();
+    let mut debouncers = Vec::with_capacity(roots.len());
+    for root in &roots {
+        let tx = tx.clone();
+        let event_root = root.clone();
+        debouncers.push(watch_workspace_changes(root, move |batch| {
+            let _ = tx.send((event_root.clone(), batch));
+        })?);
+    }
+
+    let handle = WatchHandle { kb_manager: kb_manager.clone(), holder: holder.clone(), log: log.clone() };
+
+    tokio::spawn(async move {
+        // Dropping the debouncers would stop the OS watches.
+        let _debouncers = debouncers;
+        if model::wait_until_ready(&mut model_status, &Progress::none()).await.is_err() {
+            log.warning("watch", "not indexing workspaces: the embedding model is unavailable");
+            return;
+        }
+        let mut renew = tokio::time::interval(LEASE_RENEW_INTERVAL);
+        let mut leader = false;
+        loop {
+            tokio::select! {
+                _ = renew.tick() => {
+                    let mut acquired = match acquire(&kb_manager, &holder).await {
+                        Ok(acquired) => acquired,
+                        Err(e) => {
+                            log.warning("watch", e);
+                            false
+                        }
+                    };
+                    if acquired && !leader {
+                        log.info("watch", format!("watching {} workspace(s); this server now keeps them indexed", roots.len()));
+                        for root in &roots {
+                            match while_leased(&kb_manager, &holder, sync_workspace(&kb_manager, root, false, &Progress::none())).await {
+                                Ok(Ok(summary)) if summary.indexed + summary.removed > 0 => log.info(
+                                    "watch",
+                                    format!("synced {}: {} indexed, {} removed", root.display(), summary.indexed, summary.removed),
+                                ),
+                                Ok(Ok(_)) => {}
+                                Ok(Err(e)) => log.error("watch", format!("failed to sync {}: {e}", root.display())),
+                                Err(e) => {
+                                    log.warning("watch", format!("stopped syncing {}: {e}; standing by", root.display()));
+                                    acquired = false;
+                                    break;
+                                }
+                            }
+                        }
+                    } else if !acquired && leader {
+                        log.info("watch", "another process took over indexing; standing by");
+                    }
+                    leader = acquired;
+                }
+                Some((root, batch)) = rx.recv() => {
+                    // Re-check right before writing, in case the desktop app started since the last tick.
+                    leader = leader && acquire(&kb_manager, &holder).await.unwrap_or(false);
+                    if !leader {
+                        continue;
+                    }
+                    let paths: BTreeSet<PathBuf> = batch
+                        .into_iter()
+                        .flat_map(|change| [Some(change.path), change.from_path])
+                        .flatten()
+                        .map(PathBuf::from)
+                        .filter(|path| is_markdown(path))
+                        .collect();
+                    let progress = Progress::none();
+                    let sync = sync_paths(&kb_manager, &root, paths.into_iter().collect(), &progress);
+                    match while_leased(&kb_manager, &holder, sync).await {
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => log.error("watch", format!("failed to index changes in {}: {e}", root.display())),
+                        Err(e) => {
+                            log.warning("watch", format!("stopped indexing changes in {}: {e}; standing by", root.display()));
+                            leader = false;
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(handle)
+}
+
+async fn acquire(kb_manager: &KbManager, holder: &str) -> Result<bool, String> {
+    let db = kb_manager.db_lock();
+    let mut conn = db.lock().await;
+    lease::try_acquire(&mut conn, INDEXER_LEASE, holder, LEASE_TTL)
+}
+
+/// Run `work` while renewing `holder`'s indexer lease every `LEASE_RENEW_INTERVAL`.
+/// If a renewal fails or another process has taken the lease, `work` is dropped, which
+/// stops it at its next await, and the reason is returned as the error.
+async fn while_leased<T>(kb_manager: &KbManager, holder: &str, work: impl Future<Output = T>) -> Result<T, String> {
+    let renewal = async {
+        let mut renew = tokio::time::interval(LEASE_RENEW_INTERVAL);
+        renew.tick().await;
+        loop {
+            renew.tick().await;
+            match acquire(kb_manager, holder).await {
+                Ok(true) => {}
+                Ok(false) => return "another process took over the indexer lease".to_string(),
+                Err(e) => return format!("failed to renew the indexer lease: {e}"),
+            }
+        }
+    };
+    tokio::select! {
+        result = work => Ok(result),
+        reason = renewal => Err(reason),
+    }
+}
+
+/// Run `sync_workspace` for `kb_reindex` as `holder` of the indexer lease, renewing it
+/// until the sync ends. Returns `None` without indexing while another process holds the
+/// lease. With `release`, the lease is given up afterwards; a watching server keeps it.
+pub async fn sync_workspace_leased(
+    kb_manager: &KbManager,
+    holder: &str,
+    release: bool,
+    root: &Path,
+    force: bool,
+    progress: &Progress,
+) -> Result<Option<SyncSummary>, String> {
+    if !acquire(kb_manager, holder).await? {
+        return Ok(None);
+    }
+    let result = while_leased(kb_manager, holder, sync_workspace(kb_manager, root, force, progress))
+        .await
+        .and_then(|result| result);
+    if release {
+        let db = kb_manager.db_lock();
+        let conn = db.lock().await;
+        lease::release(&conn, INDEXER_LEASE, holder)?;
+    }
+    result.map(Some)
+}
+
+/// Files indexed and files whose documents were removed by a sync.
+#[derive(Debug, Default, Serialize)]
+pub struct SyncSummary {
+    pub indexed: usize,
+    pub removed: usize,
+}
+
+/// Index each path that exists and drop the documents of each path that does not.
+async fn sync_paths(
+    kb_manager: &KbManager,
+    root: &Path,
+    paths: Vec<PathBuf>,
+    progress: &Progress,
+) -> Result<SyncSummary, String> {
+    let group_id = root.to_string_lossy().to_string();
+    let mut summary = SyncSummary::default();
+    let total = paths.len();
+    for (done, path) in paths.into_iter().enumerate() {
+        let file_path = path.to_string_lossy().to_string();
+        if path.is_file() {
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) => {
+                    eprintln!("skipping {file_path}: {e}");
+                    continue;
+                }
+            };
+            let title = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| file_path.clone());
+            kb_manager
+                .index_markdown_document_sections(file_path, title, content, vec![group_id.clone()])
+                .await?;
+            summary.indexed += 1;
+        } else {
+            kb_manager.delete_document(format!("file:{file_path}")).await?;
+            summary.removed += 1;
+        }
+        progress.report(done + 1, total, path.to_string_lossy()).await;
+    }
+    // Links between files resolve by path, so new or moved files can complete edges elsewhere.
+    if summary.indexed + summary.removed > 0 {
+        kb_manager.rebuild_all_edges().await?;
+    }
+    Ok(summary)
+}
+
+/// Bring `root` up to date with the disk: index markdown files whose content differs
+/// from what was last indexed (every file with `force`), and drop documents of deleted
+/// files. Used after time without an indexer and by `kb_reindex`.
+pub async fn sync_workspace(
+    kb_manager: &KbManager,
+    root: &Path,
+    force: bool,
+    progress: &Progress,
+) -> Result<SyncSummary, String> {
+    let indexed = indexed_files(kb_manager, root).await?;
+
+    let mut on_disk = Vec::new();
+    collect_markdown(root, root, &mut on_disk);
+
+    let mut stale: Vec<PathBuf> = on_disk
+        .iter()
+        .filter(|(path, modified)| {
+            let Some(file) = indexed.get(path.to_string_lossy().as_ref()) else {
+                return true;
+            };
+            if force {
+                return true;
+            }
+            if file.updated_at.is_some_and(|updated_at| *modified <= updated_at) {
+                return false;
+            }
+            // Checkouts and formatters touch files without changing them; only a new
+            // content hash is worth re-embedding.
+            match (&file.content_hash, fs::read_to_string(path)) {
+                (Some(hash), Ok(content)) => content_hash(&content) != *hash,
+                _ => true,
+            }
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+    stale.extend(
+        indexed
+            .keys()
+            .map(PathBuf::from)
+            .filter(|path| is_markdown(path) && !path.exists()),
+    );
+
+    sync_paths(kb_manager, root, stale, progress).await
+}
+
+/// What the database knows about an indexed file.
+struct IndexedFile {
+    /// Latest `updated_at` of the file's documents.
+    updated_at: Option<DateTime<Utc>>,
+    /// Hash of the file content last indexed, from the file's latest revision.
+    content_hash: Option<String>,
+}
+
+/// Paths of file-backed documents under `root`, with what was last indexed for each.
+async fn indexed_files(kb_manager: &KbManager, root: &Path) -> Result<HashMap<String, IndexedFile>, String> {
+    let prefix = format!("file:{}{}", root.to_string_lossy(), std::path::MAIN_SEPARATOR);
+    let conn = kb_manager.read_connection().await;
+    let mut stmt = conn
+        .prepare(
+            "WITH files AS (
+                 SELECT CASE WHEN instr(id, '#section:') > 0
+                             THEN substr(id, 6, instr(id, '#section:') - 6)
+                             ELSE substr(id, 6) END AS path,
+                        MAX(updated_at) AS updated_at
+                 FROM documents
+                 WHERE substr(id, 1, length(?1)) = ?1
+                 GROUP BY path
+             )
+             SELECT path, updated_at,
+                    (SELECT content_hash FROM document_revisions
+                     WHERE document_id = 'file:' || files.path ORDER BY id DESC LIMIT 1)
+             FROM files",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![prefix], |row| {
+            let updated_at: Option<String> = row.get(1)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                IndexedFile {
+                    updated_at: updated_at
+                        .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+                        .map(|t| t.with_timezone(&Utc)),
+                    content_hash: row.get(2)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn collect_markdown(root: &Path, dir: &Path, files: &mut Vec<(PathBuf, DateTime<Utc>)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if is_ignored_path(path.strip_prefix(root).unwrap_or(&path)) {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            collect_markdown(root, &path, files);
+        } else if file_type.is_file() && is_markdown(&path) {
+            let modified = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((path, modified.into()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knowledge_base::tests::{build_test_manager, TempDir};
+
+    #[tokio::test]
+    async fn leased_sync_waits_for_the_indexer_lease() -> Result<(), String> {
+        let kb = build_test_manager()?;
+        let dir = TempDir::new("reindex")?;
+        fs::write(dir.path().join("guide.md"), "# Guide\n\nHow to start.").map_err(|e| e.to_string())?;
+
+        {
+            let db = kb.db_lock();
+            let conn = db.lock().await;
+            lease::claim(&conn, INDEXER_LEASE, "app:other", LEASE_TTL)?;
+        }
+        let busy = sync_workspace_leased(&kb, "mcp:me", true, dir.path(), false, &Progress::none()).await?;
+        assert!(busy.is_none(), "another holder's live lease blocks the sync");
+
+        {
+            let db = kb.db_lock();
+            let conn = db.lock().await;
+            lease::release(&conn, INDEXER_LEASE, "app:other")?;
+        }
+        let summary = sync_workspace_leased(&kb, "mcp:me", true, dir.path(), false, &Progress::none())
+            .await?
+            .ok_or("the free lease should have been taken")?;
+        assert_eq!(summary.indexed, 1);
+
+        let db = kb.db_lock();
+        let mut conn = db.lock().await;
+        assert!(lease::try_acquire(&mut conn, INDEXER_LEASE, "app:other", LEASE_TTL)?, "released after the sync");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn touched_files_are_only_reindexed_when_their_content_changed() -> Result<(), String> {
+        let kb = build_test_manager()?;
+        let dir = TempDir::new("sync")?;
+        let path = dir.path().join("guide.md");
+        fs::write(&path, "# Guide\n\nHow to start.\n\n# Usage\n\nRun it.").map_err(|e| e.to_string())?;
+        assert_eq!(sync_workspace(&kb, dir.path(), false, &Progress::none()).await?.indexed, 1);
+
+        let touch = |path: &Path| -> Result<(), String> {
+            let later = SystemTime::now() + std::time::Duration::from_secs(60);
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_modified(later))
+                .map_err(|e| e.to_string())
+        };
+        touch(&path)?;
+        assert_eq!(sync_workspace(&kb, dir.path(), false, &Progress::none()).await?.indexed, 0);
+
+        fs::write(&path, "# Guide\n\nHow to begin.\n\n# Usage\n\nRun it.").map_err(|e| e.to_string())?;
+        touch(&path)?;
+        assert_eq!(sync_workspace(&kb, dir.path(), false, &Progress::none()).await?.indexed, 1);
+        assert_eq!(sync_workspace(&kb, dir.path(), true, &Progress::none()).await?.indexed, 1);
+        Ok(())
+    }
+}
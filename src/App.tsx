@@ -20,6 +20,9 @@ import { refreshLicenseStatusAtom } from './stores/license';
 import { useSyncRecentFoldersToDock } from './hooks/useSyncRecentFoldersToDock';
 import { useProjectStateSync } from './hooks/useProjectStateSync';
 import { LLMChatPanel } from './features/LLMChat';
+import { toast } from 'sonner';
+import { listen } from '@tauri-apps/api/event';
+import { dismissRecoveryReport, getRecoveryReport, type EmbedderStatus } from './api-client/knowledge-base';
 
 function LayoutRoute() {
   return (
@@ -50,6 +53,53 @@ function App() {
     refreshLicenseStatus();
   }, [refreshLicenseStatus]);
 
+  // Tell the user if the knowledge base had to be rebuilt from a corrupt file
+  useEffect(() => {
+    getRecoveryReport()
+      .then((report) => {
+        if (!report) return;
+        const rows = report.tables.reduce((sum, table) => sum + table.rows, 0);
+        const partial = report.tables
+          .filter((table) => !table.complete && table.table !== 'kb_meta')
+          .map((table) => table.table);
+        const quantizationReset = report.tables.some((table) => table.table === 'kb_meta' && !table.complete);
+        toast.warning('Knowledge base was repaired', {
+          description:
+            `The database was corrupt and has been rebuilt. ${rows} rows were recovered` +
+            (partial.length ? `; ${partial.join(', ')} could only be partly read.` : '.') +
+            (quantizationReset ? ' Vector quantization was reset to float.' : '') +
+            ` The damaged file was kept at ${report.quarantinedPath}.`,
+          duration: Infinity,
+          action: { label: 'Dismiss', onClick: () => void dismissRecoveryReport() },
+        });
+      })
+      .catch((error) => console.error('[App] Failed to read recovery report:', error));
+  }, []);
+
+  // The embedding server connects in the background; say so while it is unreachable
+  useEffect(() => {
+    const unlisten = listen<EmbedderStatus>('kb-embedder-status', ({ payload }) => {
+      if (payload.status === 'ready') {
+        toast.dismiss('kb-embedder-status');
+      } else if (payload.status === 'unavailable') {
+        toast.warning('Embedding server unavailable', {
+          id: 'kb-embedder-status',
+          description: `${payload.error} Retrying in ${payload.retryInSeconds}s.`,
+          duration: Infinity,
+        });
+      } else {
+        toast.error('Embedding model cannot be used', {
+          id: 'kb-embedder-status',
+          description: payload.error,
+          duration: Infinity,
+        });
+      }
+    });
+    return () => {
+      void unlisten.then((stop) => stop());
+    };
+  }, []);
+
   return (
     <ThemeProvider attribute="class" defaultTheme={savedTheme} enableSystem>
       <>
@@ -4,6 +4,11 @@ export interface KnowledgeGraphDocument {
   id: string;
   title: string;
   content: string;
+  createdAt: string | null;
+  updatedAt: string | null;
+  /** LLM-written summary, present once generated and while the content is unchanged. */
+  summary?: string;
+  keywords?: string[];
 }
 
 export interface KnowledgeGraphEdge {
@@ -69,6 +74,206 @@ export async function getProjectGraph(groupId: string): Promise<KnowledgeGraphDa
   return await invoke<KnowledgeGraphData>('get_project_graph', { groupId });
 }
 
+export interface KnowledgeGroup {
+  id: string;
+  title: string;
+  parentId: string | null;
+  documentCount: number;
+}
+
+export interface DeleteGroupResult {
+  deletedGroups: number;
+  deletedDocuments: number;
+}
+
+export async function listGroups(): Promise<KnowledgeGroup[]> {
+  return await invoke<KnowledgeGroup[]>('list_groups');
+}
+
+/** Replace a document's groups; upserts only ever add groups. Resolves to whether they changed. */
+export async function setDocumentGroups(documentId: string, groupIds: string[]): Promise<boolean> {
+  return await invoke<boolean>('set_document_groups', { documentId, groupIds });
+}
+
+export async function renameGroup(groupId: string, title: string): Promise<void> {
+  await invoke('rename_group', { groupId, title });
+}
+
+export async function mergeGroups(sourceId: string, targetId: string): Promise<void> {
+  await invoke('merge_groups', { sourceId, targetId });
+}
+
+export async function deleteGroup(
+  groupId: string,
+  deleteExclusiveDocuments = false
+): Promise<DeleteGroupResult> {
+  return await invoke<DeleteGroupResult>('delete_group', { groupId, deleteExclusiveDocuments });
+}
+
+/** Storage mode for chunk embeddings; quantized modes trade a little recall for a smaller index. */
+export type VectorQuantization = 'float' | 'int8' | 'binary';
+
+export async function getVectorQuantization(): Promise<VectorQuantization> {
+  return await invoke<VectorQuantization>('get_vector_quantization');
+}
+
+/** Rebuilds the vector index in the given mode from the stored vectors, without re-embedding. Returns the document count. */
+export async function setVectorQuantization(quantization: VectorQuantization): Promise<number> {
+  return await invoke<number>('set_vector_quantization', { quantization });
+}
+
+export async function getImageExtraction(): Promise<boolean> {
+  return await invoke<boolean>('get_image_extraction');
+}
+
+/** Enables OCR/SVG text indexing of images referenced from markdown (downloads the OCR models once). */
+export async function setImageExtraction(enabled: boolean): Promise<void> {
+  await invoke('set_image_extraction', { enabled });
+}
+
+export async function getAutoSummaries(): Promise<boolean> {
+  return await invoke<boolean>('get_auto_summaries');
+}
+
+/** Enables background summaries and keywords per document, written by the configured LLM provider. */
+export async function setAutoSummaries(enabled: boolean): Promise<void> {
+  await invoke('set_auto_summaries', { enabled });
+}
+
+export interface SalvagedTable {
+  table: string;
+  rows: number;
+  complete: boolean;
+  error: string | null;
+}
+
+/** Present after a corrupt database was quarantined and rebuilt, until dismissed. */
+export interface RecoveryReport {
+  recoveredAt: string;
+  reason: string;
+  quarantinedPath: string;
+  tables: SalvagedTable[];
+  /** `recover` when SQLite's `.recover` read the file, `copy` for the plain-query fallback. */
+  method: 'recover' | 'copy';
+  reembedPending: boolean;
+}
+
+/** Payload of `kb-embedder-status`, sent while the app connects to an embedding server. */
+export type EmbedderStatus =
+  | { status: 'ready' }
+  | { status: 'unavailable'; error: string; retryInSeconds: number }
+  | { status: 'error'; error: string };
+
+export interface KbBackup {
+  name: string;
+  path: string;
+  createdAt: string;
+  size: number;
+}
+
+export async function getRecoveryReport(): Promise<RecoveryReport | null> {
+  return await invoke<RecoveryReport | null>('get_recovery_report');
+}
+
+export async function dismissRecoveryReport(): Promise<void> {
+  return await invoke('dismiss_recovery_report');
+}
+
+export async function listKbBackups(): Promise<KbBackup[]> {
+  return await invoke<KbBackup[]>('list_kb_backups');
+}
+
+export async function createKbBackup(): Promise<KbBackup> {
+  return await invoke<KbBackup>('create_kb_backup');
+}
+
+/** The restore is applied on the next app start. */
+export async function restoreKbBackup(name: string): Promise<void> {
+  return await invoke('restore_kb_backup', { name });
+}
+
+export interface RecentDocument {
+  id: string;
+  title: string;
+  createdAt: string | null;
+  updatedAt: string | null;
+}
+
+export interface DocumentChange {
+  seq: number;
+  documentId: string;
+  changeType: 'upsert' | 'delete';
+  changedAt: string;
+  title: string | null;
+}
+
+export interface DocumentChangePage {
+  changes: DocumentChange[];
+  nextCursor: number;
+  hasMore: boolean;
+}
+
+export async function listRecentDocuments(
+  groupId: string,
+  since?: string,
+  limit = 20
+): Promise<RecentDocument[]> {
+  return await invoke<RecentDocument[]>('list_recent_documents', {
+    groupId,
+    since: since ?? null,
+    limit,
+  });
+}
+
+export async function getDocumentChanges(
+  cursor = 0,
+  limit = 100,
+  groupId?: string
+): Promise<DocumentChangePage> {
+  return await invoke<DocumentChangePage>('get_document_changes', {
+    cursor,
+    limit,
+    groupId: groupId ?? null,
+  });
+}
+
+export interface DocumentRevision {
+  id: number;
+  documentId: string;
+  title: string;
+  contentHash: string;
+  createdAt: string;
+  size: number;
+}
+
+export interface RevisionDiffLine {
+  kind: 'unchanged' | 'added' | 'removed';
+  text: string;
+}
+
+export interface RevisionDiff {
+  from: DocumentRevision;
+  to: DocumentRevision;
+  added: number;
+  removed: number;
+  lines: RevisionDiffLine[];
+}
+
+export async function listDocumentRevisions(documentId: string): Promise<DocumentRevision[]> {
+  return await invoke<DocumentRevision[]>('list_document_revisions', { documentId });
+}
+
+export async function diffDocumentRevisions(
+  fromRevisionId: number,
+  toRevisionId: number
+): Promise<RevisionDiff> {
+  return await invoke<RevisionDiff>('diff_document_revisions', { fromRevisionId, toRevisionId });
+}
+
+export async function restoreDocumentRevision(revisionId: number): Promise<string> {
+  return await invoke<string>('restore_document_revision', { revisionId });
+}
+
 export async function connectDocuments(
   sourceId: string,
   targetId: string,
@@ -89,14 +294,103 @@ export async function searchSimilar(query: string, limit = 20): Promise<Knowledg
   return await invoke<KnowledgeSearchResult[]>('search_similar', { query, limit });
 }
 
+export interface HybridSearchResult {
+  documentId: string;
+  title: string;
+  content: string;
+  score: number;
+  matchedChunks: string[];
+  summary?: string;
+  keywords?: string[];
+  /** Most common detected language of the document's chunks. */
+  language?: string;
+}
+
+/** LLM query rewriting: alternative queries ("expand") or a hypothetical answer passage ("hyde"). */
+export type QueryExpansion = 'expand' | 'hyde';
+
+/** ISO 639-1 code (e.g. "en", "vi") to keep only ("filter") or rank first ("boost", the default). */
+export interface LanguagePreference {
+  language: string;
+  mode?: 'filter' | 'boost';
+}
+
+/** Hybrid keyword + semantic search. Pass `groupIds` to search several workspaces at once. */
+export async function searchHybrid(
+  query: string,
+  limit = 20,
+  groupIds?: string[],
+  expansion?: QueryExpansion,
+  language?: LanguagePreference
+): Promise<HybridSearchResult[]> {
+  return await invoke<HybridSearchResult[]>('search_hybrid', {
+    query,
+    limit,
+    groupIds: groupIds ?? null,
+    expansion: expansion ?? null,
+    language: language?.language ?? null,
+    languageMode: language?.mode ?? null,
+  });
+}
+
+export interface ChunkLocation {
+  chunkId: string;
+  documentId: string;
+  title: string;
+}
+
+export interface DuplicateChunkGroup {
+  contentHash: string;
+  preview: string;
+  occurrences: ChunkLocation[];
+}
+
+export interface NearDuplicatePair {
+  documentA: string;
+  titleA: string;
+  documentB: string;
+  titleB: string;
+  /** Percentage of A's chunks with a near-identical chunk in B. */
+  overlapA: number;
+  overlapB: number;
+  similarity: number;
+}
+
+export interface DuplicateReport {
+  groupId: string;
+  similarityThreshold: number;
+  minOverlap: number;
+  exactDuplicates: DuplicateChunkGroup[];
+  nearDuplicates: NearDuplicatePair[];
+}
+
+/**
+ * Find repeated chunks and near-duplicate documents in a group. `similarityThreshold`
+ * is the chunk cosine similarity (default 0.92); `minOverlap` the share of a document's
+ * chunks that must match (default 0.5).
+ */
+export async function findDuplicates(
+  groupId: string,
+  similarityThreshold?: number,
+  minOverlap?: number
+): Promise<DuplicateReport> {
+  return await invoke<DuplicateReport>('find_duplicates', {
+    groupId,
+    similarityThreshold: similarityThreshold ?? null,
+    minOverlap: minOverlap ?? null,
+  });
+}
+
 export async function rebuildAllEdges(): Promise<void> {
   await invoke('rebuild_all_edges');
 }
 
 export interface CurrentModelStatus {
-  modelType: 'local' | 'remote';
+  modelType: 'local' | 'remote' | 'server' | 'gguf';
   modelName: string;
   openaiKey?: string;
+  /** Base URL of the OpenAI-compatible server when `modelType` is `'server'`. */
+  endpoint?: string | null;
   isDownloaded: boolean;
 }
 
@@ -108,12 +402,14 @@ export async function updateEmbeddingModelAndReindex(
   modelType: string,
   modelName: string,
   openaiKey?: string,
-  workspaceRoot?: string
+  workspaceRoot?: string,
+  endpoint?: string
 ): Promise<number> {
   return await invoke<number>('update_embedding_model_and_reindex', {
     modelType,
     modelName,
     openaiKey: openaiKey || null,
+    endpoint: endpoint || null,
     workspaceRoot: workspaceRoot || '',
   });
 }
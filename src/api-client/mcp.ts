@@ -5,3 +5,26 @@ export const getMcpServerPaths = (): Promise<string[]> =>
 
 export const checkMcpConfigStatus = (agentId: string, workspaceRoot: string | null): Promise<boolean> =>
   invoke("check_mcp_config_status", { agentId, workspaceRoot });
+
+export type McpConfigScope = "user" | "workspace";
+
+export interface McpConfigChange {
+  path: string;
+  backupPath: string | null;
+  changed: boolean;
+}
+
+export const writeMcpClientConfig = (
+  agentId: string,
+  scope: McpConfigScope,
+  workspaceRoot: string | null,
+  serverPath: string
+): Promise<McpConfigChange> =>
+  invoke("write_mcp_client_config", { agentId, scope, workspaceRoot, serverPath });
+
+export const removeMcpClientConfig = (
+  agentId: string,
+  scope: McpConfigScope,
+  workspaceRoot: string | null
+): Promise<McpConfigChange> =>
+  invoke("remove_mcp_client_config", { agentId, scope, workspaceRoot });
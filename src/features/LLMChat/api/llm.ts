@@ -24,12 +24,23 @@ export interface LlmProviderStatus {
   model_name: string | null;
 }
 
+/** Knowledge-base passage retrieved for a user turn; `citation` is the `file:line` label the model cites. */
+export interface ChatSource {
+  document_id: string;
+  title: string;
+  path?: string;
+  line?: number;
+  citation: string;
+  excerpt: string;
+}
+
 export interface ChatMessage {
   role: "system" | "user" | "assistant" | "tool";
   content: string;
   name?: string;
   tool_call_id?: string;
   attachedFiles?: Array<{ name: string; path: string }>;
+  sources?: ChatSource[];
 }
 
 export interface GgufModelInfo {
@@ -59,6 +70,7 @@ export interface ChatSession {
     name?: string;
     tool_input?: unknown;
     tool_output?: unknown;
+    sources?: ChatSource[];
   }>;
 }
 
@@ -92,6 +104,7 @@ export const sendChatMessage = (
   messages: ChatMessage[],
   workspaceRoot: string,
   sessionId: string,
+  useKnowledgeBase = false,
 ): Promise<void> => {
   const cleanedMessages = messages.map(({ role, content, name, tool_call_id }) => ({
     role,
@@ -103,6 +116,7 @@ export const sendChatMessage = (
     messages: cleanedMessages,
     workspaceRoot,
     sessionId,
+    useKnowledgeBase,
   });
 };
 
@@ -160,6 +174,9 @@ export const onLlmToolCallPending = (
 ): Promise<UnlistenFn> =>
   listen<ToolCallPendingEvent>("llm-tool-call-pending", (e) => cb(e.payload));
 
+export const onLlmSources = (cb: (sources: ChatSource[]) => void): Promise<UnlistenFn> =>
+  listen<ChatSource[]>("llm-sources", (e) => cb(e.payload));
+
 export const onLlmDone = (cb: () => void): Promise<UnlistenFn> =>
   listen("llm-done", () => cb());
 
@@ -374,7 +374,7 @@ export function LLMChatInput({ onSend, onStop, isGenerating, disabled }: LLMChat
       </div>
       <div className="flex justify-between items-center mt-1 px-1">
         <p className="text-[10px] text-muted-foreground/60">
-          Tools: read_file · write_file · list_directory · run_shell · web_search
+          Tools: read_file · write_file · list_directory · run_shell · web_search · kb_search
         </p>
         <div className="flex items-center gap-2">
           <span className={`text-[10px] ${tokenColorClass}`} title="Estimated tokens">
@@ -1,4 +1,4 @@
-import { User, FileCode } from "lucide-react";
+import { User, FileCode, BookOpen } from "lucide-react";
 import type { ChatMessage, ToolCallPendingEvent } from "../api/llm";
 import { LLMChatToolCall } from "./LLMChatToolCall";
 
@@ -82,6 +82,20 @@ export function LLMChatMessage({ message, isStreaming, activeToolCall }: LLMChat
             </div>
           )}
         </div>
+        {message.sources && message.sources.length > 0 && (
+          <div className="flex flex-wrap justify-end gap-1.5 max-w-[85%]">
+            {message.sources.map((source, idx) => (
+              <div
+                key={idx}
+                className="flex items-center gap-1 px-1.5 py-0.5 rounded border border-border/60 bg-muted/40 text-[10px] text-muted-foreground"
+                title={`${source.title}\n\n${source.excerpt}`}
+              >
+                <BookOpen className="h-3 w-3" />
+                <span className="truncate max-w-[180px]">{source.citation}</span>
+              </div>
+            ))}
+          </div>
+        )}
       </div>
     </div>
   );
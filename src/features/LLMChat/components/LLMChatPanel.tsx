@@ -1,6 +1,6 @@
 import { useCallback, useRef } from "react";
 import { useAtom, useAtomValue } from "jotai";
-import { Bot, X, Plus, Trash2 } from "lucide-react";
+import { Bot, X, Plus, Trash2, BookOpen } from "lucide-react";
 import { toast } from "sonner";
 
 import { Button } from "@/components/ui/button";
@@ -17,6 +17,7 @@ import {
   llmProviderStatusAtom,
   generateSessionId,
   taggedFilesAtom,
+  groundInKnowledgeBaseAtom,
 } from "../store/LLMChatStore";
 import { useLlmStream } from "../hooks/useLlmStream";
 import { LLMChatMessage } from "./LLMChatMessage";
@@ -32,6 +33,7 @@ export function LLMChatPanel() {
   const providerStatus = useAtomValue(llmProviderStatusAtom);
   const workspaceRoot = useAtomValue(workspaceRootAtom);
   const [taggedFiles, setTaggedFiles] = useAtom(taggedFilesAtom);
+  const [groundInKnowledgeBase, setGroundInKnowledgeBase] = useAtom(groundInKnowledgeBaseAtom);
 
   const messagesEndRef = useRef<HTMLDivElement>(null);
 
@@ -66,13 +68,13 @@ export function LLMChatPanel() {
       setTaggedFiles([]); // Clear tag pills in UI
 
       try {
-        await sendChatMessage(newMessages, workspaceRoot, sessionId);
+        await sendChatMessage(newMessages, workspaceRoot, sessionId, groundInKnowledgeBase);
       } catch (err) {
         setIsGenerating(false);
         toast.error(`Failed to send message: ${String(err)}`);
       }
     },
-    [messages, setMessages, setIsGenerating, workspaceRoot, sessionId, taggedFiles, setTaggedFiles],
+    [messages, setMessages, setIsGenerating, workspaceRoot, sessionId, taggedFiles, setTaggedFiles, groundInKnowledgeBase],
   );
 
   const handleStop = useCallback(async () => {
@@ -119,6 +121,15 @@ export function LLMChatPanel() {
         </div>
 
         <div className="flex items-center gap-1">
+          <Button
+            variant="ghost"
+            size="icon"
+            className={`h-6 w-6 cursor-pointer ${groundInKnowledgeBase ? "text-primary bg-primary/10" : "text-muted-foreground hover:text-foreground"}`}
+            onClick={() => setGroundInKnowledgeBase(!groundInKnowledgeBase)}
+            title={groundInKnowledgeBase ? "Answering from knowledge base (click to disable)" : "Answer from knowledge base"}
+          >
+            <BookOpen className="h-3.5 w-3.5" />
+          </Button>
           <Button
             variant="ghost"
             size="icon"
@@ -8,6 +8,7 @@ import {
   onLlmDone,
   onLlmError,
   onLlmToolCallPending,
+  onLlmSources,
   executeLlmTool,
   sendChatMessage,
 } from "../api/llm";
@@ -17,12 +18,13 @@ import {
   isGeneratingAtom,
   activeToolCallAtom,
   currentSessionIdAtom,
+  groundInKnowledgeBaseAtom,
 } from "../store/LLMChatStore";
 import { workspaceRootAtom } from "@/features/FileExplorer/store";
 
 /**
  * Manages the full LLM streaming lifecycle:
- * - Subscribes to llm-token, llm-tool-call-pending, llm-done, llm-error
+ * - Subscribes to llm-token, llm-tool-call-pending, llm-sources, llm-done, llm-error
  * - Appends streamed tokens to the last assistant message
  * - Executes pending tool calls and injects results as tool messages
  * - Cleans up all listeners on unmount
@@ -34,6 +36,7 @@ export function useLlmStream() {
   const workspaceRoot = useAtomValue(workspaceRootAtom);
   const sessionId = useAtomValue(currentSessionIdAtom);
   const isGenerating = useAtomValue(isGeneratingAtom);
+  const groundInKnowledgeBase = useAtomValue(groundInKnowledgeBaseAtom);
 
   // Keep a mutable ref of the streamed tokens to avoid React batching/staleness during stream completion
   const assistantTextRef = useRef("");
@@ -54,9 +57,15 @@ export function useLlmStream() {
     sessionIdRef.current = sessionId;
   }, [sessionId]);
 
+  const groundInKnowledgeBaseRef = useRef(groundInKnowledgeBase);
+  useEffect(() => {
+    groundInKnowledgeBaseRef.current = groundInKnowledgeBase;
+  }, [groundInKnowledgeBase]);
+
   useEffect(() => {
     let unlistenToken: (() => void) | null = null;
     let unlistenTool: (() => void) | null = null;
+    let unlistenSources: (() => void) | null = null;
     let unlistenDone: (() => void) | null = null;
     let unlistenError: (() => void) | null = null;
 
@@ -76,6 +85,17 @@ export function useLlmStream() {
         });
       });
 
+      unlistenSources = await onLlmSources((sources) => {
+        // Sources belong to the user turn they were retrieved for
+        setMessages((prev) => {
+          const index = prev.map((m) => m.role).lastIndexOf("user");
+          if (index === -1) return prev;
+          const next = [...prev];
+          next[index] = { ...next[index], sources };
+          return next;
+        });
+      });
+
       unlistenTool = await onLlmToolCallPending(
         async (event: ToolCallPendingEvent) => {
           setActiveToolCall(event);
@@ -108,7 +128,7 @@ export function useLlmStream() {
           // Recursively call sendChatMessage to submit the tool execution output back to the LLM
           try {
             const currentSession = sessionIdRef.current;
-            await sendChatMessage(nextMessages, wsRoot, currentSession);
+            await sendChatMessage(nextMessages, wsRoot, currentSession, groundInKnowledgeBaseRef.current);
           } catch (err) {
             setIsGenerating(false);
             console.error("Failed to continue response after tool call:", err);
@@ -165,6 +185,7 @@ export function useLlmStream() {
     return () => {
       unlistenToken?.();
       unlistenTool?.();
+      unlistenSources?.();
       unlistenDone?.();
       unlistenError?.();
     };
@@ -20,6 +20,9 @@ export const currentSessionIdAtom = atom<string>(generateSessionId());
 /** All messages in the current session */
 export const chatMessagesAtom = atom<ChatMessage[]>([]);
 
+/** Ground each user turn in the current project's knowledge base */
+export const groundInKnowledgeBaseAtom = atom(false);
+
 /** Whether the LLM is currently generating */
 export const isGeneratingAtom = atom(false);
 
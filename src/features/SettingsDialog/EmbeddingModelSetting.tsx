@@ -15,6 +15,7 @@ import {
   RefreshCw,
   ExternalLink,
   Trash2,
+  Server,
 } from "lucide-react";
 import { openUrl } from "@tauri-apps/plugin-opener";
 import { listen } from "@tauri-apps/api/event";
@@ -29,6 +30,7 @@ import {
   deleteEmbeddingModel,
   getModelDownloadSize,
 } from "@/api-client/knowledge-base";
+import { scanLocalLlmModels, type GgufModelInfo } from "@/features/LLMChat/api/llm";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
@@ -37,8 +39,9 @@ import { workspaceRootAtom } from "@/features/FileExplorer/store";
 interface ModelInfo {
   id: string;
   name: string;
-  type: "local" | "remote";
-  dims: number;
+  type: "local" | "remote" | "server" | "gguf";
+  /** Server models report their size at connect time, so this is a label there. */
+  dims: number | string;
   sizeMb?: number;
   languages: string;
   description: string;
@@ -157,6 +160,17 @@ const LOCAL_MODELS: ModelInfo[] = [
     url: "https://huggingface.co/Xenova/paraphrase-multilingual-MiniLM-L12-v2",
     downloadUrl: "https://huggingface.co/Xenova/paraphrase-multilingual-MiniLM-L12-v2/resolve/main/onnx/model.onnx",
   },
+  {
+    id: "paraphrase-multilingual-mpnet-base-v2",
+    name: "paraphrase-multilingual-mpnet-base-v2",
+    type: "local",
+    dims: 768,
+    sizeMb: 1110,
+    languages: "Multilingual (50+)",
+    description: "Larger multilingual sentence transformer. Better cross-language matching, e.g. English queries over Vietnamese docs.",
+    url: "https://huggingface.co/Xenova/paraphrase-multilingual-mpnet-base-v2",
+    downloadUrl: "https://huggingface.co/Xenova/paraphrase-multilingual-mpnet-base-v2/resolve/main/onnx/model.onnx",
+  },
   {
     id: "bge-small-zh-v1.5",
     name: "bge-small-zh-v1.5",
@@ -211,19 +225,28 @@ const REMOTE_MODELS: ModelInfo[] = [
   },
 ];
 
+type EmbeddingTab = "local" | "remote" | "server" | "gguf";
+
+/** Ollama's default address; LM Studio listens on http://localhost:1234. */
+const DEFAULT_SERVER_ENDPOINT = "http://localhost:11434";
+
 export function EmbeddingModelSetting(): JSX.Element {
   const workspaceRoot = useAtomValue(workspaceRootAtom);
-  const [activeTab, setActiveTab] = useState<"local" | "remote">("local");
+  const [activeTab, setActiveTab] = useState<EmbeddingTab>("local");
   const [currentModel, setCurrentModel] = useState<{
     type: string;
     name: string;
     key?: string;
+    endpoint?: string;
     isDownloaded: boolean;
   } | null>(null);
 
   const [selectedModel, setSelectedModel] = useState<string>("");
   const [openaiKey, setOpenaiKey] = useState<string>("");
   const [showKey, setShowKey] = useState<boolean>(false);
+  const [serverEndpoint, setServerEndpoint] = useState<string>(DEFAULT_SERVER_ENDPOINT);
+  const [serverKey, setServerKey] = useState<string>("");
+  const [ggufModels, setGgufModels] = useState<GgufModelInfo[]>([]);
 
   const [isLoading, setIsLoading] = useState<boolean>(true);
   const [isReindexing, setIsReindexing] = useState<boolean>(false);
@@ -267,6 +290,7 @@ export function EmbeddingModelSetting(): JSX.Element {
             type: res.modelType,
             name: res.modelName,
             key: res.openaiKey,
+            endpoint: res.endpoint ?? undefined,
             isDownloaded: res.isDownloaded,
           });
           if (res.isDownloaded) {
@@ -275,7 +299,10 @@ export function EmbeddingModelSetting(): JSX.Element {
           } else {
             setSelectedModel(""); // Unselect model if not downloaded!
           }
-          if (res.openaiKey) {
+          if (res.modelType === "server") {
+            setServerEndpoint(res.endpoint || DEFAULT_SERVER_ENDPOINT);
+            setServerKey(res.openaiKey ?? "");
+          } else if (res.openaiKey) {
             setOpenaiKey(res.openaiKey);
           }
         }
@@ -297,9 +324,20 @@ export function EmbeddingModelSetting(): JSX.Element {
         console.error("Failed to fetch cache directory:", err);
       }
     };
+    const fetchGgufModels = async () => {
+      try {
+        const models = await scanLocalLlmModels();
+        if (active) {
+          setGgufModels(models);
+        }
+      } catch (err) {
+        console.error("Failed to scan GGUF models:", err);
+      }
+    };
     void fetchCurrentModel();
     void fetchDownloaded();
     void fetchCacheDirectory();
+    void fetchGgufModels();
     return () => {
       active = false;
     };
@@ -315,12 +353,25 @@ export function EmbeddingModelSetting(): JSX.Element {
       return;
     }
 
+    if (activeTab === "server" && (!serverEndpoint.trim() || !modelId.trim())) {
+      toast.error("Please enter the server URL and the model name.");
+      return;
+    }
+
+    if (activeTab === "gguf" && !modelId) {
+      toast.error("Please select a GGUF model.");
+      return;
+    }
+
     if (!workspaceRoot) {
       toast.error("No active workspace. Please open a folder first.");
       return;
     }
 
-    const isDownloaded = activeTab === "remote" || downloadedModels.includes(modelId);
+    const isDownloaded = activeTab !== "local" || downloadedModels.includes(modelId);
+    const apiKey =
+      activeTab === "remote" ? openaiKey : activeTab === "server" ? serverKey.trim() || undefined : undefined;
+    const endpoint = activeTab === "server" ? serverEndpoint.trim() : undefined;
 
     setIsReindexing(true);
     setSelectedModel(modelId);
@@ -351,8 +402,9 @@ export function EmbeddingModelSetting(): JSX.Element {
       const count = await updateEmbeddingModelAndReindex(
         activeTab,
         modelId,
-        activeTab === "remote" ? openaiKey : undefined,
-        workspaceRoot
+        apiKey,
+        workspaceRoot,
+        endpoint
       );
 
       setDownloadPercent(null);
@@ -360,7 +412,8 @@ export function EmbeddingModelSetting(): JSX.Element {
       setCurrentModel({
         type: activeTab,
         name: modelId,
-        key: activeTab === "remote" ? openaiKey : undefined,
+        key: apiKey,
+        endpoint,
         isDownloaded: true,
       });
       void fetchDownloaded();
@@ -441,6 +494,8 @@ export function EmbeddingModelSetting(): JSX.Element {
     currentModel?.type !== activeTab ||
     currentModel?.name !== selectedModel ||
     (activeTab === "remote" && currentModel?.key !== openaiKey) ||
+    (activeTab === "server" &&
+      (currentModel?.endpoint !== serverEndpoint.trim() || (currentModel?.key ?? "") !== serverKey.trim())) ||
     currentModel?.isDownloaded === false;
 
   if (isLoading) {
@@ -452,9 +507,28 @@ export function EmbeddingModelSetting(): JSX.Element {
   }
 
   const activeModels = activeTab === "local" ? LOCAL_MODELS : REMOTE_MODELS;
-  const currentActiveModelInfo = [...LOCAL_MODELS, ...REMOTE_MODELS].find(
-    (m) => m.id === currentModel?.name
-  );
+  const currentActiveModelInfo: ModelInfo | undefined =
+    currentModel?.type === "server"
+      ? {
+        id: currentModel.name,
+        name: currentModel.name,
+        description: `Served by ${currentModel.endpoint ?? "a local OpenAI-compatible server"}.`,
+        dims: "probed from server",
+        languages: "Model dependent",
+        type: "server",
+        url: "",
+      }
+      : currentModel?.type === "gguf"
+        ? {
+          id: currentModel.name,
+          name: currentModel.name,
+          description: "GGUF embedding model run through llama.cpp from the LLM models folder.",
+          dims: "read from model",
+          languages: "Model dependent",
+          type: "gguf",
+          url: "",
+        }
+        : [...LOCAL_MODELS, ...REMOTE_MODELS].find((m) => m.id === currentModel?.name);
 
   return (
     <div className="flex flex-col h-full overflow-hidden space-y-6">
@@ -484,6 +558,20 @@ export function EmbeddingModelSetting(): JSX.Element {
                           <AlertTriangle className="w-3 h-3 text-yellow-500" /> Offline (Not Downloaded)
                         </span>
                       )
+                    ) : currentActiveModelInfo.type === "gguf" ? (
+                      currentModel?.isDownloaded ? (
+                        <span className="text-xs px-2 py-0.5 rounded-full bg-secondary font-medium text-secondary-foreground flex items-center gap-1">
+                          <Cpu className="w-3 h-3" /> Offline (GGUF)
+                        </span>
+                      ) : (
+                        <span className="text-xs px-2 py-0.5 rounded-full bg-yellow-500/20 text-yellow-800 dark:text-yellow-800 border border-yellow-500/30 font-medium flex items-center gap-1 animate-pulse">
+                          <AlertTriangle className="w-3 h-3 text-yellow-500" /> Offline (File Missing)
+                        </span>
+                      )
+                    ) : currentActiveModelInfo.type === "server" ? (
+                      <span className="text-xs px-2 py-0.5 rounded-full bg-secondary font-medium text-secondary-foreground flex items-center gap-1">
+                        <Server className="w-3 h-3" /> Local Server
+                      </span>
                     ) : (
                       <span className="text-xs px-2 py-0.5 rounded-full bg-secondary font-medium text-secondary-foreground flex items-center gap-1">
                         <Sparkles className="w-3 h-3" /> Online (Remote)
@@ -560,7 +648,7 @@ export function EmbeddingModelSetting(): JSX.Element {
           </div>
         </div>
         {/* Model Type Selector */}
-        <div className="grid grid-cols-2 bg-muted p-1 rounded-lg text-muted-foreground gap-1 max-w-sm">
+        <div className="grid grid-cols-4 bg-muted p-1 rounded-lg text-muted-foreground gap-1 max-w-2xl">
           <button
             type="button"
             disabled={isBusy}
@@ -599,182 +687,309 @@ export function EmbeddingModelSetting(): JSX.Element {
             <Sparkles className="w-3.5 h-3.5" />
             <span>Remote Models (Online)</span>
           </button>
+          <button
+            type="button"
+            disabled={isBusy}
+            className={`flex items-center justify-center gap-2 rounded-md py-1.5 text-xs font-medium transition-all cursor-pointer ${activeTab === "server"
+              ? "bg-background text-foreground shadow-sm font-semibold"
+              : "text-muted-foreground hover:text-foreground hover:bg-background/20"
+              }`}
+            onClick={() => {
+              setActiveTab("server");
+              setSelectedModel(currentModel?.type === "server" ? currentModel.name : "");
+            }}
+          >
+            <Server className="w-3.5 h-3.5" />
+            <span>Local Server</span>
+          </button>
+          <button
+            type="button"
+            disabled={isBusy}
+            className={`flex items-center justify-center gap-2 rounded-md py-1.5 text-xs font-medium transition-all cursor-pointer ${activeTab === "gguf"
+              ? "bg-background text-foreground shadow-sm font-semibold"
+              : "text-muted-foreground hover:text-foreground hover:bg-background/20"
+              }`}
+            onClick={() => {
+              setActiveTab("gguf");
+              setSelectedModel(currentModel?.type === "gguf" && currentModel.isDownloaded ? currentModel.name : "");
+            }}
+          >
+            <Cpu className="w-3.5 h-3.5" />
+            <span>GGUF (llama.cpp)</span>
+          </button>
         </div>
 
         {/* Models List Table */}
-        <div className="border border-border rounded-lg overflow-hidden bg-card">
-          <div className="overflow-x-auto">
-            <table className="w-full text-left border-collapse">
-              <thead>
-                <tr className="border-b border-border bg-muted/40 text-xs font-medium text-muted-foreground">
-                  <th className="py-3 px-4 w-10"></th>
-                  <th className="py-3 px-4">Model Name</th>
-                  <th className="py-3 px-4 text-center">Dims</th>
-                  <th className="py-3 px-4 text-center">Languages</th>
-                  <th className="py-3 px-4 text-center">Disk Size</th>
-                  <th className="py-3 px-4 text-center w-24">Status</th>
-                  <th className="py-3 px-4 text-center w-24">Re-index</th>
-                </tr>
-              </thead>
-              <tbody className="divide-y divide-border text-xs">
-                {activeModels.map((model) => {
-                  const isSelected = selectedModel === model.id;
-                  const isActive = currentModel?.name === model.id && currentModel?.type === activeTab;
-                  return (
-                    <tr
-                      key={model.id}
-                      className={`hover:bg-muted/30 transition-colors cursor-pointer ${isSelected ? "bg-primary/5 dark:bg-primary/10" : ""
-                        }`}
-                      onClick={() => !isBusy && handleSelectModel(model)}
-                    >
-                      <td className="py-3 px-4 text-center">
-                        <div
-                          className={`w-4 h-4 rounded-full border flex items-center justify-center transition-all ${isSelected
-                            ? "border-primary bg-primary text-primary-foreground"
-                            : "border-muted-foreground/30 bg-transparent"
-                            }`}
-                        >
-                          {isSelected && <Check className="w-3 h-3 stroke-[3]" />}
-                        </div>
-                      </td>
-                      <td className="py-3 px-4 text-foreground">
-                        <div className="flex items-center gap-2">
-                          <span className="font-semibold">{model.name}</span>
-                          {model.url && (
-                            <button
-                              type="button"
-                              onClick={async (e) => {
-                                e.stopPropagation();
-                                try {
-                                  await openUrl(model.url);
-                                } catch (err) {
-                                  console.error("Failed to open model URL:", err);
-                                  toast.error("Failed to open link in browser");
-                                }
-                              }}
-                              className="text-muted-foreground/60 hover:text-primary hover:bg-muted/80 p-0.5 rounded transition-all cursor-pointer inline-flex items-center justify-center"
-                              title="Open download source page in browser"
-                            >
-                              <ExternalLink className="w-3.5 h-3.5" />
-                            </button>
-                          )}
-                          {(() => {
-                            const dlUrl = model.downloadUrl;
-                            if (dlUrl) {
-                              return (
-                                <button
-                                  type="button"
-                                  onClick={async (e) => {
-                                    e.stopPropagation();
-                                    try {
-                                      await openUrl(dlUrl);
-                                    } catch (err) {
-                                      console.error("Failed to open download URL:", err);
-                                      toast.error("Failed to open link in browser");
-                                    }
-                                  }}
-                                  className="text-muted-foreground/60 hover:text-primary hover:bg-muted/80 p-0.5 rounded transition-all cursor-pointer inline-flex items-center justify-center"
-                                  title="Download model weights file directly in browser"
-                                >
-                                  <Download className="w-3.5 h-3.5" />
-                                </button>
-                              );
-                            }
-                            return null;
-                          })()}
-                          {isActive && (
-                            <span className={`text-[10px] px-1.5 py-0.2 rounded border font-medium ${currentModel?.isDownloaded
-                              ? "bg-green-500/20 text-green-600 dark:text-green-400 border-green-500/30"
-                              : "bg-yellow-500/20 text-yellow-600 dark:text-yellow-400 border-yellow-500/30"
-                              }`}>
-                              {currentModel?.isDownloaded ? "Active" : "Active (Missing weights)"}
-                            </span>
-                          )}
-                        </div>
-                        <p className="text-[11px] text-muted-foreground font-normal mt-0.5 max-w-md">
-                          {model.description}
-                        </p>
-                      </td>
-                      <td className="py-3 px-4 text-center font-medium text-muted-foreground">
-                        {model.dims}
-                      </td>
-                      <td className="py-3 px-4 text-center font-medium text-muted-foreground">
-                        {model.languages}
-                      </td>
-                      <td className="py-3 px-4 text-center text-muted-foreground font-medium">
-                        {actualSizes[model.id]
-                          ? `${(actualSizes[model.id] / (1024 * 1024)).toFixed(1)} MB`
-                          : model.sizeMb
-                            ? `${model.sizeMb} MB`
-                            : "N/A"}
-                      </td>
-                      <td className="py-3 px-4 text-center group/status relative">
-                        {model.type === "local" ? (
-                          downloadedModels.includes(model.id) ? (
-                            isActive ? (
-                              <div className="flex items-center justify-center text-green-500" title="Downloaded & Active">
-                                <CheckCircle2 className="w-4 h-4" />
-                              </div>
-                            ) : (
-                              <div className="flex items-center justify-center min-h-[28px]">
-                                <div className="group-hover/status:hidden text-green-500" title="Downloaded">
+        {(activeTab === "local" || activeTab === "remote") && (
+          <div className="border border-border rounded-lg overflow-hidden bg-card">
+            <div className="overflow-x-auto">
+              <table className="w-full text-left border-collapse">
+                <thead>
+                  <tr className="border-b border-border bg-muted/40 text-xs font-medium text-muted-foreground">
+                    <th className="py-3 px-4 w-10"></th>
+                    <th className="py-3 px-4">Model Name</th>
+                    <th className="py-3 px-4 text-center">Dims</th>
+                    <th className="py-3 px-4 text-center">Languages</th>
+                    <th className="py-3 px-4 text-center">Disk Size</th>
+                    <th className="py-3 px-4 text-center w-24">Status</th>
+                    <th className="py-3 px-4 text-center w-24">Re-index</th>
+                  </tr>
+                </thead>
+                <tbody className="divide-y divide-border text-xs">
+                  {activeModels.map((model) => {
+                    const isSelected = selectedModel === model.id;
+                    const isActive = currentModel?.name === model.id && currentModel?.type === activeTab;
+                    return (
+                      <tr
+                        key={model.id}
+                        className={`hover:bg-muted/30 transition-colors cursor-pointer ${isSelected ? "bg-primary/5 dark:bg-primary/10" : ""
+                          }`}
+                        onClick={() => !isBusy && handleSelectModel(model)}
+                      >
+                        <td className="py-3 px-4 text-center">
+                          <div
+                            className={`w-4 h-4 rounded-full border flex items-center justify-center transition-all ${isSelected
+                              ? "border-primary bg-primary text-primary-foreground"
+                              : "border-muted-foreground/30 bg-transparent"
+                              }`}
+                          >
+                            {isSelected && <Check className="w-3 h-3 stroke-[3]" />}
+                          </div>
+                        </td>
+                        <td className="py-3 px-4 text-foreground">
+                          <div className="flex items-center gap-2">
+                            <span className="font-semibold">{model.name}</span>
+                            {model.url && (
+                              <button
+                                type="button"
+                                onClick={async (e) => {
+                                  e.stopPropagation();
+                                  try {
+                                    await openUrl(model.url);
+                                  } catch (err) {
+                                    console.error("Failed to open model URL:", err);
+                                    toast.error("Failed to open link in browser");
+                                  }
+                                }}
+                                className="text-muted-foreground/60 hover:text-primary hover:bg-muted/80 p-0.5 rounded transition-all cursor-pointer inline-flex items-center justify-center"
+                                title="Open download source page in browser"
+                              >
+                                <ExternalLink className="w-3.5 h-3.5" />
+                              </button>
+                            )}
+                            {(() => {
+                              const dlUrl = model.downloadUrl;
+                              if (dlUrl) {
+                                return (
+                                  <button
+                                    type="button"
+                                    onClick={async (e) => {
+                                      e.stopPropagation();
+                                      try {
+                                        await openUrl(dlUrl);
+                                      } catch (err) {
+                                        console.error("Failed to open download URL:", err);
+                                        toast.error("Failed to open link in browser");
+                                      }
+                                    }}
+                                    className="text-muted-foreground/60 hover:text-primary hover:bg-muted/80 p-0.5 rounded transition-all cursor-pointer inline-flex items-center justify-center"
+                                    title="Download model weights file directly in browser"
+                                  >
+                                    <Download className="w-3.5 h-3.5" />
+                                  </button>
+                                );
+                              }
+                              return null;
+                            })()}
+                            {isActive && (
+                              <span className={`text-[10px] px-1.5 py-0.2 rounded border font-medium ${currentModel?.isDownloaded
+                                ? "bg-green-500/20 text-green-600 dark:text-green-400 border-green-500/30"
+                                : "bg-yellow-500/20 text-yellow-600 dark:text-yellow-400 border-yellow-500/30"
+                                }`}>
+                                {currentModel?.isDownloaded ? "Active" : "Active (Missing weights)"}
+                              </span>
+                            )}
+                          </div>
+                          <p className="text-[11px] text-muted-foreground font-normal mt-0.5 max-w-md">
+                            {model.description}
+                          </p>
+                        </td>
+                        <td className="py-3 px-4 text-center font-medium text-muted-foreground">
+                          {model.dims}
+                        </td>
+                        <td className="py-3 px-4 text-center font-medium text-muted-foreground">
+                          {model.languages}
+                        </td>
+                        <td className="py-3 px-4 text-center text-muted-foreground font-medium">
+                          {actualSizes[model.id]
+                            ? `${(actualSizes[model.id] / (1024 * 1024)).toFixed(1)} MB`
+                            : model.sizeMb
+                              ? `${model.sizeMb} MB`
+                              : "N/A"}
+                        </td>
+                        <td className="py-3 px-4 text-center group/status relative">
+                          {model.type === "local" ? (
+                            downloadedModels.includes(model.id) ? (
+                              isActive ? (
+                                <div className="flex items-center justify-center text-green-500" title="Downloaded & Active">
                                   <CheckCircle2 className="w-4 h-4" />
                                 </div>
-                                <button
-                                  type="button"
-                                  onClick={(e) => {
-                                    e.stopPropagation();
-                                    void handleDeleteModel(model.id);
-                                  }}
-                                  className="hidden group-hover/status:inline-flex p-1 rounded hover:bg-red-500/10 text-red-500 transition-colors cursor-pointer items-center justify-center"
-                                  title="Delete model weights"
-                                  disabled={isBusy}
-                                >
-                                  <Trash2 className="w-4 h-4" />
-                                </button>
-                              </div>
+                              ) : (
+                                <div className="flex items-center justify-center min-h-[28px]">
+                                  <div className="group-hover/status:hidden text-green-500" title="Downloaded">
+                                    <CheckCircle2 className="w-4 h-4" />
+                                  </div>
+                                  <button
+                                    type="button"
+                                    onClick={(e) => {
+                                      e.stopPropagation();
+                                      void handleDeleteModel(model.id);
+                                    }}
+                                    className="hidden group-hover/status:inline-flex p-1 rounded hover:bg-red-500/10 text-red-500 transition-colors cursor-pointer items-center justify-center"
+                                    title="Delete model weights"
+                                    disabled={isBusy}
+                                  >
+                                    <Trash2 className="w-4 h-4" />
+                                  </button>
+                                </div>
+                              )
+                            ) : (
+                              <button
+                                type="button"
+                                onClick={(e) => {
+                                  e.stopPropagation();
+                                  void handleDownloadOnly(model.id);
+                                }}
+                                className="p-1 rounded hover:bg-muted/80 text-muted-foreground hover:text-foreground transition-colors cursor-pointer inline-flex items-center justify-center"
+                                title="Download model weights"
+                                disabled={isBusy}
+                              >
+                                <Download className="w-4 h-4" />
+                              </button>
                             )
                           ) : (
-                            <button
-                              type="button"
-                              onClick={(e) => {
-                                e.stopPropagation();
-                                void handleDownloadOnly(model.id);
-                              }}
-                              className="p-1 rounded hover:bg-muted/80 text-muted-foreground hover:text-foreground transition-colors cursor-pointer inline-flex items-center justify-center"
-                              title="Download model weights"
-                              disabled={isBusy}
-                            >
-                              <Download className="w-4 h-4" />
-                            </button>
-                          )
-                        ) : (
-                          <span className="text-muted-foreground/40">-</span>
-                        )}
-                      </td>
-                      <td className="py-3 px-4 text-center">
-                        <Button
-                          type="button"
-                          variant="ghost"
-                          size="icon"
-                          className="h-7 w-7 hover:bg-muted/80 text-muted-foreground hover:text-foreground cursor-pointer flex items-center justify-center mx-auto"
-                          title={`Re-index workspace using ${model.name}`}
-                          disabled={isBusy || !workspaceRoot}
-                          onClick={(e) => {
-                            e.stopPropagation();
-                            void handleDownloadModel(model.id);
-                          }}
-                        >
-                          <RefreshCw className={`w-3.5 h-3.5 ${isReindexing && selectedModel === model.id ? "animate-spin text-primary" : ""}`} />
-                        </Button>
-                      </td>
-                    </tr>
+                            <span className="text-muted-foreground/40">-</span>
+                          )}
+                        </td>
+                        <td className="py-3 px-4 text-center">
+                          <Button
+                            type="button"
+                            variant="ghost"
+                            size="icon"
+                            className="h-7 w-7 hover:bg-muted/80 text-muted-foreground hover:text-foreground cursor-pointer flex items-center justify-center mx-auto"
+                            title={`Re-index workspace using ${model.name}`}
+                            disabled={isBusy || !workspaceRoot}
+                            onClick={(e) => {
+                              e.stopPropagation();
+                              void handleDownloadModel(model.id);
+                            }}
+                          >
+                            <RefreshCw className={`w-3.5 h-3.5 ${isReindexing && selectedModel === model.id ? "animate-spin text-primary" : ""}`} />
+                          </Button>
+                        </td>
+                      </tr>
+                    );
+                  })}
+                </tbody>
+              </table>
+            </div>
+          </div>
+        )}
+
+        {/* GGUF Embedding Models (shared with the local LLM models folder) */}
+        {activeTab === "gguf" && (
+          <div className="space-y-2 pb-4">
+            <p className="text-xs text-muted-foreground leading-relaxed">
+              Embedding models in GGUF format (for example nomic-embed-text or bge) placed in the LLM models
+              folder. Download or add them from the AI model settings; chat models cannot be used here.
+            </p>
+            {ggufModels.length === 0 ? (
+              <p className="text-xs text-muted-foreground">No GGUF models found.</p>
+            ) : (
+              <div className="border border-border rounded-lg divide-y divide-border bg-card text-xs">
+                {ggufModels.map((model) => {
+                  const isSelected = selectedModel === model.filename;
+                  const isActive = currentModel?.type === "gguf" && currentModel?.name === model.filename;
+                  return (
+                    <div
+                      key={model.filename}
+                      className={`flex items-center gap-3 px-4 py-3 cursor-pointer hover:bg-muted/30 transition-colors ${isSelected ? "bg-primary/5 dark:bg-primary/10" : ""
+                        }`}
+                      onClick={() => !isBusy && setSelectedModel(model.filename)}
+                    >
+                      <div
+                        className={`w-4 h-4 rounded-full border flex items-center justify-center transition-all ${isSelected
+                          ? "border-primary bg-primary text-primary-foreground"
+                          : "border-muted-foreground/30 bg-transparent"
+                          }`}
+                      >
+                        {isSelected && <Check className="w-3 h-3 stroke-[3]" />}
+                      </div>
+                      <span className="font-semibold text-foreground flex-1 truncate">{model.filename}</span>
+                      {isActive && (
+                        <span className="text-[10px] px-1.5 py-0.2 rounded border font-medium bg-green-500/20 text-green-600 dark:text-green-400 border-green-500/30">
+                          Active
+                        </span>
+                      )}
+                      <span className="text-muted-foreground font-medium">
+                        {`${(model.size_bytes / (1024 * 1024)).toFixed(1)} MB`}
+                      </span>
+                    </div>
                   );
                 })}
-              </tbody>
-            </table>
+              </div>
+            )}
           </div>
-        </div>
+        )}
+
+        {/* OpenAI-compatible Server Settings */}
+        {activeTab === "server" && (
+          <div className="space-y-4 max-w-md pb-4">
+            <p className="text-xs text-muted-foreground leading-relaxed">
+              Use any server that exposes the OpenAI <code>/v1/embeddings</code> API, such as Ollama or
+              LM Studio. The vector size is detected by embedding a short probe when you apply.
+            </p>
+            <div className="space-y-2">
+              <Label htmlFor="server-endpoint" className="text-xs font-semibold uppercase tracking-wider text-muted-foreground">
+                Server URL
+              </Label>
+              <Input
+                id="server-endpoint"
+                placeholder={DEFAULT_SERVER_ENDPOINT}
+                value={serverEndpoint}
+                disabled={isBusy}
+                onChange={(e) => setServerEndpoint(e.target.value)}
+                className="text-xs border-border/80"
+              />
+            </div>
+            <div className="space-y-2">
+              <Label htmlFor="server-model" className="text-xs font-semibold uppercase tracking-wider text-muted-foreground">
+                Model Name
+              </Label>
+              <Input
+                id="server-model"
+                placeholder="nomic-embed-text"
+                value={selectedModel}
+                disabled={isBusy}
+                onChange={(e) => setSelectedModel(e.target.value)}
+                className="text-xs border-border/80"
+              />
+            </div>
+            <div className="space-y-2">
+              <Label htmlFor="server-key" className="text-xs font-semibold uppercase tracking-wider text-muted-foreground">
+                API Key (optional)
+              </Label>
+              <Input
+                id="server-key"
+                type="password"
+                value={serverKey}
+                disabled={isBusy}
+                onChange={(e) => setServerKey(e.target.value)}
+                className="text-xs border-border/80"
+              />
+            </div>
+          </div>
+        )}
 
         {/* Remote Model API Key Input */}
         {activeTab === "remote" && (
@@ -836,10 +1051,14 @@ export function EmbeddingModelSetting(): JSX.Element {
                   onClick={() => {
                     if (currentModel) {
                       setSelectedModel(currentModel.name);
-                      setActiveTab(currentModel.type as "local" | "remote");
+                      setActiveTab(currentModel.type as EmbeddingTab);
                       if (currentModel.type === "remote" && currentModel.key) {
                         setOpenaiKey(currentModel.key);
                       }
+                      if (currentModel.type === "server") {
+                        setServerEndpoint(currentModel.endpoint ?? DEFAULT_SERVER_ENDPOINT);
+                        setServerKey(currentModel.key ?? "");
+                      }
                     }
                   }}
                 >
@@ -0,0 +1,102 @@
+import { useEffect, useState } from "react";
+import { toast } from "sonner";
+import { ArchiveRestore, DatabaseBackup, LoaderCircle } from "lucide-react";
+
+import {
+  createKbBackup,
+  listKbBackups,
+  restoreKbBackup,
+  type KbBackup,
+} from "@/api-client/knowledge-base";
+import { Button } from "@/components/ui/button";
+import { Label } from "@/components/ui/label";
+
+function formatSize(bytes: number): string {
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(0)} KB`;
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
+export function KnowledgeBaseBackupSetting(): JSX.Element {
+  const [backups, setBackups] = useState<KbBackup[]>([]);
+  const [loading, setLoading] = useState(true);
+  const [backingUp, setBackingUp] = useState(false);
+
+  const refresh = async () => {
+    try {
+      setBackups(await listKbBackups());
+    } catch (error) {
+      console.error("[KnowledgeBaseBackupSetting] Failed to list backups:", error);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  useEffect(() => {
+    refresh();
+  }, []);
+
+  const handleBackup = async () => {
+    setBackingUp(true);
+    try {
+      await createKbBackup();
+      toast.success("Knowledge base backed up");
+      await refresh();
+    } catch (error) {
+      toast.error(`Backup failed: ${error}`);
+    } finally {
+      setBackingUp(false);
+    }
+  };
+
+  const handleRestore = async (backup: KbBackup) => {
+    try {
+      await restoreKbBackup(backup.name);
+      toast.success("Backup will be restored the next time Depdok starts");
+    } catch (error) {
+      toast.error(`Failed to schedule restore: ${error}`);
+    }
+  };
+
+  return (
+    <div className="space-y-4 w-full">
+      <div className="flex items-start justify-between gap-4">
+        <div className="space-y-0.5">
+          <Label>Knowledge Base Backups</Label>
+          <p className="text-sm text-muted-foreground">
+            Snapshots are taken automatically every 6 hours; the latest 5 are kept.
+          </p>
+        </div>
+        <Button variant="outline" size="sm" onClick={handleBackup} disabled={backingUp} className="cursor-pointer">
+          {backingUp ? <LoaderCircle className="h-3.5 w-3.5 animate-spin" /> : <DatabaseBackup className="h-3.5 w-3.5" />}
+          Back up now
+        </Button>
+      </div>
+
+      {loading ? (
+        <p className="text-xs text-muted-foreground animate-pulse">Loading backups...</p>
+      ) : backups.length === 0 ? (
+        <p className="text-xs text-muted-foreground">No backups yet.</p>
+      ) : (
+        <div className="divide-y rounded-md border">
+          {backups.map((backup) => (
+            <div key={backup.name} className="flex items-center justify-between px-3 py-2 text-xs">
+              <div className="flex flex-col">
+                <span className="font-medium">{new Date(backup.createdAt).toLocaleString()}</span>
+                <span className="text-muted-foreground">{formatSize(backup.size)}</span>
+              </div>
+              <Button
+                variant="ghost"
+                size="sm"
+                className="cursor-pointer"
+                onClick={() => handleRestore(backup)}
+              >
+                <ArchiveRestore className="h-3.5 w-3.5" />
+                Restore on restart
+              </Button>
+            </div>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,59 @@
+import { useEffect, useState } from "react";
+import { toast } from "sonner";
+
+import { getImageExtraction, setImageExtraction } from "@/api-client/knowledge-base";
+import { Label } from "@/components/ui/label";
+import { Switch } from "@/components/ui/switch";
+
+export function KnowledgeBaseImageSetting(): JSX.Element {
+  const [enabled, setEnabled] = useState<boolean | null>(null);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    getImageExtraction()
+      .then(setEnabled)
+      .catch((error) => {
+        console.error("[KnowledgeBaseImageSetting] Failed to read setting:", error);
+        setEnabled(false);
+      });
+  }, []);
+
+  const handleToggle = async (checked: boolean) => {
+    setSaving(true);
+    try {
+      await setImageExtraction(checked);
+      setEnabled(checked);
+      toast.success(
+        checked
+          ? "Image text will be indexed the next time files are indexed"
+          : "Image text indexing disabled"
+      );
+    } catch (error) {
+      toast.error(`Failed to update image indexing: ${error}`);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <div className="flex items-start justify-between gap-4 w-full">
+      <div className="space-y-0.5">
+        <Label htmlFor="kb-image-extraction">Index Text in Images</Label>
+        <p className="text-sm text-muted-foreground">
+          Read screenshots and diagram exports referenced from markdown with on-device OCR (SVG labels are
+          read directly), so searches match the text inside them. Downloads about 12 MB of OCR models once.
+        </p>
+      </div>
+      {enabled === null ? (
+        <div className="h-6 w-10 bg-muted animate-pulse rounded-full shrink-0" />
+      ) : (
+        <Switch
+          id="kb-image-extraction"
+          checked={enabled}
+          onCheckedChange={handleToggle}
+          disabled={saving}
+        />
+      )}
+    </div>
+  );
+}
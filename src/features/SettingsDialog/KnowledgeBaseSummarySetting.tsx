@@ -0,0 +1,59 @@
+import { useEffect, useState } from "react";
+import { toast } from "sonner";
+
+import { getAutoSummaries, setAutoSummaries } from "@/api-client/knowledge-base";
+import { Label } from "@/components/ui/label";
+import { Switch } from "@/components/ui/switch";
+
+export function KnowledgeBaseSummarySetting(): JSX.Element {
+  const [enabled, setEnabled] = useState<boolean | null>(null);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    getAutoSummaries()
+      .then(setEnabled)
+      .catch((error) => {
+        console.error("[KnowledgeBaseSummarySetting] Failed to read setting:", error);
+        setEnabled(false);
+      });
+  }, []);
+
+  const handleToggle = async (checked: boolean) => {
+    setSaving(true);
+    try {
+      await setAutoSummaries(checked);
+      setEnabled(checked);
+      toast.success(
+        checked
+          ? "Documents will be summarized in the background"
+          : "Automatic summaries disabled"
+      );
+    } catch (error) {
+      toast.error(`Failed to update automatic summaries: ${error}`);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <div className="flex items-start justify-between gap-4 w-full">
+      <div className="space-y-0.5">
+        <Label htmlFor="kb-auto-summaries">Summarize Documents</Label>
+        <p className="text-sm text-muted-foreground">
+          Use the chat model to write a short summary and keywords for each indexed document, shown in the
+          knowledge graph and returned with search results. Summaries are refreshed when a document changes.
+        </p>
+      </div>
+      {enabled === null ? (
+        <div className="h-6 w-10 bg-muted animate-pulse rounded-full shrink-0" />
+      ) : (
+        <Switch
+          id="kb-auto-summaries"
+          checked={enabled}
+          onCheckedChange={handleToggle}
+          disabled={saving}
+        />
+      )}
+    </div>
+  );
+}
@@ -1,19 +1,35 @@
 import { useEffect, useState } from "react";
+import { useAtomValue } from "jotai";
 import { writeText } from "@tauri-apps/plugin-clipboard-manager";
 import hljs from "highlight.js";
-import { Copy } from "lucide-react";
+import { Copy, Plus, Trash2 } from "lucide-react";
 import { toast } from "sonner";
 import "highlight.js/styles/github-dark.css";
 
-import { getMcpServerPaths } from "@/api-client/mcp";
+import { getMcpServerPaths, McpConfigScope, removeMcpClientConfig, writeMcpClientConfig } from "@/api-client/mcp";
+import { workspaceRootAtom } from "@/features/FileExplorer/store";
 import { Button } from "@/components/ui/button";
 import { Label } from "@/components/ui/label";
 import { Input } from "@/components/ui/input";
 
+type AgentId = 'claude' | 'claudecode' | 'copilot' | 'gemini' | 'codex' | 'opencode';
+
+/** Config scopes the app can write for each agent; Codex only reads TOML. */
+const AGENT_SCOPES: Record<AgentId, McpConfigScope[]> = {
+  claude: ["user"],
+  claudecode: ["user", "workspace"],
+  copilot: ["user", "workspace"],
+  gemini: ["user", "workspace"],
+  codex: [],
+  opencode: ["workspace"],
+};
+
 export function McpServerPathSetting(): JSX.Element {
   const [paths, setPaths] = useState<string[]>([]);
   const [loading, setLoading] = useState(true);
-  const [activeTab, setActiveTab] = useState<'claude' | 'claudecode' | 'copilot' | 'gemini' | 'codex' | 'opencode'>('claude');
+  const [activeTab, setActiveTab] = useState<AgentId>('claude');
+  const [scope, setScope] = useState<McpConfigScope>("user");
+  const workspaceRoot = useAtomValue(workspaceRootAtom);
 
   useEffect(() => {
     let cancelled = false;
@@ -47,10 +63,7 @@ export function McpServerPathSetting(): JSX.Element {
   const isWindows = typeof navigator !== "undefined" && navigator.userAgent.toLowerCase().includes("win");
   const serverPath = paths[0] || (isWindows ? "C:\\absolute\\path\\to\\depdok-mcp-server.exe" : "/absolute/path/to/depdok-mcp-server");
 
-  const getAgentConfig = (
-    tab: 'claude' | 'claudecode' | 'copilot' | 'gemini' | 'codex' | 'opencode',
-    pathVal: string
-  ) => {
+  const getAgentConfig = (tab: AgentId, pathVal: string) => {
     switch (tab) {
       case 'claude':
         return {
@@ -89,7 +102,7 @@ export function McpServerPathSetting(): JSX.Element {
         };
       case 'copilot':
         return {
-          path: ".vscode/mcp.json (VS Code) or ~/.copilot/mcp-config.json (CLI)",
+          path: ".vscode/mcp.json (VS Code); the CLI's ~/.copilot/mcp-config.json uses \"mcpServers\" instead of \"servers\"",
           json: JSON.stringify(
             {
               servers: {
@@ -105,7 +118,7 @@ export function McpServerPathSetting(): JSX.Element {
         };
       case 'gemini':
         return {
-          path: "~/.gemini/settings.json (CLI) or .vscode/mcp.json (VS Code)",
+          path: "~/.gemini/settings.json (User global) or .gemini/settings.json (Project root)",
           json: JSON.stringify(
             {
               mcpServers: {
@@ -121,20 +134,13 @@ export function McpServerPathSetting(): JSX.Element {
         };
       case 'codex':
         return {
-          path: ".vscode/mcp.json or codex-config.json",
-          json: JSON.stringify(
-            {
-              name: "depdok",
-              transport: "stdio",
-              command: pathVal,
-              args: [],
-              env: {
-                DEPDOK_MCP_MODE: "stdio"
-              }
-            },
-            null,
-            2
-          )
+          path: "~/.codex/config.toml",
+          language: "ini",
+          json: [
+            "[mcp_servers.depdok]",
+            `command = ${JSON.stringify(pathVal)}`,
+            "args = []",
+          ].join("\n")
         };
       case 'opencode':
         return {
@@ -168,6 +174,44 @@ export function McpServerPathSetting(): JSX.Element {
     }
   };
 
+  const scopes = AGENT_SCOPES[activeTab];
+  const activeScope = scopes.includes(scope) ? scope : scopes[0];
+  const needsWorkspace = activeScope === "workspace" && !workspaceRoot;
+
+  const addToConfig = async () => {
+    if (!paths[0]) {
+      toast.error("MCP server binary not found");
+      return;
+    }
+    try {
+      const change = await writeMcpClientConfig(activeTab, activeScope, workspaceRoot, paths[0]);
+      if (!change.changed) {
+        toast.success(`Already configured in ${change.path}`);
+      } else if (change.backupPath) {
+        toast.success(`Added to ${change.path} (backup: ${change.backupPath})`);
+      } else {
+        toast.success(`Created ${change.path}`);
+      }
+    } catch (error) {
+      console.error("[McpServerPathSetting] Failed to write config:", error);
+      toast.error(String(error));
+    }
+  };
+
+  const removeFromConfig = async () => {
+    try {
+      const change = await removeMcpClientConfig(activeTab, activeScope, workspaceRoot);
+      if (!change.changed) {
+        toast.success(`Not configured in ${change.path}`);
+      } else {
+        toast.success(`Removed from ${change.path} (backup: ${change.backupPath})`);
+      }
+    } catch (error) {
+      console.error("[McpServerPathSetting] Failed to remove config:", error);
+      toast.error(String(error));
+    }
+  };
+
   const agents = [
     { id: "claude", name: "Claude Desktop", icon: "/ai-icons/claude-color.svg" },
     { id: "claudecode", name: "Claude Code", icon: "/ai-icons/claudecode-color.svg" },
@@ -211,7 +255,54 @@ export function McpServerPathSetting(): JSX.Element {
           {/* Config file path and JSON code block */}
           <div className="space-y-3 w-full">
             <div className="flex flex-col gap-1 w-full">
-              <Label className="text-xs font-medium text-muted-foreground">Config File Location</Label>
+              <div className="flex items-center justify-between">
+                <Label className="text-xs font-medium text-muted-foreground">Config File Location</Label>
+                {scopes.length > 0 && (
+                  <div className="flex items-center gap-1">
+                    {scopes.length > 1 && (
+                      <div className="flex bg-muted p-0.5 rounded-md text-muted-foreground">
+                        {scopes.map((option) => (
+                          <button
+                            key={option}
+                            type="button"
+                            className={`rounded px-2 py-0.5 text-xs capitalize transition-all cursor-pointer ${activeScope === option
+                              ? "bg-background text-foreground shadow-sm font-semibold"
+                              : "hover:text-foreground"
+                              }`}
+                            onClick={() => setScope(option)}
+                          >
+                            {option}
+                          </button>
+                        ))}
+                      </div>
+                    )}
+                    <Button
+                      type="button"
+                      variant="ghost"
+                      size="sm"
+                      className="h-6 px-2 text-xs cursor-pointer"
+                      disabled={needsWorkspace}
+                      title={needsWorkspace ? "Open a workspace to configure it" : undefined}
+                      onClick={addToConfig}
+                    >
+                      <Plus className="h-3 w-3" />
+                      Add to {activeScope} config
+                    </Button>
+                    <Button
+                      type="button"
+                      variant="ghost"
+                      size="sm"
+                      className="h-6 px-2 text-xs cursor-pointer"
+                      disabled={needsWorkspace}
+                      title={needsWorkspace ? "Open a workspace to configure it" : undefined}
+                      onClick={removeFromConfig}
+                    >
+                      <Trash2 className="h-3 w-3" />
+                      Remove
+                    </Button>
+                  </div>
+                )}
+              </div>
               <div className="relative">
                 <Input readOnly value={currentConfig.path} className="text-xs pr-10" />
                 <Button
@@ -234,7 +325,7 @@ export function McpServerPathSetting(): JSX.Element {
                   variant="ghost"
                   size="icon"
                   className="h-6 w-6 text-[10px] text-zinc-400 hover:text-white hover:bg-white/10 px-2 cursor-pointer"
-                  onClick={() => copyToClipboard(currentConfig.json, "Copied JSON configuration")}
+                  onClick={() => copyToClipboard(currentConfig.json, "Copied configuration")}
                 >
                   <Copy className="h-3 w-3" />
 
@@ -242,9 +333,9 @@ export function McpServerPathSetting(): JSX.Element {
               </div>
               <pre className="overflow-x-auto py-2 px-3 w-full whitespace-pre-wrap font-mono">
                 <code
-                  className="hljs language-json bg-transparent p-0 block"
+                  className={`hljs language-${currentConfig.language ?? "json"} bg-transparent p-0 block`}
                   dangerouslySetInnerHTML={{
-                    __html: hljs.highlight(currentConfig.json, { language: "json" }).value,
+                    __html: hljs.highlight(currentConfig.json, { language: currentConfig.language ?? "json" }).value,
                   }}
                 />
               </pre>
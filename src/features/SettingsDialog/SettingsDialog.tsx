@@ -16,6 +16,9 @@ import { autoSaveEnabledAtom, themeAtom } from "@/stores/SettingsStore";
 
 import { AssetsFolderSetting } from "./AssetsFolderSetting";
 import { EmbeddingModelSetting } from "./EmbeddingModelSetting";
+import { KnowledgeBaseBackupSetting } from "./KnowledgeBaseBackupSetting";
+import { KnowledgeBaseImageSetting } from "./KnowledgeBaseImageSetting";
+import { KnowledgeBaseSummarySetting } from "./KnowledgeBaseSummarySetting";
 import { McpServerPathSetting } from "./McpServerPathSetting";
 import { MonacoThemeSetting } from "./MonacoThemeSetting";
 import { PlantUmlServerSetting } from "./PlantUmlServerSetting";
@@ -213,7 +216,14 @@ export function SettingsDialog({ open, onOpenChange, defaultTab }: SettingsDialo
             )}
 
             {activeTab === "embeddings" && (
-              <EmbeddingModelSetting />
+              <>
+                <EmbeddingModelSetting />
+                <div className="px-8 pb-8 space-y-8">
+                  <KnowledgeBaseImageSetting />
+                  <KnowledgeBaseSummarySetting />
+                  <KnowledgeBaseBackupSetting />
+                </div>
+              </>
             )}
 
             {activeTab === "mcp" && (